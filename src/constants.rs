@@ -4,14 +4,23 @@ pub struct Constants;
 pub type EthAddress = [u8; 20];
 
 impl Constants {
+    // Program version
+    pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
     // Limits
     pub const MAX_PROPOSERS: usize = 32;
     pub const MAX_EXECUTORS: usize = 32;
     pub const MAX_TOKENS: usize = 32;
+    pub const MAX_SWEEP_PROPOSALS: usize = 8; // keeps `SweepExpired` within compute limits
+    pub const MAX_PROPOSER_ACTIVITY: usize = 16; // req_ids kept in each proposer index
+    pub const MAX_RELAYERS: usize = 8;
+    pub const MAX_RATE_LIMITED_PROPOSERS: usize = 32; // tracked by the propose rate limit, the earliest is dropped past this
+    pub const MAX_DECIMALS: u8 = 12; // `AddToken` needs `allow_high_decimals` above this
 
     // Zero address and placeholder
     pub const ETH_ZERO_ADDRESS: EthAddress = [0; 20];
     pub const EXECUTED_PLACEHOLDER: Pubkey = Pubkey::new_from_array([0xed; 32]);
+    pub const RENOUNCED_ADMIN: Pubkey = Pubkey::new_from_array([0; 32]); // admin after `FinalizeAdminRenounce`
 
     // Contract signer
     pub const CONTRACT_SIGNER: &'static [u8] = b"contract-signer";
@@ -22,8 +31,24 @@ impl Constants {
     pub const PROPOSE_PERIOD: u64 = 48 * 60 * 60;
     pub const EXPIRE_PERIOD: u64 = 72 * 60 * 60;
     pub const EXPIRE_EXTRA_PERIOD: u64 = 96 * 60 * 60;
+    pub const VAULT_ROTATION_GRACE_PERIOD: u64 = Self::EXPIRE_EXTRA_PERIOD; // outlives every in-flight proposal
+    pub const EXECUTION_COMMIT_SLOTS: u64 = 150; // about a minute of exclusivity after `CommitExecution`
+    pub const EXECUTORS_ROTATION_MIN_DELAY: u64 = 36 * 60 * 60; // default, stored in `BasicStorage`
+    pub const EXECUTORS_ROTATION_MAX_DELAY: u64 = 120 * 60 * 60; // default, stored in `BasicStorage`
+    pub const EXECUTORS_ROTATION_MIN_DELAY_FLOOR: u64 = 60 * 60;
     pub const ETH_SIGN_HEADER: &'static [u8] = b"\x19Ethereum Signed Message:\n";
 
+    // Compute budget estimate for signature-heavy instructions, for relayers to size `ComputeBudget`
+    pub const CU_BASE: u64 = 40_000; // account checks, storage reads and the final CPI
+    pub const CU_PER_SIGNATURE: u64 = 30_000; // `secp256k1_recover` alone costs 25_000
+    pub const CU_SOFT_CAP: u64 = 1_400_000; // the per-transaction maximum
+
+    // Timelocked admin parameter changes
+    pub const PARAM_CHANGE_DELAY: u64 = 48 * 60 * 60;
+    pub const PARAM_MIN_PROPOSE_INTERVAL: u8 = 0; // value: borsh `u64`
+    pub const PARAM_ADMIN: u8 = 1; // value: borsh `Pubkey`
+    pub const ADMIN_RENOUNCE_DELAY: u64 = 7 * 24 * 60 * 60; // between `InitiateAdminRenounce` and its finalization
+
     // Data account storage location
     pub const BASIC_STORAGE: &'static [u8] = b"basic-storage";
     pub const PREFIX_EXECUTORS: &'static [u8] = b"executors";
@@ -31,6 +56,20 @@ impl Constants {
     pub const PREFIX_BURN: &'static [u8] = b"burn";
     pub const PREFIX_LOCK: &'static [u8] = b"lock";
     pub const PREFIX_UNLOCK: &'static [u8] = b"unlock";
+    pub const PREFIX_PENDING_CHANGE: &'static [u8] = b"pending-change";
+    pub const PREFIX_PROPOSER_INDEX: &'static [u8] = b"proposer-index";
+    pub const PREFIX_RENT_VAULT: &'static [u8] = b"rent-vault"; // system-owned, holds lamports only
+    pub const PREFIX_EXECUTION_COMMIT: &'static [u8] = b"execution-commit";
+
+    // Proposal header, at fixed offsets for `getProgramAccounts` memcmp filters
+    pub const PROPOSAL_OFFSET_DISCRIMINATOR: usize = 0; // `ProposalKind::discriminator`
+    pub const PROPOSAL_OFFSET_STATUS: usize = 1;
+    pub const PROPOSAL_OFFSET_TOKEN_INDEX: usize = 2;
+    pub const PROPOSAL_OFFSET_CREATED_TIME: usize = 3; // u64 LE
+    pub const PROPOSAL_OFFSET_PARTY: usize = 11; // recipient for mint/unlock, proposer for burn/lock
+    pub const PROPOSAL_OFFSET_AMOUNT: usize = 43; // u64 LE, in the token's smallest unit
+    pub const PROPOSAL_STATUS_PROPOSED: u8 = 0;
+    pub const PROPOSAL_STATUS_EXECUTED: u8 = 1;
 
     // Data account size
     pub const SIZE_LENGTH: usize = 4; // actual length for the data account (not capacity)
@@ -39,8 +78,36 @@ impl Constants {
         + (4 + Self::MAX_TOKENS * (1 + 32))
         + (4 + Self::MAX_TOKENS * (1 + 32))
         + (4 + Self::MAX_TOKENS * (1 + 1))
-        + (4 + Self::MAX_TOKENS * (1 + 8));
+        + (4 + Self::MAX_TOKENS * (1 + 8))
+        + 8 + (4 + Self::MAX_RATE_LIMITED_PROPOSERS * (32 + 8))
+        + 1
+        + (4 + Self::MAX_TOKENS * (1 + 2))
+        + (4 + Self::MAX_TOKENS * (1 + 32 + 8))
+        + (4 + Self::MAX_TOKENS * (1 + 20))
+        + 8 + 8
+        + (4 + Self::MAX_TOKENS * (1 + 32))
+        + (4 + 32 * Self::MAX_RELAYERS)
+        + (4 + Self::MAX_TOKENS * (1 + 32))
+        + (4 + Self::MAX_TOKENS * (1 + 8))
+        + 8
+        + 8
+        + 1
+        + 8;
     pub const SIZE_EXECUTORS_STORAGE: usize =
-        8 + 8 + 8 + 8 + (4 + 20 * Self::MAX_EXECUTORS);
+        8 + 8 + 8 + 8 + (4 + 20 * Self::MAX_EXECUTORS) + (4 + 32 * Self::MAX_EXECUTORS);
     pub const SIZE_ADDRESS_STORAGE: usize = 32;
+    pub const SIZE_PROPOSER_ACTIVITY: usize = 32 + (4 + 32 * Self::MAX_PROPOSER_ACTIVITY);
+    pub const SIZE_EXECUTION_COMMIT: usize = 32 + 8;
+    pub const SIZE_PROPOSAL_HEADER: usize = 1 + 1 + 1 + 8 + 32 + 8; // followed by the length-prefixed proposal
+    pub const SIZE_PENDING_CHANGE: usize = 1 + (4 + 32) + 8; // values are at most a pubkey
+
+    /// Account length of basic storage allowing `max_proposers`, which grows it past `MAX_PROPOSERS`
+    pub const fn basic_storage_length(max_proposers: u8) -> usize {
+        let extra_proposers = (max_proposers as usize).saturating_sub(Self::MAX_PROPOSERS);
+        Self::SIZE_LENGTH + Self::SIZE_BASIC_STORAGE + extra_proposers * 32
+    }
+
+    pub const fn expected_compute_units(signature_count: usize) -> u64 {
+        Self::CU_BASE + Self::CU_PER_SIGNATURE * signature_count as u64
+    }
 }
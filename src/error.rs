@@ -14,7 +14,7 @@ impl From<DataAccountError> for ProgramError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum FreeTunnelError {
     // Solana-only account/token checks
     InvalidSystemProgram = 0,
@@ -56,24 +56,68 @@ pub enum FreeTunnelError {
     NonExecutors = 31,
     SignerCannotBeZeroAddress = 32,
     InvalidSignature = 34,
-    ActiveSinceShouldAfter36h = 35,
-    ActiveSinceShouldWithin5d = 36,
+    ActiveSinceShouldAfter36h = 35, // named after the default `rotation_min_delay`
+    ActiveSinceShouldWithin5d = 36, // named after the default `rotation_max_delay`
     FailedToOverwriteExistingExecutors = 37,
+    InvalidExeIndex = 38,
+    ExecutorsNotSorted = 39,
 
     LockedBalanceMustBeZero = 40,
     VaultBalanceMustBeZero = 41,
     LockedBalanceInsufficient = 42,
     RefundAccountNotWritable = 43,
+    InvalidRefundAccount = 44,
+    InvalidAssociatedTokenProgram = 45,
+    VaultBalanceInsufficient = 46,
+    TokenAccountFrozen = 47,
+    PendingProposalsExist = 48,
+    InvalidNewVault = 49,
 
     // Mint/Lock (aligned with Aptos)
-    ReqIdOccupied = 50,
+    ReqIdOccupied = 50, // propose on a req_id whose proposal PDA already holds data
     NotLockMint = 51,
     NotBurnUnlock = 52,
     NotBurnMint = 53,
     InvalidProposer = 54,
     InvalidRecipient = 55,
     WaitUntilExpired = 56,
-    ReqIdExecuted = 57,
+    ReqIdExecuted = 57, // execute/cancel on a proposal already executed
+    ProposalNotStuck = 58,
+    ProposeTooFrequent = 59,
+    MaxProposersTooLow = 60,
+    SweepLimitExceeded = 61,
+    ReqIdNotFound = 62, // execute/cancel on a req_id that was never proposed (or already cancelled)
+    InvalidParameterId = 63,
+    ParamChangeNotEffective = 64,
+    InvalidRotationWindow = 65,
+    TokenIndexPreviouslyUsed = 66, // re-adding a token index for a different mint needs `confirm_reuse`
+    TooManySignaturesForBudget = 67, // see `Constants::expected_compute_units`
+    AdminMismatch = 68, // replaces `RequireAdminSigner` for a wrong admin key
+    AdminSignatureMissing = 69, // replaces `RequireAdminSigner` for the admin key without its signature
+    NotAProposer = 70, // replaces `RequireProposerSigner` for a key that is not a proposer
+    ProposerSignatureMissing = 71, // replaces `RequireProposerSigner` for a proposer without its signature
+    CriticalAccountNotClosable = 72, // closing an account that holds `BasicStorage` or `ExecutorsInfo`
+    AdminRenounced = 73, // admin-only operations after `FinalizeAdminRenounce`
+    TokenAccountOwnerMismatch = 74, // deposit from a token account not owned by the proposer
+    InvariantViolated = 75, // only with the `invariants` feature
+    AlreadyRelayer = 76,
+    NotExistingRelayer = 77,
+    NotARelayer = 78, // execute signed by a key missing from a non-empty `relayers` list
+    RelayerSignatureMissing = 79, // execute without a relayer signer while `relayers` is non-empty
+    InvalidCommitment = 80, // `CommitExecution` commitment is not `keccak256(relayer || req_id)`
+    ExecutionCommitActive = 81, // recommitting or closing before the exclusive window is over
+    NotCommittingRelayer = 82, // executing or closing as another relayer within the window
+    TokenProgramMismatch = 83, // token program other than the one the token was added with
+    DecimalsTooHigh = 84, // `AddToken` of a mint above `Constants::MAX_DECIMALS` without `allow_high_decimals`
+    RefundBelowRentExempt = 85, // closing into a fresh refund account the rent would not keep alive
+    PinnedExeIndexMismatch = 86, // executing a pinned proposal with signatures of another executors group
+    AdminRenouncePending = 87, // `InitiateAdminRenounce` while one is already pending
+    AdminRenounceNotPending = 88, // cancelling or finalizing without a pending renounce
+    AdminRenounceNotReady = 89, // finalizing before `Constants::ADMIN_RENOUNCE_DELAY` has elapsed
+    NewAdminIsRenounced = 91, // handing the admin to `Constants::RENOUNCED_ADMIN` outside `FinalizeAdminRenounce`
+    BridgePaused = 92, // proposing while the admin has paused the bridge with `SetPaused`
+    DecommissionNotReady = 93, // `DecommissionWithdraw` unless paused for `Constants::EXPIRE_EXTRA_PERIOD`
+    DecommissionQuorumIncomplete = 94, // `DecommissionWithdraw` not signed by every executor of the group
 }
 
 impl From<FreeTunnelError> for ProgramError {
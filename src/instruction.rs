@@ -1,46 +1,55 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-use crate::{constants::EthAddress, logic::req_helpers::ReqId};
+use crate::{constants::EthAddress, logic::req_helpers::ReqId, state::ProposalKind};
 
+/// For every instruction carrying `signatures` and `executors`, relayers must list the signing
+/// executors in strictly increasing address order (byte-wise compare of the 20-byte addresses),
+/// with each signature at the same position as its executor. Unsorted lists fail with
+/// `ExecutorsNotSorted`, repeated addresses with `DuplicatedExecutors`.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum FreeTunnelInstruction {
     // The admin(deployer) must call this init function first
     /// [0]
     /// 0. system_program: system program account, `11111111111111111111111111111111`
-    /// 1. account_admin: the admin account, should be signer and payer
+    /// 1. account_admin: (signer) the admin account, also the payer unless `account_payer` is given
     /// 2. data_account_basic_storage: data account for storing basic storage (includes tokens, decimals, locked_balance, and proposers)
     /// 3. data_account_executors: data account for storing executors at index
+    /// 4. account_payer: (optional, signer) pays the rent of both data accounts
+    /// Combines `InitializeBasicStorage` and `InitializeExecutors`
     Initialize {
         is_mint_contract: bool,
         executors: Vec<EthAddress>,
         threshold: u64,
-        exe_index: u64,
+        exe_index: u64, // must be 0 for a fresh deployment
+        max_proposers: u8, // 0 for the default `Constants::MAX_PROPOSERS`
     },
 
     /// [1] Transfer admin
-    /// 0. account_admin
+    /// 0. account_admin: (signer)
     /// 1. data_account_basic_storage
     TransferAdmin { new_admin: Pubkey },
 
     /// [2]
-    /// 0. account_admin
+    /// 0. account_admin: (signer)
     /// 1. data_account_basic_storage
     AddProposer { new_proposer: Pubkey },
 
     /// [3]
-    /// 0. account_admin
+    /// 0. account_admin: (signer)
     /// 1. data_account_basic_storage
     RemoveProposer { proposer: Pubkey },
 
     /// [4]
     /// 0. system_program: system program account, `11111111111111111111111111111111`
-    /// 1. account_payer
+    /// 1. account_payer: (signer)
     /// 2. data_account_basic_storage
     /// 3. data_account_executors: data account for storing executors at `index`
     /// 4. data_account_new_executors: data account for storing executors at `index + 1`
+    /// 5. sysvar_instructions: (optional) instructions sysvar, to verify signatures via `secp256k1_program` instructions in the same transaction
     UpdateExecutors {
         new_executors: Vec<EthAddress>,
+        new_ed25519_executors: Vec<Pubkey>,
         threshold: u64,
         active_since: u64,
         signatures: Vec<[u8; 64]>,
@@ -51,30 +60,37 @@ pub enum FreeTunnelInstruction {
     /// [5]
     /// 0. system_program: system program account
     /// 1. token_program: token program account
-    /// 2. account_admin
+    /// 2. account_admin: (signer)
     /// 3. token_account_contract: contract ATA for this mint
     /// 4. account_contract_signer: contract signer PDA
     /// 5. data_account_basic_storage
     /// 6. token_mint: the token mint account
     /// 7. rent_sysvar: rent sysvar account
+    /// 8. associated_token_program: associated token account program
     AddToken {
         token_index: u8,
+        peer_address: EthAddress, // token address on the EVM side, for cross-checking the index
+        is_token_2022: bool, // must match `token_program`
+        confirm_reuse: bool, // required when the index was last used by a different mint
+        allow_high_decimals: bool, // required above `Constants::MAX_DECIMALS`, see `ReqId::max_raw_amount`
     },
 
     /// [6]
-    /// 0. account_admin
+    /// 0. account_admin: (signer)
     /// 1. data_account_basic_storage
     /// 2. token_account_contract
     RemoveToken { token_index: u8 },
 
-    /// [7]
+    /// [7] With `pinned_exe_index`, only that executors group can execute it
     /// 0. system_program
-    /// 1. account_proposer: the proposer account, should be signer and payer
+    /// 1. account_proposer: (signer) the proposer account, should be signer and payer
     /// 2. data_account_basic_storage
     /// 3. data_account_proposed_mint: data account for storing `ProposedMint` (recipient)
-    ProposeMint { req_id: ReqId, recipient: Pubkey },
+    /// 4. data_account_proposer_index: (optional) PDA of `["proposer-index", proposer]`, records `req_id` in it
+    /// 5. account_rent_vault: (optional) PDA of `["rent-vault"]`, pays the proposal rent when funded enough. Can follow or replace `data_account_proposer_index`
+    ProposeMint { req_id: ReqId, recipient: Pubkey, pinned_exe_index: Option<u64> },
 
-    /// [8]
+    /// [8] Authorized by the executors' signatures. Permissionless unless `relayers` is set
     /// 0. token_program: token program account, should be `TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA` on mainnet
     /// 1. account_contract_signer: contract signer that can sign for the token transfer
     /// 2. token_account_recipient: token account for the recipient, should be different for each token
@@ -83,6 +99,12 @@ pub enum FreeTunnelInstruction {
     /// 5. data_account_executors
     /// 6. token_mint: token mint account (token contract address)
     /// 7. account_multisig_owner: multisig owner account
+    /// 8. data_account_execution_commit: (optional) PDA of `["execution-commit", req_id]`, see `CommitExecution`.
+    ///    Told apart by its address, and need not exist. The commitment is only checked when passed
+    /// 9. account_relayer: (optional, signer) one of `relayers`, required once that list is non-empty. Told apart by
+    ///    its signature, so it can be omitted in permissionless mode
+    /// 10. sysvar_instructions: (optional) instructions sysvar, to verify signatures via `secp256k1_program` instructions in the same transaction
+    /// 11. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it. Can follow or replace `sysvar_instructions`
     ExecuteMint {
         req_id: ReqId,
         signatures: Vec<[u8; 64]>,
@@ -90,23 +112,27 @@ pub enum FreeTunnelInstruction {
         exe_index: u64,
     },
 
-    /// [9]
+    /// [9] Permissionless once expired
     /// 0. data_account_basic_storage
     /// 1. data_account_proposed_mint
-    /// 2. account_refund: refund account for closing PDA
+    /// 2. account_refund: the original mint proposer, or the rent vault if it paid the rent, receives the rent of the closed PDA
+    /// 3. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it
     CancelMint { req_id: ReqId },
 
-    /// [10]
+    /// [10] Open to any token holder, the proposer list is not checked. With `pinned_exe_index`,
+    /// only that executors group can execute it
     /// 0. system_program
     /// 1. token_program
-    /// 2. account_proposer: the proposer account, should be signer and payer
+    /// 2. account_proposer: (signer) the proposer account, should be signer and payer
     /// 3. token_account_contract: token account for this contract, should be different for each token
-    /// 4. token_account_proposer: token account for the proposer, should be different for each token
+    /// 4. token_account_proposer: token account owned by the proposer, recorded for refunds on cancel
     /// 5. data_account_basic_storage
     /// 6. data_account_proposed_burn: data account for storing `ProposedBurn` (recipient)
-    ProposeBurn { req_id: ReqId },
+    /// 7. data_account_proposer_index: (optional) PDA of `["proposer-index", proposer]`, records `req_id` in it
+    /// 8. account_rent_vault: (optional) PDA of `["rent-vault"]`, pays the proposal rent when funded enough. Can follow or replace `data_account_proposer_index`
+    ProposeBurn { req_id: ReqId, pinned_exe_index: Option<u64> },
 
-    /// [11]
+    /// [11] Authorized by the executors' signatures. Permissionless unless `relayers` is set
     /// 0. token_program
     /// 1. account_contract_signer: contract signer that can sign for the token transfer
     /// 2. token_account_contract
@@ -114,6 +140,12 @@ pub enum FreeTunnelInstruction {
     /// 4. data_account_proposed_burn
     /// 5. data_account_executors
     /// 6. token_mint
+    /// 7. data_account_execution_commit: (optional) PDA of `["execution-commit", req_id]`, see `CommitExecution`.
+    ///    Told apart by its address, and need not exist. The commitment is only checked when passed
+    /// 8. account_relayer: (optional, signer) one of `relayers`, required once that list is non-empty. Told apart by
+    ///    its signature, so it can be omitted in permissionless mode
+    /// 9. sysvar_instructions: (optional) instructions sysvar, to verify signatures via `secp256k1_program` instructions in the same transaction
+    /// 10. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it. Can follow or replace `sysvar_instructions`
     ExecuteBurn {
         req_id: ReqId,
         signatures: Vec<[u8; 64]>,
@@ -121,30 +153,40 @@ pub enum FreeTunnelInstruction {
         exe_index: u64,
     },
 
-    /// [12]
+    /// [12] Permissionless once expired
     /// 0. token_program
     /// 1. account_contract_signer
     /// 2. token_account_contract
-    /// 3. token_account_proposer
+    /// 3. token_account_proposer: the token account recorded by `ProposeBurn` (the proposer's ATA for older proposals)
     /// 4. data_account_basic_storage
     /// 5. data_account_proposed_burn
-    /// 6. account_refund: refund account for closing PDA
+    /// 6. account_refund: the original burn proposer, or the rent vault if it paid the rent, receives the rent of the closed PDA
+    /// 7. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it
     CancelBurn { req_id: ReqId },
 
-    /// [13]
+    /// [13] Only listed proposers can lock, or any signer once `SetOpenLock` opened it. With
+    /// `pinned_exe_index`, only that executors group can execute it
     /// 0. system_program
     /// 1. token_program
-    /// 2. account_proposer: the proposer account, should be signer and payer
+    /// 2. account_proposer: (signer) the proposer account, should be signer and payer
     /// 3. token_account_contract
-    /// 4. token_account_proposer
+    /// 4. token_account_proposer: the proposer's ATA for the token, recorded for refunds on cancel
     /// 5. data_account_basic_storage
     /// 6. data_account_proposed_lock
-    ProposeLock { req_id: ReqId },
+    /// 7. data_account_proposer_index: (optional) PDA of `["proposer-index", proposer]`, records `req_id` in it
+    /// 8. account_rent_vault: (optional) PDA of `["rent-vault"]`, pays the proposal rent when funded enough. Can follow or replace `data_account_proposer_index`
+    ProposeLock { req_id: ReqId, pinned_exe_index: Option<u64> },
 
-    /// [14]
+    /// [14] Authorized by the executors' signatures. Permissionless unless `relayers` is set
     /// 0. data_account_basic_storage
     /// 1. data_account_proposed_lock
     /// 2. data_account_executors
+    /// 3. data_account_execution_commit: (optional) PDA of `["execution-commit", req_id]`, see `CommitExecution`.
+    ///    Told apart by its address, and need not exist. The commitment is only checked when passed
+    /// 4. account_relayer: (optional, signer) one of `relayers`, required once that list is non-empty. Told apart by
+    ///    its signature, so it can be omitted in permissionless mode
+    /// 5. sysvar_instructions: (optional) instructions sysvar, to verify signatures via `secp256k1_program` instructions in the same transaction
+    /// 6. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it. Can follow or replace `sysvar_instructions`
     ExecuteLock {
         req_id: ReqId,
         signatures: Vec<[u8; 64]>,
@@ -152,24 +194,27 @@ pub enum FreeTunnelInstruction {
         exe_index: u64,
     },
 
-    /// [15]
+    /// [15] Permissionless once expired
     /// 0. token_program
     /// 1. account_contract_signer
     /// 2. token_account_contract
-    /// 3. token_account_proposer
+    /// 3. token_account_proposer: the token account recorded by `ProposeLock` (the proposer's ATA for older proposals)
     /// 4. data_account_basic_storage
     /// 5. data_account_proposed_lock
-    /// 6. account_refund: refund account for closing PDA
+    /// 6. account_refund: the original lock proposer, or the rent vault if it paid the rent, receives the rent of the closed PDA
+    /// 7. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it
     CancelLock { req_id: ReqId },
 
-    /// [16]
+    /// [16] With `pinned_exe_index`, only that executors group can execute it
     /// 0. system_program
-    /// 1. account_proposer: the proposer account, should be signer and payer
+    /// 1. account_proposer: (signer) the proposer account, should be signer and payer
     /// 2. data_account_basic_storage
     /// 3. data_account_proposed_unlock
-    ProposeUnlock { req_id: ReqId, recipient: Pubkey },
+    /// 4. data_account_proposer_index: (optional) PDA of `["proposer-index", proposer]`, records `req_id` in it
+    /// 5. account_rent_vault: (optional) PDA of `["rent-vault"]`, pays the proposal rent when funded enough. Can follow or replace `data_account_proposer_index`
+    ProposeUnlock { req_id: ReqId, recipient: Pubkey, pinned_exe_index: Option<u64> },
 
-    /// [17]
+    /// [17] Authorized by the executors' signatures. Permissionless unless `relayers` is set
     /// 0. token_program
     /// 1. account_contract_signer
     /// 2. token_account_contract
@@ -177,6 +222,14 @@ pub enum FreeTunnelInstruction {
     /// 4. data_account_basic_storage
     /// 5. data_account_proposed_unlock
     /// 6. data_account_executors
+    /// 7. data_account_execution_commit: (optional) PDA of `["execution-commit", req_id]`, see `CommitExecution`.
+    ///    Told apart by its address, and need not exist. The commitment is only checked when passed
+    /// 8. account_relayer: (optional, signer) one of `relayers`, required once that list is non-empty. Told apart by
+    ///    its signature, so it can be omitted in permissionless mode
+    /// 9. sysvar_instructions: (optional) instructions sysvar, to verify signatures via `secp256k1_program` instructions in the same transaction
+    /// 10. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it. Can follow or replace `sysvar_instructions`
+    /// 11. token_account_previous_vault: (optional) vault replaced by `RotateVault`, still in its grace period. Drained
+    ///    first, the current vault pays the rest. Told apart by its address, so it can come in any trailing position
     ExecuteUnlock {
         req_id: ReqId,
         signatures: Vec<[u8; 64]>,
@@ -184,27 +237,380 @@ pub enum FreeTunnelInstruction {
         exe_index: u64,
     },
 
-    /// [18]
+    /// [18] Permissionless once expired
     /// 0. data_account_basic_storage
     /// 1. data_account_proposed_unlock
-    /// 2. account_refund: refund account for closing PDA
+    /// 2. account_refund: the original unlock proposer, or the rent vault if it paid the rent, receives the rent of the closed PDA
+    /// 3. data_account_proposer_index: (optional) proposer index holding `req_id`, pruned from it
     CancelUnlock { req_id: ReqId },
+
+    /// [19] Force-close a proposal whose data cannot be decoded, logging its raw bytes. The token
+    /// index and amount in its header are released like a cancel; proposals created before the
+    /// header leave the pending counters behind
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    /// 2. data_account_proposal: proposal data account of `kind` for `req_id`
+    /// 3. account_refund: refund account for closing PDA
+    ClaimStuckProposal { req_id: ReqId, kind: ProposalKind },
+
+    /// [20] Set the minimum interval (seconds) between two proposals of the same proposer, 0 to disable
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    SetMinProposeInterval { interval: u64 },
+
+    /// [21] Dry-run of the execute signature check, writes nothing. Returns the borsh-encoded
+    /// `SignaturesView` via return data, which starts with the little-endian `u64` bitmask of
+    /// the valid signatures; intended for `simulateTransaction`. At most `Constants::MAX_EXECUTORS`
+    /// signatures
+    /// 0. data_account_executors
+    VerifySignatures {
+        req_id: ReqId,
+        signatures: Vec<[u8; 64]>,
+        executors: Vec<EthAddress>,
+        exe_index: u64,
+    },
+
+    /// [22] Set the maximum number of proposers. Raising it above `Constants::MAX_PROPOSERS`
+    /// resizes the basic storage account, with the admin paying the extra rent
+    /// 0. system_program
+    /// 1. account_admin: (signer) payer when resizing
+    /// 2. data_account_basic_storage
+    SetMaxProposers { max: u8 },
+
+    /// [23] Cancel up to `Constants::MAX_SWEEP_PROPOSALS` expired proposals of `kind` at once.
+    /// Entries that are not expired, already executed or missing, or whose refund accounts do not
+    /// match, are skipped; returns a little-endian `u64` bitmap of the cancelled entries via
+    /// return data. Permissionless
+    /// 0. data_account_basic_storage
+    /// (burn/lock kinds only) 1. token_program, 2. account_contract_signer
+    /// then for each `req_ids[i]`, a group of:
+    ///   - mint/unlock: data_account_proposal, account_refund
+    ///   - burn/lock: data_account_proposal, token_account_contract, token_account_proposer, account_refund
+    SweepExpired { kind: ProposalKind, req_ids: Vec<ReqId> },
+
+    /// [24] Returns the borsh-encoded `ConfigView` via return data. Read-only
+    /// 0. data_account_basic_storage
+    GetConfig,
+
+    /// [25] Logs the proposal's inner pubkey (recipient or proposer), whether it is executed,
+    /// and the timestamp after which it can be cancelled. Read-only
+    /// 0. data_account_proposal: proposal PDA of `kind` for `req_id`
+    QueryProposalState { req_id: ReqId, kind: ProposalKind },
+
+    /// [26] Returns the borsh-encoded `TokenInfoView` of `token_index` via return data. Read-only
+    /// 0. data_account_basic_storage
+    GetTokenInfo { token_index: u8 },
+
+    /// [27] First half of `Initialize`: creates basic storage only
+    /// 0. system_program
+    /// 1. account_admin: (signer)
+    /// 2. data_account_basic_storage
+    /// 3. account_payer: (optional, signer) defaults to the admin
+    InitializeBasicStorage {
+        is_mint_contract: bool,
+        max_proposers: u8, // 0 for the default `Constants::MAX_PROPOSERS`
+    },
+
+    /// [28] Second half of `Initialize`: creates the first executors group. Retryable while
+    /// no executors group exists
+    /// 0. system_program
+    /// 1. account_admin: (signer)
+    /// 2. data_account_basic_storage
+    /// 3. data_account_executors: data account for storing executors at index 0
+    /// 4. account_payer: (optional, signer) defaults to the admin
+    InitializeExecutors {
+        executors: Vec<EthAddress>,
+        threshold: u64,
+        exe_index: u64, // must be 0
+    },
+
+    /// [29] Cancel a proposal before it expires, with the same refund/close logic as the
+    /// regular cancel. Requires the admin and a threshold of executors signing
+    /// `ReqId::msg_from_req_force_cancel_message`
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    /// 2. data_account_executors
+    /// 3. data_account_proposal: proposal PDA of `kind` for `req_id`
+    /// 4. account_refund: refund account for closing PDA
+    /// (burn/lock kinds only) 5. token_program, 6. account_contract_signer,
+    /// 7. token_account_contract, 8. token_account_proposer
+    ForceCancel {
+        req_id: ReqId,
+        kind: ProposalKind,
+        signatures: Vec<[u8; 64]>,
+        executors: Vec<EthAddress>,
+        exe_index: u64,
+    },
+
+    /// [30] Move the vault of `token_index` to a new token account owned by the contract signer,
+    /// transferring the full balance. The old vault stays accepted for cancel refunds during
+    /// `Constants::VAULT_ROTATION_GRACE_PERIOD`
+    /// 0. token_program
+    /// 1. account_admin: (signer)
+    /// 2. account_contract_signer
+    /// 3. data_account_basic_storage
+    /// 4. token_account_contract: the current vault
+    /// 5. token_account_new_vault: initialized token account of the same mint, owned by the contract signer
+    RotateVault { token_index: u8 },
+
+    /// [31] Returns the borsh-encoded `ExecutorsInfoView` of `exe_index` via return data. Read-only
+    /// 0. data_account_executors: data account for storing executors at `exe_index`
+    GetExecutorsInfo { exe_index: u64 },
+
+    /// [32] Schedule a change of an admin parameter, applicable after `Constants::PARAM_CHANGE_DELAY`.
+    /// Only one change can be pending per admin
+    /// 0. system_program
+    /// 1. account_admin: (signer) also pays for the pending change PDA
+    /// 2. data_account_basic_storage
+    /// 3. data_account_pending_change: PDA of `["pending-change", admin]`
+    ProposeParamChange {
+        parameter_id: u8, // one of `Constants::PARAM_*`
+        new_value: Vec<u8>, // borsh-encoded value for the parameter
+    },
+
+    /// [33] Apply the pending parameter change once effective, closing its PDA
+    /// 0. account_admin: (signer) receives the rent of the closed PDA
+    /// 1. data_account_basic_storage
+    /// 2. data_account_pending_change: PDA of `["pending-change", admin]`
+    ApplyParamChange,
+
+    /// [34] Set the window for `active_since` of new executors, relative to the `UpdateExecutors` time
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    SetExecutorRotationWindow { min_delay: u64, max_delay: u64 },
+
+    /// [35] Returns the borsh-encoded `ProposerActivity` of `proposer` via return data. Read-only
+    /// 0. data_account_proposer_index: PDA of `["proposer-index", proposer]`
+    GetProposerActivity { proposer: Pubkey },
+
+    /// [36] Top up the rent vault that pays proposal rent for proposers passing it
+    /// 0. system_program
+    /// 1. account_admin: (signer) pays the lamports
+    /// 2. data_account_basic_storage
+    /// 3. account_rent_vault: PDA of `["rent-vault"]`
+    FundRentVault { lamports: u64 },
+
+    /// [37] `Initialize` followed by `AddToken` for each entry of `tokens`, in one transaction.
+    /// Peer addresses are left as the zero address
+    /// 0. system_program
+    /// 1. account_admin: (signer) the admin account, also the payer
+    /// 2. data_account_basic_storage
+    /// 3. data_account_executors
+    /// 4. account_contract_signer: contract signer PDA
+    /// 5. rent_sysvar
+    /// 6. associated_token_program
+    /// 7.. for each token, in order: token_program, token_mint, token_account_contract (contract ATA)
+    InitializeWithTokens {
+        is_mint_contract: bool,
+        executors: Vec<EthAddress>,
+        threshold: u64,
+        exe_index: u64, // must be 0 for a fresh deployment
+        tokens: Vec<(u8, Pubkey, u8)>, // (token_index, token_mint, decimals), decimals must match the mint
+    },
+
+    /// [38] Logs the locked balance of `token_index`, with its vault and mint. Read-only
+    /// 0. data_account_basic_storage
+    GetLockedBalance { token_index: u8 },
+
+    /// [39] Runs the req_id checks of the propose instructions (created time, hub side, token
+    /// index, amount) and logs the result of each. Fails with the first failing check. Read-only
+    /// 0. data_account_basic_storage
+    VerifyReqId { req_id: ReqId },
+
+    /// [41] Emergency transfer of `amount` from the vault of `token_index` to a recovery token
+    /// account. Only while nothing is owed from the vault: the locked balance and the token's
+    /// pending proposal count must both be zero
+    /// 0. token_program
+    /// 1. account_admin: (signer)
+    /// 2. account_contract_signer
+    /// 3. data_account_basic_storage
+    /// 4. token_account_contract: the current vault
+    /// 5. token_account_recovery: token account of the same mint receiving the tokens
+    TransferTokens { token_index: u8, amount: u64 },
+
+    /// [42] Restricts executes to the listed relayers, see `ExecuteMint`
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    AddRelayer { relayer: Pubkey },
+
+    /// [43] Removing the last relayer makes executes permissionless again
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    RemoveRelayer { relayer: Pubkey },
+
+    /// [44] Claims the execution of `req_id` for `Constants::EXECUTION_COMMIT_SLOTS` slots, so the
+    /// signature bundle cannot be sniped from the mempool. `commitment` is `keccak256(relayer || req_id)`.
+    /// Can be taken over once expired
+    /// 0. system_program
+    /// 1. account_relayer: (signer) pays for the commitment PDA
+    /// 2. data_account_execution_commit: PDA of `["execution-commit", req_id]`
+    CommitExecution { req_id: ReqId, commitment: [u8; 32] },
+
+    /// [45] Returns the commitment rent to its relayer once the exclusive window is over
+    /// 0. account_relayer: (signer) the committing relayer, receives the rent
+    /// 1. data_account_execution_commit: PDA of `["execution-commit", req_id]`
+    /// 2. data_account_basic_storage
+    CloseExecutionCommit { req_id: ReqId },
+
+    /// [46] Returns the borsh-encoded `ReconciliationView` of `token_index` via return data. Read-only
+    /// 0. data_account_basic_storage
+    /// 1. token_account_contract: the token's current vault, SPL Token or Token-2022
+    GetReconciliation { token_index: u8 },
+
+    /// [47] A proposer removes itself from the proposer list
+    /// 0. account_proposer: (signer)
+    /// 1. data_account_basic_storage
+    RenounceProposer,
+
+    /// [48] A proposer replaces its own key with `new_key`, e.g. after the old key leaked
+    /// 0. account_proposer: (signer)
+    /// 1. data_account_basic_storage
+    RotateProposerKey { new_key: Pubkey },
+
+    /// [49] Starts the admin renounce, finalizable after `Constants::ADMIN_RENOUNCE_DELAY`
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    InitiateAdminRenounce,
+
+    /// [50] Drops the pending admin renounce
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    CancelAdminRenounce,
+
+    /// [51] Sets the admin to `Constants::RENOUNCED_ADMIN`, disabling every admin-only
+    /// instruction for good. Irreversible. Fails while any token has a locked balance, unless
+    /// `force` is set and a threshold of executors signed
+    /// `Permissions::msg_from_force_admin_renounce`; `signatures`, `executors` and `exe_index`
+    /// are ignored otherwise
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    /// 2. data_account_executors: (`force` only)
+    FinalizeAdminRenounce {
+        force: bool,
+        signatures: Vec<[u8; 64]>,
+        executors: Vec<EthAddress>,
+        exe_index: u64,
+    },
+
+    /// [52] Lets any signer `ProposeLock` when `open_lock`, otherwise only listed proposers.
+    /// `ProposeUnlock` stays proposer-only either way
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    SetOpenLock { open_lock: bool },
+
+    /// [53] Rewrites basic storage created by an earlier version in the current layout, growing
+    /// the account first. Open to anyone, as it changes no setting
+    /// 0. system_program
+    /// 1. account_payer: (signer) pays the rent for the extra bytes
+    /// 2. data_account_basic_storage
+    MigrateBasicStorage,
+
+    /// [54] Pausing stops new proposals and records when, for `DecommissionWithdraw`. Proposals
+    /// already made can still be executed or cancelled
+    /// 0. account_admin: (signer)
+    /// 1. data_account_basic_storage
+    SetPaused { paused: bool },
+
+    /// [55] Withdraws `amount` of a lock-mode token from its vault to the token account
+    /// `recipient`, reducing `locked_balance`. Needs the admin and every executor of the group
+    /// over `AtomicLock::msg_from_decommission`, after a pause of `EXPIRE_EXTRA_PERIOD`
+    /// 0. token_program
+    /// 1. account_admin: (signer)
+    /// 2. account_contract_signer
+    /// 3. data_account_basic_storage
+    /// 4. data_account_executors: PDA of `["executors", exe_index]`
+    /// 5. token_account_contract: the current vault of `token_index`
+    /// 6. token_account_recipient: `recipient`
+    /// 7. sysvar_instructions: (optional) for ed25519 executors, or secp256k1 signatures
+    ///    verified by `secp256k1_program` instructions
+    DecommissionWithdraw {
+        token_index: u8,
+        amount: u64,
+        recipient: Pubkey,
+        signatures: Vec<[u8; 64]>,
+        executors: Vec<EthAddress>,
+        exe_index: u64,
+    },
 }
 
 impl FreeTunnelInstruction {
+    /// Index of the account that must sign the instruction, if any. Executes are authorized
+    /// by the executors' signatures and cancels are open to anyone once expired, so neither
+    /// requires a signer.
+    pub fn required_signer(&self) -> Option<usize> {
+        match self {
+            Self::Initialize { .. } => Some(1),
+            Self::TransferAdmin { .. } => Some(0),
+            Self::AddProposer { .. } => Some(0),
+            Self::RemoveProposer { .. } => Some(0),
+            Self::UpdateExecutors { .. } => Some(1),
+            Self::AddToken { .. } => Some(2),
+            Self::RemoveToken { .. } => Some(0),
+            Self::ProposeMint { .. } => Some(1),
+            Self::ExecuteMint { .. } => None,
+            Self::CancelMint { .. } => None,
+            Self::ProposeBurn { .. } => Some(2),
+            Self::ExecuteBurn { .. } => None,
+            Self::CancelBurn { .. } => None,
+            Self::ProposeLock { .. } => Some(2),
+            Self::ExecuteLock { .. } => None,
+            Self::CancelLock { .. } => None,
+            Self::ProposeUnlock { .. } => Some(1),
+            Self::ExecuteUnlock { .. } => None,
+            Self::CancelUnlock { .. } => None,
+            Self::ClaimStuckProposal { .. } => Some(0),
+            Self::SetMinProposeInterval { .. } => Some(0),
+            Self::VerifySignatures { .. } => None,
+            Self::SetMaxProposers { .. } => Some(1),
+            Self::SweepExpired { .. } => None,
+            Self::GetConfig => None,
+            Self::QueryProposalState { .. } => None,
+            Self::GetTokenInfo { .. } => None,
+            Self::InitializeBasicStorage { .. } => Some(1),
+            Self::InitializeExecutors { .. } => Some(1),
+            Self::ForceCancel { .. } => Some(0),
+            Self::RotateVault { .. } => Some(1),
+            Self::GetExecutorsInfo { .. } => None,
+            Self::ProposeParamChange { .. } => Some(1),
+            Self::ApplyParamChange => Some(0),
+            Self::SetExecutorRotationWindow { .. } => Some(0),
+            Self::GetProposerActivity { .. } => None,
+            Self::FundRentVault { .. } => Some(1),
+            Self::InitializeWithTokens { .. } => Some(1),
+            Self::GetLockedBalance { .. } => None,
+            Self::VerifyReqId { .. } => None,
+            Self::TransferTokens { .. } => Some(1),
+            Self::AddRelayer { .. } => Some(0),
+            Self::RemoveRelayer { .. } => Some(0),
+            Self::CommitExecution { .. } => Some(1),
+            Self::CloseExecutionCommit { .. } => Some(0),
+            Self::GetReconciliation { .. } => None,
+            Self::RenounceProposer => Some(0),
+            Self::RotateProposerKey { .. } => Some(0),
+            Self::InitiateAdminRenounce => Some(0),
+            Self::CancelAdminRenounce => Some(0),
+            Self::FinalizeAdminRenounce { .. } => Some(0),
+            Self::SetOpenLock { .. } => Some(0),
+            Self::MigrateBasicStorage => Some(1),
+            Self::SetPaused { .. } => Some(0),
+            Self::DecommissionWithdraw { .. } => Some(1),
+        }
+    }
+
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
         let (&variant, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
         match variant {
             0 => {
-                let (is_mint_contract, executors, threshold, exe_index) =
+                let (is_mint_contract, executors, threshold, exe_index, max_proposers) =
                     BorshDeserialize::try_from_slice(rest)?;
                 Ok(Self::Initialize {
                     is_mint_contract,
                     executors,
                     threshold,
                     exe_index,
+                    max_proposers,
                 })
             }
             1 => {
@@ -220,10 +626,11 @@ impl FreeTunnelInstruction {
                 Ok(Self::RemoveProposer { proposer })
             }
             4 => {
-                let (new_executors, threshold, active_since, signatures, executors, exe_index) =
+                let (new_executors, new_ed25519_executors, threshold, active_since, signatures, executors, exe_index) =
                     BorshDeserialize::try_from_slice(rest)?;
                 Ok(Self::UpdateExecutors {
                     new_executors,
+                    new_ed25519_executors,
                     threshold,
                     active_since,
                     signatures,
@@ -232,9 +639,14 @@ impl FreeTunnelInstruction {
                 })
             }
             5 => {
-                let token_index = BorshDeserialize::try_from_slice(rest)?;
+                let (token_index, peer_address, is_token_2022, confirm_reuse, allow_high_decimals) =
+                    BorshDeserialize::try_from_slice(rest)?;
                 Ok(Self::AddToken {
                     token_index,
+                    peer_address,
+                    is_token_2022,
+                    confirm_reuse,
+                    allow_high_decimals,
                 })
             }
             6 => {
@@ -242,8 +654,8 @@ impl FreeTunnelInstruction {
                 Ok(Self::RemoveToken { token_index })
             }
             7 => {
-                let (req_id, recipient) = BorshDeserialize::try_from_slice(rest)?;
-                Ok(Self::ProposeMint { req_id, recipient })
+                let (req_id, recipient, pinned_exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ProposeMint { req_id, recipient, pinned_exe_index })
             }
             8 => {
                 let (req_id, signatures, executors, exe_index) =
@@ -260,8 +672,8 @@ impl FreeTunnelInstruction {
                 Ok(Self::CancelMint { req_id })
             }
             10 => {
-                let req_id = BorshDeserialize::try_from_slice(rest)?;
-                Ok(Self::ProposeBurn { req_id })
+                let (req_id, pinned_exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ProposeBurn { req_id, pinned_exe_index })
             }
             11 => {
                 let (req_id, signatures, executors, exe_index) =
@@ -278,8 +690,8 @@ impl FreeTunnelInstruction {
                 Ok(Self::CancelBurn { req_id })
             }
             13 => {
-                let req_id = BorshDeserialize::try_from_slice(rest)?;
-                Ok(Self::ProposeLock { req_id })
+                let (req_id, pinned_exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ProposeLock { req_id, pinned_exe_index })
             }
             14 => {
                 let (req_id, signatures, executors, exe_index) =
@@ -296,8 +708,8 @@ impl FreeTunnelInstruction {
                 Ok(Self::CancelLock { req_id })
             }
             16 => {
-                let (req_id, recipient) = BorshDeserialize::try_from_slice(rest)?;
-                Ok(Self::ProposeUnlock { req_id, recipient })
+                let (req_id, recipient, pinned_exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ProposeUnlock { req_id, recipient, pinned_exe_index })
             }
             17 => {
                 let (req_id, signatures, executors, exe_index) =
@@ -313,7 +725,146 @@ impl FreeTunnelInstruction {
                 let req_id = BorshDeserialize::try_from_slice(rest)?;
                 Ok(Self::CancelUnlock { req_id })
             }
-            // If the variant is not one of 0-20, return an error
+            19 => {
+                let (req_id, kind) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ClaimStuckProposal { req_id, kind })
+            }
+            20 => {
+                let interval = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SetMinProposeInterval { interval })
+            }
+            21 => {
+                let (req_id, signatures, executors, exe_index) =
+                    BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::VerifySignatures {
+                    req_id,
+                    signatures,
+                    executors,
+                    exe_index,
+                })
+            }
+            22 => {
+                let max = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SetMaxProposers { max })
+            }
+            23 => {
+                let (kind, req_ids) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SweepExpired { kind, req_ids })
+            }
+            24 => Ok(Self::GetConfig),
+            25 => {
+                let (req_id, kind) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::QueryProposalState { req_id, kind })
+            }
+            26 => {
+                let token_index = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::GetTokenInfo { token_index })
+            }
+            27 => {
+                let (is_mint_contract, max_proposers) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::InitializeBasicStorage { is_mint_contract, max_proposers })
+            }
+            28 => {
+                let (executors, threshold, exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::InitializeExecutors { executors, threshold, exe_index })
+            }
+            29 => {
+                let (req_id, kind, signatures, executors, exe_index) =
+                    BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ForceCancel {
+                    req_id,
+                    kind,
+                    signatures,
+                    executors,
+                    exe_index,
+                })
+            }
+            30 => {
+                let token_index = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::RotateVault { token_index })
+            }
+            31 => {
+                let exe_index = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::GetExecutorsInfo { exe_index })
+            }
+            32 => {
+                let (parameter_id, new_value) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::ProposeParamChange { parameter_id, new_value })
+            }
+            33 => Ok(Self::ApplyParamChange),
+            34 => {
+                let (min_delay, max_delay) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SetExecutorRotationWindow { min_delay, max_delay })
+            }
+            35 => {
+                let proposer = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::GetProposerActivity { proposer })
+            }
+            36 => {
+                let lamports = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::FundRentVault { lamports })
+            }
+            37 => {
+                let (is_mint_contract, executors, threshold, exe_index, tokens) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::InitializeWithTokens { is_mint_contract, executors, threshold, exe_index, tokens })
+            }
+            38 => {
+                let token_index = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::GetLockedBalance { token_index })
+            }
+            39 => {
+                let req_id = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::VerifyReqId { req_id })
+            }
+            41 => {
+                let (token_index, amount) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::TransferTokens { token_index, amount })
+            }
+            42 => {
+                let relayer = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::AddRelayer { relayer })
+            }
+            43 => {
+                let relayer = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::RemoveRelayer { relayer })
+            }
+            44 => {
+                let (req_id, commitment) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::CommitExecution { req_id, commitment })
+            }
+            45 => {
+                let req_id = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::CloseExecutionCommit { req_id })
+            }
+            46 => {
+                let token_index = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::GetReconciliation { token_index })
+            }
+            47 => Ok(Self::RenounceProposer),
+            48 => {
+                let new_key = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::RotateProposerKey { new_key })
+            }
+            49 => Ok(Self::InitiateAdminRenounce),
+            50 => Ok(Self::CancelAdminRenounce),
+            51 => {
+                let (force, signatures, executors, exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::FinalizeAdminRenounce { force, signatures, executors, exe_index })
+            }
+            52 => {
+                let open_lock = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SetOpenLock { open_lock })
+            }
+            53 => Ok(Self::MigrateBasicStorage),
+            54 => {
+                let paused = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::SetPaused { paused })
+            }
+            55 => {
+                let (token_index, amount, recipient, signatures, executors, exe_index) = BorshDeserialize::try_from_slice(rest)?;
+                Ok(Self::DecommissionWithdraw { token_index, amount, recipient, signatures, executors, exe_index })
+            }
+            // If the variant is not one of 0-55, return an error
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
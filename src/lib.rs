@@ -1,10 +1,15 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+    account_info::AccountInfo, declare_id, entrypoint, entrypoint::ProgramResult,
+    program_error::ProgramError, pubkey::Pubkey,
 };
 
 use crate::processor::Processor;
 entrypoint!(process_instruction);
 
+// Placeholder until the canonical deployment is pinned here. Builds for forks, or any other
+// keypair, enable `custom-program-id` to skip the check in `process_instruction`
+declare_id!("FreeTunne1111111111111111111111111111111111");
+
 pub mod constants;
 pub mod error;
 pub mod instruction;
@@ -15,14 +20,29 @@ pub mod utils;
 pub mod logic {
     pub mod atomic_lock;
     pub mod atomic_mint;
+    pub mod execution_commit;
     pub mod permissions;
+    pub mod proposer_index;
+    pub mod rent_vault;
     pub mod req_helpers;
     pub mod token_ops;
 }
 
 #[cfg(test)]
 pub mod test {
+    pub mod atomic_lock_test;
+    pub mod atomic_mint_test;
+    pub mod error_test;
+    pub mod execution_commit_test;
+    pub mod instruction_test;
+    pub mod permissions_test;
+    pub mod processor_test;
+    pub mod rent_vault_test;
     pub mod req_helpers_test;
+    pub mod runtime_stubs;
+    pub mod state_test;
+    pub mod test_fixtures;
+    pub mod token_ops_test;
     pub mod utils_test;
 }
 
@@ -32,5 +52,9 @@ pub fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
+    // A deploy under another keypair would derive PDAs clients never expect
+    if !cfg!(feature = "custom-program-id") && program_id != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
     Processor::process_instruction(program_id, accounts, instruction_data)
 }
@@ -2,23 +2,34 @@ use solana_program::{
     account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
     program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
-use std::mem::size_of;
 
 use crate::{
     constants::{Constants, EthAddress},
     error::FreeTunnelError,
-    logic::{permissions::Permissions, req_helpers::ReqId, token_ops},
-    state::{BasicStorage, ProposedLock, ProposedUnlock},
+    logic::{permissions::Permissions, rent_vault::RentVault, req_helpers::ReqId, token_ops},
+    state::{BasicStorage, ProposalKind, ProposedLock, ProposedUnlock},
     utils::{DataAccountUtils, SignatureUtils},
 };
 
 pub struct AtomicLock;
 
+/// Accounts of `DecommissionWithdraw`, in instruction order
+pub(crate) struct DecommissionAccounts<'b, 'a> {
+    pub token_program: &'b AccountInfo<'a>,
+    pub account_admin: &'b AccountInfo<'a>,
+    pub account_contract_signer: &'b AccountInfo<'a>,
+    pub data_account_basic_storage: &'b AccountInfo<'a>,
+    pub data_account_executors: &'b AccountInfo<'a>,
+    pub token_account_contract: &'b AccountInfo<'a>,
+    pub token_account_recipient: &'b AccountInfo<'a>,
+    pub sysvar_instructions: Option<&'b AccountInfo<'a>>,
+}
+
 impl AtomicLock {
     fn assert_contract_mode_is_lock<'a>(
         data_account_basic_storage: &AccountInfo<'a>,
     ) -> ProgramResult {
-        let basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         match basic_storage.mint_or_lock {
             true => Err(FreeTunnelError::NotLockContract.into()),
             false => Ok(()),
@@ -34,40 +45,66 @@ impl AtomicLock {
         token_account_proposer: &AccountInfo<'a>,
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_lock: &AccountInfo<'a>,
+        account_rent_vault: Option<&AccountInfo<'a>>,
+        bump_seed: u8,
         req_id: &ReqId,
+        pinned_exe_index: Option<u64>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        req_id.assert_mint_opposite_side()?;
+        Permissions::assert_not_paused(data_account_basic_storage)?;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        req_id.assert_mint_opposite_side(Constants::HUB_ID)?;
         if req_id.action() & 0x0f != 1 { return Err(FreeTunnelError::NotLockMint.into()); }
 
         if !account_proposer.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+        Permissions::assert_can_propose_lock(data_account_basic_storage, account_proposer)?;
         req_id.checked_created_time()?;
+        Permissions::assert_propose_rate_limit(data_account_basic_storage, account_proposer)?;
         if !data_account_proposed_lock.data_is_empty() { return Err(FreeTunnelError::ReqIdOccupied.into()); }
         if account_proposer.key == &Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::InvalidProposer.into());
         }
 
         // Check amount & token
-        let (token_index, decimal, _) = req_id.get_checked_token(data_account_basic_storage, Some(token_account_proposer))?;
+        let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, Some(token_account_proposer))?;
         let amount = req_id.get_checked_amount(decimal)?;
+        token_ops::assert_is_ata(token_program, token_account_proposer, account_proposer.key, &mint_pubkey)?;
+        token_ops::assert_token_account_owner(token_program, token_account_proposer, account_proposer.key)?;
 
         // Write proposed-lock data
-        DataAccountUtils::create_data_account(
+        let mut proposal = ProposedLock {
+            inner: *account_proposer.key,
+            rent_from_vault: false,
+            token_account: *token_account_proposer.key,
+            pinned_exe_index,
+        };
+        proposal.rent_from_vault = RentVault::fund_proposal(program_id, system_program, account_rent_vault, account_proposer, &proposal)?;
+        DataAccountUtils::create_proposal_account(
             program_id,
             system_program,
             account_proposer,
             data_account_proposed_lock,
-            Constants::PREFIX_LOCK,
+            ProposalKind::Lock,
             &req_id.data,
-            size_of::<ProposedLock>() + Constants::SIZE_LENGTH,
-            ProposedLock { inner: *account_proposer.key },
+            bump_seed,
+            req_id.proposal_header(ProposalKind::Lock, *account_proposer.key, amount),
+            proposal,
         )?;
 
         // Deposit token
         token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
-        token_ops::transfer_to_contract(token_program, token_account_proposer, token_account_contract, account_proposer, amount)?;
+        token_ops::assert_not_frozen(token_program, token_account_proposer)?;
+        token_ops::assert_not_frozen(token_program, token_account_contract)?;
+        token_ops::transfer_to_contract(token_program, token_account_contract, token_account_proposer, account_proposer, amount)?;
+
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), true)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, true)?;
 
-        msg!("TokenLockProposed: req_id={}, proposer={}", hex::encode(req_id.data), account_proposer.key);
+        #[cfg(feature = "invariants")]
+        token_ops::assert_vault_invariant(token_program, data_account_basic_storage, token_index, token_account_contract)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenLockProposed: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), account_proposer.key, event_seq);
         Ok(())
     }
 
@@ -76,34 +113,44 @@ impl AtomicLock {
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_lock: &AccountInfo<'a>,
         data_account_executors: &AccountInfo<'a>,
+        sysvar_instructions: Option<&AccountInfo<'a>>,
         req_id: &ReqId,
         signatures: &Vec<[u8; 64]>,
         executors: &Vec<EthAddress>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        let proposer = DataAccountUtils::read_account_data::<ProposedLock>(data_account_proposed_lock)?.inner;
+        if data_account_proposed_lock.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let proposer = DataAccountUtils::read_proposal_inner(data_account_proposed_lock)?;
         if proposer == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let message = req_id.msg_from_req_signing_message();
-        SignatureUtils::assert_multisig_valid(data_account_executors, &message, signatures, executors)?;
+        SignatureUtils::assert_multisig_valid(data_account_executors, sysvar_instructions, &message, signatures, executors)?;
 
-        // Update proposed-lock data
-        DataAccountUtils::write_account_data(
-            data_account_proposed_lock,
-            ProposedLock { inner: Constants::EXECUTED_PLACEHOLDER },
-        )?;
-
-        // Update locked-balance data
         let (token_index, decimal, _) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
+
+        // Update locked-balance data, then mark executed last
         Self::update_locked_balance(data_account_basic_storage, token_index, amount, true)?;
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+        DataAccountUtils::mark_proposal_executed(data_account_proposed_lock)?;
 
-        msg!("TokenLockExecuted: req_id={}, proposer={}", hex::encode(req_id.data), proposer);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenLockExecuted: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), proposer, event_seq);
         Ok(())
     }
 
+    /// Whether a lock's rent came from the rent vault, and the token account it locked from.
+    /// Proposals created before either was recorded hold only `inner`, and were paid by the proposer
+    pub(crate) fn lock_refund_of(data_account_proposed_lock: &AccountInfo) -> (bool, Option<Pubkey>) {
+        match DataAccountUtils::read_proposal_data::<ProposedLock>(data_account_proposed_lock) {
+            Ok(proposal) => (proposal.rent_from_vault, Some(proposal.token_account)),
+            Err(_) => (false, None),
+        }
+    }
+
     pub(crate) fn cancel_lock<'a>(
         program_id: &Pubkey,
         token_program: &AccountInfo<'a>,
@@ -114,25 +161,34 @@ impl AtomicLock {
         data_account_proposed_lock: &AccountInfo<'a>,
         account_refund: &AccountInfo<'a>,
         req_id: &ReqId,
+        forced: bool, // skips the expiry check, see `ForceCancel`
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        let proposer = DataAccountUtils::read_account_data::<ProposedLock>(data_account_proposed_lock)?.inner;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        if data_account_proposed_lock.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let proposer = DataAccountUtils::read_proposal_inner(data_account_proposed_lock)?;
         if proposer == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let now = Clock::get()?.unix_timestamp;
-        if now <= (req_id.created_time() + Constants::EXPIRE_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
+        if !forced && now <= (req_id.created_time() + Constants::EXPIRE_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
 
         let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
 
-        Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?;
-        DataAccountUtils::close_account(program_id, data_account_proposed_lock, account_refund)?;
+        let (rent_from_vault, source) = Self::lock_refund_of(data_account_proposed_lock);
+        // `inner` records the proposer of every lock proposal, so the refund goes back to it even
+        // if it was never listed (`open_lock`) or has left the list since
+        RentVault::assert_rent_refund(program_id, account_refund, rent_from_vault, &proposer)?;
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+        DataAccountUtils::close_account(program_id, data_account_proposed_lock, account_refund, Constants::PREFIX_LOCK, &req_id.data, false)?;
 
         // Refund token
-        token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
-        token_ops::assert_is_ata(token_program, token_account_proposer, &proposer, &mint_pubkey)?;
+        token_ops::assert_is_current_or_previous_vault(data_account_basic_storage, token_index, token_account_contract)?;
+        token_ops::assert_vault_balance(token_program, token_account_contract, amount)?;
+        token_ops::assert_refund_token_account(token_program, token_account_proposer, source, &proposer, &mint_pubkey)?;
         token_ops::transfer_from_contract(
             program_id,
             token_program,
@@ -142,7 +198,11 @@ impl AtomicLock {
             amount,
         )?;
 
-        msg!("TokenLockCancelled: req_id={}, proposer={}", hex::encode(req_id.data), proposer);
+        #[cfg(feature = "invariants")]
+        token_ops::assert_vault_invariant(token_program, data_account_basic_storage, token_index, token_account_contract)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenLockCancelled: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), proposer, event_seq);
         Ok(())
     }
 
@@ -152,38 +212,52 @@ impl AtomicLock {
         account_proposer: &AccountInfo<'a>, // signer
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_unlock: &AccountInfo<'a>,
+        account_rent_vault: Option<&AccountInfo<'a>>,
+        bump_seed: u8,
         req_id: &ReqId,
         recipient: &Pubkey,
+        pinned_exe_index: Option<u64>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        req_id.assert_mint_opposite_side()?;
+        Permissions::assert_not_paused(data_account_basic_storage)?;
+        req_id.assert_mint_opposite_side(Constants::HUB_ID)?;
         if req_id.action() & 0x0f != 2 { return Err(FreeTunnelError::NotBurnUnlock.into()); }
 
         Permissions::assert_only_proposer(data_account_basic_storage, account_proposer, true)?;
         req_id.checked_created_time()?;
-        if !data_account_proposed_unlock.data_is_empty() { return Err(FreeTunnelError::ReqIdOccupied.into()); }
-        if *recipient == Constants::EXECUTED_PLACEHOLDER {
-            return Err(FreeTunnelError::InvalidRecipient.into());
-        }
 
-        // Check amount & token
+        // Check amount & token, and that the locked balance covers it before writing any state
         let (token_index, decimal, _) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
+        Self::assert_locked_balance_sufficient(data_account_basic_storage, token_index, amount)?;
+
+        Permissions::assert_propose_rate_limit(data_account_basic_storage, account_proposer)?;
+        if !data_account_proposed_unlock.data_is_empty() { return Err(FreeTunnelError::ReqIdOccupied.into()); }
+        if *recipient == Constants::EXECUTED_PLACEHOLDER || *recipient == Pubkey::default() {
+            return Err(FreeTunnelError::InvalidRecipient.into());
+        }
         Self::update_locked_balance(data_account_basic_storage, token_index, amount, false)?;
 
         // Write proposed-unlock data
-        DataAccountUtils::create_data_account(
+        let mut proposal = ProposedUnlock { inner: *recipient, proposer: *account_proposer.key, rent_from_vault: false, pinned_exe_index };
+        proposal.rent_from_vault = RentVault::fund_proposal(program_id, system_program, account_rent_vault, account_proposer, &proposal)?;
+        DataAccountUtils::create_proposal_account(
             program_id,
             system_program,
             account_proposer,
             data_account_proposed_unlock,
-            Constants::PREFIX_UNLOCK,
+            ProposalKind::Unlock,
             &req_id.data,
-            size_of::<ProposedUnlock>() + Constants::SIZE_LENGTH,
-            ProposedUnlock { inner: *recipient },
+            bump_seed,
+            req_id.proposal_header(ProposalKind::Unlock, *recipient, amount),
+            proposal,
         )?;
 
-        msg!("TokenUnlockProposed: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), true)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, true)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenUnlockProposed: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
@@ -196,41 +270,80 @@ impl AtomicLock {
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_unlock: &AccountInfo<'a>,
         data_account_executors: &AccountInfo<'a>,
+        sysvar_instructions: Option<&AccountInfo<'a>>,
+        token_account_previous_vault: Option<&AccountInfo<'a>>,
         req_id: &ReqId,
         signatures: &Vec<[u8; 64]>,
         executors: &Vec<EthAddress>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        let recipient = DataAccountUtils::read_account_data::<ProposedUnlock>(data_account_proposed_unlock)?.inner;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        if data_account_proposed_unlock.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let recipient = DataAccountUtils::read_proposal_inner(data_account_proposed_unlock)?;
         if recipient == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let message = req_id.msg_from_req_signing_message();
-        SignatureUtils::assert_multisig_valid(data_account_executors, &message, signatures, executors)?;
+        SignatureUtils::assert_multisig_valid(data_account_executors, sysvar_instructions, &message, signatures, executors)?;
 
-        // Update proposed-unlock data
-        DataAccountUtils::write_account_data(
-            data_account_proposed_unlock,
-            ProposedUnlock { inner: Constants::EXECUTED_PLACEHOLDER },
-        )?;
-
-        // Unlock token to recipient
+        // Check token & accounts
         let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
-
         token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
+        if let Some(token_account_previous_vault) = token_account_previous_vault {
+            token_ops::assert_is_current_or_previous_vault(data_account_basic_storage, token_index, token_account_previous_vault)?;
+            if token_account_previous_vault.key == token_account_contract.key {
+                return Err(FreeTunnelError::InvalidTokenAccount.into());
+            }
+        }
+        let (from_previous, from_current) =
+            token_ops::split_unlock_amount(token_program, token_account_previous_vault, token_account_contract, amount)?;
         token_ops::assert_is_ata(token_program, token_account_recipient, &recipient, &mint_pubkey)?;
-        token_ops::transfer_from_contract(
-            program_id,
-            token_program,
-            account_contract_signer,
-            token_account_contract,
-            token_account_recipient,
-            amount,
-        )?;
+        if from_previous > 0 {
+            token_ops::assert_not_frozen(token_program, token_account_previous_vault.unwrap())?;
+        }
+        if from_current > 0 {
+            token_ops::assert_not_frozen(token_program, token_account_contract)?;
+        }
+        token_ops::assert_not_frozen(token_program, token_account_recipient)?;
+
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+
+        // Mark executed only after every check passed, right before the CPIs
+        DataAccountUtils::mark_proposal_executed(data_account_proposed_unlock)?;
+
+        // Unlock token to recipient, draining the previous vault first
+        if from_previous > 0 {
+            token_ops::transfer_from_contract(
+                program_id,
+                token_program,
+                account_contract_signer,
+                token_account_previous_vault.unwrap(),
+                token_account_recipient,
+                from_previous,
+            )?;
+        }
+        if from_current > 0 {
+            token_ops::transfer_from_contract(
+                program_id,
+                token_program,
+                account_contract_signer,
+                token_account_contract,
+                token_account_recipient,
+                from_current,
+            )?;
+        }
 
-        msg!("TokenUnlockExecuted: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        #[cfg(feature = "invariants")]
+        token_ops::assert_vault_invariant(token_program, data_account_basic_storage, token_index, token_account_contract)?;
+
+        if from_previous > 0 {
+            msg!("UnlockSplit: from_previous={}, from_current={}", from_previous, from_current);
+        }
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenUnlockExecuted: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
@@ -240,36 +353,61 @@ impl AtomicLock {
         data_account_proposed_unlock: &AccountInfo<'a>,
         account_refund: &AccountInfo<'a>,
         req_id: &ReqId,
+        forced: bool, // skips the expiry check, see `ForceCancel`
     ) -> ProgramResult {
         Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
-        let recipient = DataAccountUtils::read_account_data::<ProposedUnlock>(data_account_proposed_unlock)?.inner;
+        if data_account_proposed_unlock.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let recipient = DataAccountUtils::read_proposal_inner(data_account_proposed_unlock)?;
         if recipient == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let now = Clock::get()?.unix_timestamp;
-        if now <= (req_id.created_time() + Constants::EXPIRE_EXTRA_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
+        if !forced && now <= (req_id.created_time() + Constants::EXPIRE_EXTRA_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
 
         // Update locked-balance data
         let (token_index, decimal, _) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
         Self::update_locked_balance(data_account_basic_storage, token_index, amount, true)?;
 
-        Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?;
-        DataAccountUtils::close_account(program_id, data_account_proposed_unlock, account_refund)?;
+        // Anyone can trigger the cancel, but the rent always returns to whoever paid it.
+        // Proposals created before `proposer` was recorded still require a proposer as refund
+        match DataAccountUtils::read_proposal_data::<ProposedUnlock>(data_account_proposed_unlock) {
+            Ok(ProposedUnlock { proposer, rent_from_vault, .. }) => {
+                RentVault::assert_rent_refund(program_id, account_refund, rent_from_vault, &proposer)?;
+            }
+            Err(_) => Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?,
+        }
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+        DataAccountUtils::close_account(program_id, data_account_proposed_unlock, account_refund, Constants::PREFIX_UNLOCK, &req_id.data, false)?;
 
-        msg!("TokenUnlockCancelled: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenUnlockCancelled: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
 
-    fn update_locked_balance(
+    fn assert_locked_balance_sufficient(
+        data_account_basic_storage: &AccountInfo,
+        token_index: u8,
+        amount: u64,
+    ) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let locked_balance = *basic_storage.locked_balance.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        if locked_balance < amount {
+            msg!("LockedBalanceInsufficient: token_index={}, locked_balance={}, amount={}", token_index, locked_balance, amount);
+            Err(FreeTunnelError::LockedBalanceInsufficient.into())
+        } else { Ok(()) }
+    }
+
+    pub(crate) fn update_locked_balance(
         data_account_basic_storage: &AccountInfo,
         token_index: u8,
         amount: u64,
         is_add: bool,
     ) -> ProgramResult {
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         let locked_balance = basic_storage.locked_balance.get_mut(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
         if is_add {
             *locked_balance = locked_balance.checked_add(amount).ok_or(FreeTunnelError::ArithmeticOverflow)?;
@@ -278,4 +416,81 @@ impl AtomicLock {
         }
         DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)
     }
+
+    /// Message every executor signs to let the admin withdraw `amount` of a token to
+    /// `recipient` when sunsetting the bridge. It names the pause it belongs to, so signatures
+    /// cannot be replayed after an unpause
+    pub(crate) fn msg_from_decommission(token_index: u8, mint: &Pubkey, amount: u64, recipient: &Pubkey, paused_since: u64) -> Vec<u8> {
+        let mut body = b"[".to_vec();
+        body.extend_from_slice(Constants::BRIDGE_CHANNEL); body.extend_from_slice(b"]\n");
+        body.extend_from_slice(b"Sign to decommission:\n");
+        body.extend_from_slice(b"Token index: "); body.extend_from_slice(token_index.to_string().as_bytes()); body.extend_from_slice(b"\n");
+        body.extend_from_slice(b"Mint: "); body.extend_from_slice(mint.to_string().as_bytes()); body.extend_from_slice(b"\n");
+        body.extend_from_slice(b"Amount: "); body.extend_from_slice(amount.to_string().as_bytes()); body.extend_from_slice(b"\n");
+        body.extend_from_slice(b"Recipient: "); body.extend_from_slice(recipient.to_string().as_bytes()); body.extend_from_slice(b"\n");
+        body.extend_from_slice(b"Paused since: "); body.extend_from_slice(paused_since.to_string().as_bytes());
+
+        let mut msg = Constants::ETH_SIGN_HEADER.to_vec();
+        msg.extend_from_slice(body.len().to_string().as_bytes());
+        msg.extend_from_slice(&body);
+        msg
+    }
+
+    /// Returns locked funds without per-user req_ids when sunsetting a lock contract. Only once
+    /// proposals have been paused for `EXPIRE_EXTRA_PERIOD`, after which every proposal made
+    /// before the pause has expired, and only with the admin and every executor of the group
+    pub(crate) fn decommission_withdraw(
+        program_id: &Pubkey,
+        accounts: &DecommissionAccounts,
+        token_index: u8,
+        amount: u64,
+        signatures: &Vec<[u8; 64]>,
+        executors: &Vec<EthAddress>,
+    ) -> ProgramResult {
+        let data_account_basic_storage = accounts.data_account_basic_storage;
+        Permissions::assert_only_admin(data_account_basic_storage, accounts.account_admin)?;
+        Self::assert_contract_mode_is_lock(data_account_basic_storage)?;
+        token_ops::assert_token_program_of(data_account_basic_storage, token_index, accounts.token_program)?;
+
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let now = Clock::get()?.unix_timestamp as u64;
+        let paused_since = basic_storage.paused_since;
+        if paused_since == 0 || now < paused_since.saturating_add(Constants::EXPIRE_EXTRA_PERIOD) {
+            msg!("DecommissionNotReady: paused_since={}, now={}", paused_since, now);
+            return Err(FreeTunnelError::DecommissionNotReady.into());
+        }
+        if amount == 0 {
+            return Err(FreeTunnelError::AmountCannotBeZero.into());
+        }
+        let mint = *basic_storage.tokens.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        let vault = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        if accounts.token_account_contract.key != vault {
+            return Err(FreeTunnelError::InvalidTokenAccount.into());
+        }
+
+        let recipient = accounts.token_account_recipient.key;
+        let message = Self::msg_from_decommission(token_index, &mint, amount, recipient, paused_since);
+        SignatureUtils::assert_multisig_complete(accounts.data_account_executors, accounts.sysvar_instructions, &message, signatures, executors)?;
+
+        Self::update_locked_balance(data_account_basic_storage, token_index, amount, false)?;
+        token_ops::assert_vault_balance(accounts.token_program, accounts.token_account_contract, amount)?;
+        token_ops::transfer_from_contract(
+            program_id,
+            accounts.token_program,
+            accounts.account_contract_signer,
+            accounts.token_account_contract,
+            accounts.token_account_recipient,
+            amount,
+        )?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!(
+            "DecommissionWithdrawn: token_index={}, recipient={}, amount={}, event_seq={}",
+            token_index,
+            recipient,
+            amount,
+            event_seq
+        );
+        Ok(())
+    }
 }
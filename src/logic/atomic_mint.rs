@@ -2,13 +2,12 @@ use solana_program::{
     account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
     program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
-use std::mem::size_of;
 
 use crate::{
     constants::{Constants, EthAddress},
     error::FreeTunnelError,
-    logic::{permissions::Permissions, req_helpers::ReqId, token_ops},
-    state::{BasicStorage, ProposedBurn, ProposedMint},
+    logic::{permissions::Permissions, rent_vault::RentVault, req_helpers::ReqId, token_ops},
+    state::{BasicStorage, ProposalKind, ProposedBurn, ProposedMint},
     utils::{DataAccountUtils, SignatureUtils},
 };
 
@@ -18,7 +17,7 @@ impl AtomicMint {
     fn assert_contract_mode_is_mint<'a>(
         data_account_basic_storage: &AccountInfo<'a>,
     ) -> ProgramResult {
-        let basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         match basic_storage.mint_or_lock {
             true => Ok(()),
             false => Err(FreeTunnelError::NotMintContract.into()),
@@ -31,38 +30,49 @@ impl AtomicMint {
         account_proposer: &AccountInfo<'a>,
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_mint: &AccountInfo<'a>,
+        account_rent_vault: Option<&AccountInfo<'a>>,
+        bump_seed: u8,
         req_id: &ReqId,
         recipient: &Pubkey,
+        pinned_exe_index: Option<u64>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
-        req_id.assert_mint_side()?;
+        Permissions::assert_not_paused(data_account_basic_storage)?;
+        req_id.assert_mint_side(Constants::HUB_ID)?;
         let specific_action = req_id.action() & 0x0f;
         if specific_action != 1 && specific_action != 3 { return Err(FreeTunnelError::NotLockMint.into()); }
 
         Permissions::assert_only_proposer(data_account_basic_storage, account_proposer, true)?;
         req_id.checked_created_time()?;
+        Permissions::assert_propose_rate_limit(data_account_basic_storage, account_proposer)?;
         if !data_account_proposed_mint.data_is_empty() { return Err(FreeTunnelError::ReqIdOccupied.into()); }
-        if *recipient == Constants::EXECUTED_PLACEHOLDER {
+        if *recipient == Constants::EXECUTED_PLACEHOLDER || *recipient == Pubkey::default() {
             return Err(FreeTunnelError::InvalidRecipient.into());
         }
 
         // Check amount & token index
         let (_, decimal, _) = req_id.get_checked_token(data_account_basic_storage, None)?;
-        req_id.get_checked_amount(decimal)?;
+        let amount = req_id.get_checked_amount(decimal)?;
 
         // Write proposed-lock data
-        DataAccountUtils::create_data_account(
+        let mut proposal = ProposedMint { inner: *recipient, proposer: *account_proposer.key, rent_from_vault: false, pinned_exe_index };
+        proposal.rent_from_vault = RentVault::fund_proposal(program_id, system_program, account_rent_vault, account_proposer, &proposal)?;
+        DataAccountUtils::create_proposal_account(
             program_id,
             system_program,
             account_proposer,
             data_account_proposed_mint,
-            Constants::PREFIX_MINT,
+            ProposalKind::Mint,
             &req_id.data,
-            size_of::<ProposedMint>() + Constants::SIZE_LENGTH,
-            ProposedMint { inner: *recipient },
+            bump_seed,
+            req_id.proposal_header(ProposalKind::Mint, *recipient, amount),
+            proposal,
         )?;
 
-        msg!("TokenMintProposed: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), true)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenMintProposed: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
@@ -76,45 +86,53 @@ impl AtomicMint {
         data_account_executors: &AccountInfo<'a>,
         token_mint: &AccountInfo<'a>,
         account_multisig_owner: &AccountInfo<'a>,
+        sysvar_instructions: Option<&AccountInfo<'a>>,
         req_id: &ReqId,
         signatures: &Vec<[u8; 64]>,
         executors: &Vec<EthAddress>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
-        let recipient = DataAccountUtils::read_account_data::<ProposedMint>(data_account_proposed_mint)?.inner;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        if data_account_proposed_mint.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let recipient = DataAccountUtils::read_proposal_inner(data_account_proposed_mint)?;
         if recipient == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let message = req_id.msg_from_req_signing_message();
-        SignatureUtils::assert_multisig_valid(data_account_executors, &message, signatures, executors)?;
-
-        // Update proposed-mint data
-        DataAccountUtils::write_account_data(
-            data_account_proposed_mint,
-            ProposedMint { inner: Constants::EXECUTED_PLACEHOLDER },
-        )?;
+        SignatureUtils::assert_multisig_valid(data_account_executors, sysvar_instructions, &message, signatures, executors)?;
 
         // Check token match
         let (_, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
         if token_mint.key != &mint_pubkey {
+            msg!("TokenMismatch: expected={}, got={}", mint_pubkey, token_mint.key);
             return Err(FreeTunnelError::TokenMismatch.into());
         }
+        token_ops::assert_is_ata(token_program, token_account_recipient, &recipient, &mint_pubkey)?;
+        token_ops::assert_not_frozen(token_program, token_account_recipient)?;
+
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+
+        // Mark executed only after every check passed, right before the single CPI
+        DataAccountUtils::mark_proposal_executed(data_account_proposed_mint)?;
 
         // Mint to recipient
-        token_ops::assert_is_ata(token_program, token_account_recipient, &recipient, &mint_pubkey)?;
         token_ops::mint_token(
             program_id,
-            token_program,
-            token_mint,
-            token_account_recipient,
-            account_multisig_owner,
-            account_contract_signer,
+            &token_ops::MintAccounts {
+                token_program,
+                token_mint,
+                contract_signer: account_contract_signer,
+                recipient: token_account_recipient,
+                multisig_owner: account_multisig_owner,
+            },
             amount,
+            decimal,
         )?;
 
-        msg!("TokenMintExecuted: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenMintExecuted: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
@@ -124,20 +142,31 @@ impl AtomicMint {
         data_account_proposed_mint: &AccountInfo<'a>,
         account_refund: &AccountInfo<'a>,
         req_id: &ReqId,
+        forced: bool, // skips the expiry check, see `ForceCancel`
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
-        let recipient = DataAccountUtils::read_account_data::<ProposedMint>(data_account_proposed_mint)?.inner;
+        if data_account_proposed_mint.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let recipient = DataAccountUtils::read_proposal_inner(data_account_proposed_mint)?;
         if recipient == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let now = Clock::get()?.unix_timestamp;
-        if now <= (req_id.created_time() + Constants::EXPIRE_EXTRA_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
-
-        Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?;
-        DataAccountUtils::close_account(program_id, data_account_proposed_mint, account_refund)?;
+        if !forced && now <= (req_id.created_time() + Constants::EXPIRE_EXTRA_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
+
+        // Anyone can trigger the cancel, but the rent always returns to whoever paid it.
+        // Proposals created before `proposer` was recorded still require a proposer as refund
+        match DataAccountUtils::read_proposal_data::<ProposedMint>(data_account_proposed_mint) {
+            Ok(ProposedMint { proposer, rent_from_vault, .. }) => {
+                RentVault::assert_rent_refund(program_id, account_refund, rent_from_vault, &proposer)?;
+            }
+            Err(_) => Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?,
+        }
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        DataAccountUtils::close_account(program_id, data_account_proposed_mint, account_refund, Constants::PREFIX_MINT, &req_id.data, false)?;
 
-        msg!("TokenMintCancelled: req_id={}, recipient={}", hex::encode(req_id.data), recipient);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenMintCancelled: req_id={}, recipient={}, event_seq={}", hex::encode(req_id.data), recipient, event_seq);
         Ok(())
     }
 
@@ -150,18 +179,24 @@ impl AtomicMint {
         token_account_proposer: &AccountInfo<'a>,
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_proposed_burn: &AccountInfo<'a>,
+        account_rent_vault: Option<&AccountInfo<'a>>,
+        bump_seed: u8,
         req_id: &ReqId,
+        pinned_exe_index: Option<u64>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
+        Permissions::assert_not_paused(data_account_basic_storage)?;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
         let specific_action = req_id.action() & 0x0f;
         match specific_action {
-            2 => { req_id.assert_mint_side()?; }
-            3 => { req_id.assert_mint_opposite_side()?; }
+            2 => { req_id.assert_mint_side(Constants::HUB_ID)?; }
+            3 => { req_id.assert_mint_opposite_side(Constants::HUB_ID)?; }
             _ => return Err(FreeTunnelError::NotBurnUnlock.into()),
         }
 
         if !account_proposer.is_signer { return Err(ProgramError::MissingRequiredSignature); }
         req_id.checked_created_time()?;
+        Permissions::assert_propose_rate_limit(data_account_basic_storage, account_proposer)?;
         if !data_account_proposed_burn.data_is_empty() { return Err(FreeTunnelError::ReqIdOccupied.into()); }
         if account_proposer.key == &Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::InvalidProposer.into());
@@ -170,24 +205,42 @@ impl AtomicMint {
         // Check amount & token
         let (token_index, decimal, _) = req_id.get_checked_token(data_account_basic_storage, Some(token_account_proposer))?;
         let amount = req_id.get_checked_amount(decimal)?;
+        token_ops::assert_token_account_owner(token_program, token_account_proposer, account_proposer.key)?;
 
         // Write proposed-burn data
-        DataAccountUtils::create_data_account(
+        let mut proposal = ProposedBurn {
+            inner: *account_proposer.key,
+            rent_from_vault: false,
+            token_account: *token_account_proposer.key,
+            pinned_exe_index,
+        };
+        proposal.rent_from_vault = RentVault::fund_proposal(program_id, system_program, account_rent_vault, account_proposer, &proposal)?;
+        DataAccountUtils::create_proposal_account(
             program_id,
             system_program,
             account_proposer,
             data_account_proposed_burn,
-            Constants::PREFIX_BURN,
+            ProposalKind::Burn,
             &req_id.data,
-            size_of::<ProposedBurn>() + Constants::SIZE_LENGTH,
-            ProposedBurn { inner: *account_proposer.key },
+            bump_seed,
+            req_id.proposal_header(ProposalKind::Burn, *account_proposer.key, amount),
+            proposal,
         )?;
 
         // Transfer assets to contract
         token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
-        token_ops::transfer_to_contract(token_program, token_account_proposer, token_account_contract, account_proposer, amount)?;
+        token_ops::assert_not_frozen(token_program, token_account_proposer)?;
+        token_ops::assert_not_frozen(token_program, token_account_contract)?;
+        token_ops::transfer_to_contract(token_program, token_account_contract, token_account_proposer, account_proposer, amount)?;
+
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), true)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, true)?;
+
+        #[cfg(feature = "invariants")]
+        token_ops::assert_vault_invariant(token_program, data_account_basic_storage, token_index, token_account_contract)?;
 
-        msg!("TokenBurnProposed: req_id={}, proposer={}", hex::encode(req_id.data), account_proposer.key);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenBurnProposed: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), account_proposer.key, event_seq);
         Ok(())
     }
 
@@ -200,33 +253,39 @@ impl AtomicMint {
         data_account_proposed_burn: &AccountInfo<'a>,
         data_account_executors: &AccountInfo<'a>,
         token_mint: &AccountInfo<'a>,
+        sysvar_instructions: Option<&AccountInfo<'a>>,
         req_id: &ReqId,
         signatures: &Vec<[u8; 64]>,
         executors: &Vec<EthAddress>,
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
-        let proposer = DataAccountUtils::read_account_data::<ProposedBurn>(data_account_proposed_burn)?.inner;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        if data_account_proposed_burn.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let proposer = DataAccountUtils::read_proposal_inner(data_account_proposed_burn)?;
         if proposer == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let message = req_id.msg_from_req_signing_message();
-        SignatureUtils::assert_multisig_valid(data_account_executors, &message, signatures, executors)?;
+        SignatureUtils::assert_multisig_valid(data_account_executors, sysvar_instructions, &message, signatures, executors)?;
 
-        // Update proposed-burn data
-        DataAccountUtils::write_account_data(
-            data_account_proposed_burn,
-            ProposedBurn { inner: Constants::EXECUTED_PLACEHOLDER },
-        )?;
-
-        // Burn token from contract
+        // Check token match
         let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
         if token_mint.key != &mint_pubkey {
+            msg!("TokenMismatch: expected={}, got={}", mint_pubkey, token_mint.key);
             return Err(FreeTunnelError::TokenMismatch.into());
         }
-
         token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
+        token_ops::assert_vault_balance(token_program, token_account_contract, amount)?;
+
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+
+        // Mark executed only after every check passed, right before the single CPI
+        DataAccountUtils::mark_proposal_executed(data_account_proposed_burn)?;
+
+        // Burn token from contract
         token_ops::burn_token(
             program_id,
             token_program,
@@ -234,12 +293,23 @@ impl AtomicMint {
             account_contract_signer,
             token_account_contract,
             amount,
+            decimal,
         )?;
 
-        msg!("TokenBurnExecuted: req_id={}, proposer={}", hex::encode(req_id.data), proposer);
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenBurnExecuted: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), proposer, event_seq);
         Ok(())
     }
 
+    /// Whether a burn's rent came from the rent vault, and the token account it burned from.
+    /// Proposals created before either was recorded hold only `inner`, and were paid by the proposer
+    pub(crate) fn burn_refund_of(data_account_proposed_burn: &AccountInfo) -> (bool, Option<Pubkey>) {
+        match DataAccountUtils::read_proposal_data::<ProposedBurn>(data_account_proposed_burn) {
+            Ok(proposal) => (proposal.rent_from_vault, Some(proposal.token_account)),
+            Err(_) => (false, None),
+        }
+    }
+
     pub(crate) fn cancel_burn<'a>(
         program_id: &Pubkey,
         token_program: &AccountInfo<'a>,
@@ -250,26 +320,35 @@ impl AtomicMint {
         data_account_proposed_burn: &AccountInfo<'a>,
         account_refund: &AccountInfo<'a>,
         req_id: &ReqId,
+        forced: bool, // skips the expiry check, see `ForceCancel`
     ) -> ProgramResult {
         Self::assert_contract_mode_is_mint(data_account_basic_storage)?;
-        let proposer = DataAccountUtils::read_account_data::<ProposedBurn>(data_account_proposed_burn)?.inner;
+        token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+        if data_account_proposed_burn.data_is_empty() { return Err(FreeTunnelError::ReqIdNotFound.into()); }
+        let proposer = DataAccountUtils::read_proposal_inner(data_account_proposed_burn)?;
         if proposer == Constants::EXECUTED_PLACEHOLDER {
             return Err(FreeTunnelError::ReqIdExecuted.into());
         }
 
         let now = Clock::get()?.unix_timestamp;
-        if now <= (req_id.created_time() + Constants::EXPIRE_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
+        if !forced && now <= (req_id.created_time() + Constants::EXPIRE_PERIOD) as i64 { return Err(FreeTunnelError::WaitUntilExpired.into()); }
 
         // Check amount & token
         let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
         let amount = req_id.get_checked_amount(decimal)?;
 
-        Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false)?;
-        DataAccountUtils::close_account(program_id, data_account_proposed_burn, account_refund)?;
+        let (rent_from_vault, source) = Self::burn_refund_of(data_account_proposed_burn);
+        // Burns are open to any token holder, and `inner` records who proposed it, so the refund
+        // goes back to it whether or not it is a listed proposer
+        RentVault::assert_rent_refund(program_id, account_refund, rent_from_vault, &proposer)?;
+        token_ops::update_pending_proposals(data_account_basic_storage, req_id.token_index(), false)?;
+        token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+        DataAccountUtils::close_account(program_id, data_account_proposed_burn, account_refund, Constants::PREFIX_BURN, &req_id.data, false)?;
 
         // Refund token
-        token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
-        token_ops::assert_is_ata(token_program, token_account_proposer, &proposer, &mint_pubkey)?;
+        token_ops::assert_is_current_or_previous_vault(data_account_basic_storage, token_index, token_account_contract)?;
+        token_ops::assert_vault_balance(token_program, token_account_contract, amount)?;
+        token_ops::assert_refund_token_account(token_program, token_account_proposer, source, &proposer, &mint_pubkey)?;
         token_ops::transfer_from_contract(
             program_id,
             token_program,
@@ -279,7 +358,11 @@ impl AtomicMint {
             amount,
         )?;
 
-        msg!("TokenBurnCancelled: req_id={}, proposer={}", hex::encode(req_id.data), proposer);
+        #[cfg(feature = "invariants")]
+        token_ops::assert_vault_invariant(token_program, data_account_basic_storage, token_index, token_account_contract)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("TokenBurnCancelled: req_id={}, proposer={}, event_seq={}", hex::encode(req_id.data), proposer, event_seq);
         Ok(())
     }
 }
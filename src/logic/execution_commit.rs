@@ -0,0 +1,148 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    keccak,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    constants::Constants,
+    error::FreeTunnelError,
+    logic::req_helpers::ReqId,
+    state::ExecutionCommit,
+    utils::DataAccountUtils,
+};
+
+pub struct ExecutionCommits;
+
+impl ExecutionCommits {
+    /// Commitment a relayer submits for `req_id`: `keccak256(relayer || req_id)`
+    pub fn commitment_of(relayer: &Pubkey, req_id: &ReqId) -> [u8; 32] {
+        keccak::hashv(&[relayer.as_ref(), &req_id.data]).to_bytes()
+    }
+
+    /// Claims the execution of `req_id` for `Constants::EXECUTION_COMMIT_SLOTS` slots. An expired
+    /// commitment is taken over in place, its rent now returns to the new relayer
+    pub(crate) fn commit<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_relayer: &AccountInfo<'a>,
+        data_account_execution_commit: &AccountInfo<'a>,
+        req_id: &ReqId,
+        commitment: &[u8; 32],
+    ) -> ProgramResult {
+        let bump_seed = DataAccountUtils::assert_account_match(
+            program_id,
+            data_account_execution_commit,
+            Constants::PREFIX_EXECUTION_COMMIT,
+            &req_id.data,
+        )?;
+        if *commitment != Self::commitment_of(account_relayer.key, req_id) {
+            return Err(FreeTunnelError::InvalidCommitment.into());
+        }
+
+        let slot = Clock::get()?.slot;
+        if !data_account_execution_commit.data_is_empty() {
+            let existing: ExecutionCommit = DataAccountUtils::read_account_data(data_account_execution_commit)?;
+            if !existing.is_expired(slot) {
+                return Err(FreeTunnelError::ExecutionCommitActive.into());
+            }
+        }
+        let execution_commit = ExecutionCommit { relayer: *account_relayer.key, slot };
+        DataAccountUtils::create_or_update_data_account(
+            program_id,
+            system_program,
+            account_relayer,
+            data_account_execution_commit,
+            Constants::PREFIX_EXECUTION_COMMIT,
+            &req_id.data,
+            bump_seed,
+            Constants::SIZE_EXECUTION_COMMIT + Constants::SIZE_LENGTH,
+            execution_commit,
+        )?;
+
+        msg!(
+            "ExecutionCommitted: req_id={}, relayer={}, exclusive_until_slot={}",
+            hex::encode(req_id.data),
+            account_relayer.key,
+            slot + Constants::EXECUTION_COMMIT_SLOTS,
+        );
+        Ok(())
+    }
+
+    /// Without a commitment, or its account, anyone may execute. Within its window only the committing relayer may
+    pub(crate) fn assert_may_execute(
+        program_id: &Pubkey,
+        data_account_execution_commit: Option<&AccountInfo>,
+        account_relayer: Option<&AccountInfo>,
+        req_id: &ReqId,
+    ) -> ProgramResult {
+        let Some(data_account_execution_commit) = data_account_execution_commit else { return Ok(()) };
+        DataAccountUtils::assert_account_match(
+            program_id,
+            data_account_execution_commit,
+            Constants::PREFIX_EXECUTION_COMMIT,
+            &req_id.data,
+        )?;
+        if data_account_execution_commit.data_is_empty() {
+            return Ok(());
+        }
+        let execution_commit: ExecutionCommit = DataAccountUtils::read_account_data(data_account_execution_commit)?;
+        Self::assert_commit_allows(&execution_commit, account_relayer, Clock::get()?.slot)
+    }
+
+    pub(crate) fn assert_commit_allows(
+        execution_commit: &ExecutionCommit,
+        account_relayer: Option<&AccountInfo>,
+        slot: u64,
+    ) -> ProgramResult {
+        if execution_commit.is_expired(slot) {
+            return Ok(());
+        }
+        match account_relayer {
+            Some(account_relayer) if account_relayer.key == &execution_commit.relayer && account_relayer.is_signer => Ok(()),
+            _ => {
+                msg!(
+                    "ExecutionCommitExclusive: relayer={}, until_slot={}",
+                    execution_commit.relayer,
+                    execution_commit.slot + Constants::EXECUTION_COMMIT_SLOTS,
+                );
+                Err(FreeTunnelError::NotCommittingRelayer.into())
+            }
+        }
+    }
+
+    /// Returns the rent to the committing relayer once its window is over
+    pub(crate) fn close<'a>(
+        program_id: &Pubkey,
+        account_relayer: &AccountInfo<'a>,
+        data_account_execution_commit: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        req_id: &ReqId,
+    ) -> ProgramResult {
+        if data_account_execution_commit.data_is_empty() {
+            return Err(FreeTunnelError::ReqIdNotFound.into());
+        }
+        let execution_commit: ExecutionCommit = DataAccountUtils::read_account_data(data_account_execution_commit)?;
+        if account_relayer.key != &execution_commit.relayer || !account_relayer.is_signer {
+            return Err(FreeTunnelError::NotCommittingRelayer.into());
+        }
+        if !execution_commit.is_expired(Clock::get()?.slot) {
+            return Err(FreeTunnelError::ExecutionCommitActive.into());
+        }
+        DataAccountUtils::close_account(
+            program_id,
+            data_account_execution_commit,
+            account_relayer,
+            Constants::PREFIX_EXECUTION_COMMIT,
+            &req_id.data,
+            false,
+        )?;
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("ExecutionCommitClosed: req_id={}, relayer={}, event_seq={}", hex::encode(req_id.data), account_relayer.key, event_seq);
+        Ok(())
+    }
+}
@@ -1,12 +1,13 @@
+use borsh::BorshDeserialize;
 use solana_program::{
-    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, pubkey::Pubkey,
-    sysvar::Sysvar,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
 use crate::{
     constants::{Constants, EthAddress},
     error::FreeTunnelError,
-    state::{BasicStorage, ExecutorsInfo},
+    state::{BasicStorage, ExecutorsInfo, PendingAdminChange},
     utils::{DataAccountUtils, SignatureUtils},
 };
 
@@ -18,11 +19,14 @@ impl Permissions {
         account_admin: &AccountInfo,
     ) -> ProgramResult {
         let basic_storage: BasicStorage =
-            DataAccountUtils::read_account_data(data_account_basic_storage)?;
-        if &basic_storage.admin != account_admin.key {
-            Err(FreeTunnelError::RequireAdminSigner.into())
+            DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.admin == Constants::RENOUNCED_ADMIN {
+            Err(FreeTunnelError::AdminRenounced.into())
+        } else if &basic_storage.admin != account_admin.key {
+            msg!("AdminMismatch: expected={}, provided={}", basic_storage.admin, account_admin.key);
+            Err(FreeTunnelError::AdminMismatch.into())
         } else if !account_admin.is_signer {
-            Err(FreeTunnelError::RequireAdminSigner.into())
+            Err(FreeTunnelError::AdminSignatureMissing.into())
         } else { Ok(()) }
     }
 
@@ -31,11 +35,366 @@ impl Permissions {
         account_proposer: &AccountInfo,
         check_signer: bool,
     ) -> ProgramResult {
-        let basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
-        if !basic_storage.proposers.contains(account_proposer.key) {
-            Err(FreeTunnelError::RequireProposerSigner.into())
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.proposers.binary_search(account_proposer.key).is_err() {
+            msg!("NotAProposer: provided={}", account_proposer.key);
+            Err(FreeTunnelError::NotAProposer.into())
         } else if check_signer && !account_proposer.is_signer {
-            Err(FreeTunnelError::RequireProposerSigner.into())
+            Err(FreeTunnelError::ProposerSignatureMissing.into())
+        } else { Ok(()) }
+    }
+
+    /// Executes are permissionless while `relayers` is empty. Otherwise one of the listed relayers
+    /// must sign, so only they can land the transaction
+    pub(crate) fn assert_only_relayer(
+        data_account_basic_storage: &AccountInfo,
+        account_relayer: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.relayers.is_empty() {
+            return Ok(());
+        }
+        match account_relayer {
+            Some(account_relayer) if basic_storage.relayers.binary_search(account_relayer.key).is_err() => {
+                msg!("NotARelayer: provided={}", account_relayer.key);
+                Err(FreeTunnelError::NotARelayer.into())
+            }
+            Some(account_relayer) if account_relayer.is_signer => Ok(()),
+            _ => Err(FreeTunnelError::RelayerSignatureMissing.into()),
+        }
+    }
+
+    /// Rejects a proposal if the same proposer proposed less than `min_propose_interval` ago,
+    /// then records the current time. Entries that no longer restrict anyone are pruned first.
+    pub(crate) fn assert_propose_rate_limit(
+        data_account_basic_storage: &AccountInfo,
+        account_proposer: &AccountInfo,
+    ) -> ProgramResult {
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let interval = basic_storage.min_propose_interval;
+        if interval == 0 {
+            return Ok(());
+        }
+
+        let now = Clock::get()?.unix_timestamp as u64;
+        let proposer_last_propose = &mut basic_storage.proposer_last_propose;
+        if let Some((_, last_propose)) = proposer_last_propose.iter().find(|(proposer, _)| proposer == account_proposer.key) {
+            if now < last_propose.saturating_add(interval) {
+                return Err(FreeTunnelError::ProposeTooFrequent.into());
+            }
+        }
+        // Kept in propose order, and only while inside the interval. When still full, the
+        // earliest proposer is dropped rather than rejecting this propose
+        proposer_last_propose.retain(|(proposer, last_propose)| proposer != account_proposer.key && now < last_propose.saturating_add(interval));
+        if proposer_last_propose.len() >= Constants::MAX_RATE_LIMITED_PROPOSERS {
+            proposer_last_propose.remove(0);
+        }
+        proposer_last_propose.push((*account_proposer.key, now));
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)
+    }
+
+    pub(crate) fn set_min_propose_interval(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        interval: u64,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        basic_storage.min_propose_interval = interval;
+        if interval == 0 {
+            basic_storage.proposer_last_propose = vec![];
+        }
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("MinProposeIntervalSet: interval={}, event_seq={}", interval, event_seq);
+        Ok(())
+    }
+
+    pub(crate) fn set_open_lock(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        open_lock: bool,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        basic_storage.open_lock = open_lock;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("OpenLockSet: open_lock={}, event_seq={}", open_lock, event_seq);
+        Ok(())
+    }
+
+    /// Pausing records when proposals stopped, which `DecommissionWithdraw` waits on. Pausing
+    /// again keeps the original time, so it cannot push a decommission back
+    pub(crate) fn set_paused(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        paused: bool,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        basic_storage.paused_since = match (paused, basic_storage.paused_since) {
+            (false, _) => 0,
+            (true, 0) => Clock::get()?.unix_timestamp as u64,
+            (true, paused_since) => paused_since,
+        };
+        let paused_since = basic_storage.paused_since;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("PausedSet: paused={}, paused_since={}, event_seq={}", paused, paused_since, event_seq);
+        Ok(())
+    }
+
+    /// New proposals are rejected while paused. Those already proposed can still be executed or
+    /// cancelled, so they settle before a decommission
+    pub(crate) fn assert_not_paused(data_account_basic_storage: &AccountInfo) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        match basic_storage.paused_since {
+            0 => Ok(()),
+            _ => Err(FreeTunnelError::BridgePaused.into()),
+        }
+    }
+
+    /// Outbound locks are proposer-only unless the admin opened them with `SetOpenLock`
+    pub(crate) fn assert_can_propose_lock(
+        data_account_basic_storage: &AccountInfo,
+        account_proposer: &AccountInfo,
+    ) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        match basic_storage.open_lock {
+            true => Ok(()),
+            false => Self::assert_only_proposer(data_account_basic_storage, account_proposer, true),
+        }
+    }
+
+    /// Only `finalize_admin_renounce` may set `RENOUNCED_ADMIN`, after its delay and checks
+    pub(crate) fn assert_new_admin_valid(new_admin: &Pubkey) -> ProgramResult {
+        match *new_admin == Constants::RENOUNCED_ADMIN {
+            true => Err(FreeTunnelError::NewAdminIsRenounced.into()),
+            false => Ok(()),
+        }
+    }
+
+    /// Decodes the borsh-encoded `new_value` of `parameter_id`, rejecting unknown parameters
+    fn assert_param_value_valid(parameter_id: u8, new_value: &[u8]) -> ProgramResult {
+        let invalid = |_: std::io::Error| ProgramError::InvalidInstructionData;
+        match parameter_id {
+            Constants::PARAM_MIN_PROPOSE_INTERVAL => u64::try_from_slice(new_value).map(|_| ()).map_err(invalid),
+            Constants::PARAM_ADMIN => Self::assert_new_admin_valid(&Pubkey::try_from_slice(new_value).map_err(invalid)?),
+            _ => Err(FreeTunnelError::InvalidParameterId.into()),
+        }
+    }
+
+    pub(crate) fn propose_param_change<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        data_account_pending_change: &AccountInfo<'a>,
+        bump_seed: u8,
+        parameter_id: u8,
+        new_value: Vec<u8>,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        Self::assert_param_value_valid(parameter_id, &new_value)?;
+
+        let effective_at = Clock::get()?.unix_timestamp as u64 + Constants::PARAM_CHANGE_DELAY;
+        msg!(
+            "ParamChangeProposed: parameter_id={}, new_value=0x{}, effective_at={}",
+            parameter_id,
+            hex::encode(&new_value),
+            effective_at
+        );
+        DataAccountUtils::create_data_account(
+            program_id,
+            system_program,
+            account_admin,
+            data_account_pending_change,
+            Constants::PREFIX_PENDING_CHANGE,
+            account_admin.key.as_ref(),
+            bump_seed,
+            Constants::SIZE_PENDING_CHANGE + Constants::SIZE_LENGTH,
+            PendingAdminChange { parameter_id, new_value, effective_at },
+        )
+    }
+
+    pub(crate) fn apply_param_change<'a>(
+        program_id: &Pubkey,
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        data_account_pending_change: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        if data_account_pending_change.data_is_empty() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let PendingAdminChange { parameter_id, new_value, effective_at } =
+            DataAccountUtils::read_account_data(data_account_pending_change)?;
+        if (Clock::get()?.unix_timestamp as u64) < effective_at {
+            return Err(FreeTunnelError::ParamChangeNotEffective.into());
+        }
+
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        match parameter_id {
+            Constants::PARAM_MIN_PROPOSE_INTERVAL => {
+                let interval = u64::try_from_slice(&new_value)?;
+                basic_storage.min_propose_interval = interval;
+                if interval == 0 {
+                    basic_storage.proposer_last_propose = vec![];
+                }
+            }
+            Constants::PARAM_ADMIN => {
+                let new_admin = Pubkey::try_from_slice(&new_value)?;
+                Self::assert_new_admin_valid(&new_admin)?;
+                basic_storage.admin = new_admin;
+            }
+            _ => return Err(FreeTunnelError::InvalidParameterId.into()),
+        }
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        DataAccountUtils::close_account(
+            program_id,
+            data_account_pending_change,
+            account_admin,
+            Constants::PREFIX_PENDING_CHANGE,
+            account_admin.key.as_ref(),
+            false,
+        )?;
+
+        msg!(
+            "ParamChangeApplied: parameter_id={}, new_value=0x{}, event_seq={}",
+            parameter_id,
+            hex::encode(&new_value),
+            event_seq,
+        );
+        Ok(())
+    }
+
+    pub(crate) fn initiate_admin_renounce(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.renounce_after != 0 {
+            return Err(FreeTunnelError::AdminRenouncePending.into());
+        }
+        basic_storage.renounce_after = Clock::get()?.unix_timestamp as u64 + Constants::ADMIN_RENOUNCE_DELAY;
+        let renounce_after = basic_storage.renounce_after;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("AdminRenounceInitiated: renounce_after={}, event_seq={}", renounce_after, event_seq);
+        Ok(())
+    }
+
+    pub(crate) fn cancel_admin_renounce(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.renounce_after == 0 {
+            return Err(FreeTunnelError::AdminRenounceNotPending.into());
+        }
+        basic_storage.renounce_after = 0;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("AdminRenounceCancelled: event_seq={}", event_seq);
+        Ok(())
+    }
+
+    /// A renounce can be finalized once its delay is over, and only while no token has a locked
+    /// balance: without an admin, funds stuck in the vault could never be recovered. `forced`
+    /// skips the locked balance check, for when executors signed off on it
+    pub(crate) fn assert_admin_renounce_ready(basic_storage: &BasicStorage, now: u64, forced: bool) -> ProgramResult {
+        if basic_storage.renounce_after == 0 {
+            return Err(FreeTunnelError::AdminRenounceNotPending.into());
+        } else if now < basic_storage.renounce_after {
+            msg!("AdminRenounceNotReady: renounce_after={}, now={}", basic_storage.renounce_after, now);
+            return Err(FreeTunnelError::AdminRenounceNotReady.into());
+        }
+        if !forced {
+            if let Some((token_index, _)) = basic_storage.locked_balance.iter().find(|&(_, &balance)| balance != 0) {
+                msg!("LockedBalanceMustBeZero: token_index={}", token_index);
+                return Err(FreeTunnelError::LockedBalanceMustBeZero.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Message executors sign to let the admin renounce while tokens are still locked. It names
+    /// the pending `renounce_after`, so signatures for a cancelled renounce cannot be reused
+    pub(crate) fn msg_from_force_admin_renounce(admin: &Pubkey, renounce_after: u64) -> Vec<u8> {
+        let mut body = b"[".to_vec();
+        body.extend_from_slice(Constants::BRIDGE_CHANNEL); body.extend_from_slice(b"]\n");
+        body.extend_from_slice(b"Sign to force admin renounce:\n");
+        body.extend_from_slice(b"Admin: "); body.extend_from_slice(admin.to_string().as_bytes()); body.extend_from_slice(b"\n");
+        body.extend_from_slice(b"Renounce after: "); body.extend_from_slice(renounce_after.to_string().as_bytes());
+
+        let mut msg = Constants::ETH_SIGN_HEADER.to_vec();
+        msg.extend_from_slice(body.len().to_string().as_bytes());
+        msg.extend_from_slice(&body);
+        msg
+    }
+
+    /// Sets the admin to `Constants::RENOUNCED_ADMIN` for good. With `data_account_executors`,
+    /// a threshold of its executors must have signed `msg_from_force_admin_renounce`
+    pub(crate) fn finalize_admin_renounce(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        data_account_executors: Option<&AccountInfo>,
+        signatures: &Vec<[u8; 64]>,
+        executors: &Vec<EthAddress>,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let now = Clock::get()?.unix_timestamp as u64;
+        Self::assert_admin_renounce_ready(&basic_storage, now, data_account_executors.is_some())?;
+        if let Some(data_account_executors) = data_account_executors {
+            let message = Self::msg_from_force_admin_renounce(account_admin.key, basic_storage.renounce_after);
+            SignatureUtils::assert_multisig_valid(data_account_executors, None, &message, signatures, executors)?;
+        }
+
+        // No one can sign for `RENOUNCED_ADMIN`, and `assert_only_admin` rejects it
+        let prev_admin = basic_storage.admin;
+        basic_storage.admin = Constants::RENOUNCED_ADMIN;
+        basic_storage.renounce_after = 0;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("AdminRenounced: prev_admin={}, forced={}, event_seq={}", prev_admin, data_account_executors.is_some(), event_seq);
+        Ok(())
+    }
+
+    pub(crate) fn set_executor_rotation_window(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        min_delay: u64,
+        max_delay: u64,
+    ) -> ProgramResult {
+        Self::assert_only_admin(data_account_basic_storage, account_admin)?;
+        if min_delay < Constants::EXECUTORS_ROTATION_MIN_DELAY_FLOOR || min_delay >= max_delay {
+            return Err(FreeTunnelError::InvalidRotationWindow.into());
+        }
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        basic_storage.rotation_min_delay = min_delay;
+        basic_storage.rotation_max_delay = max_delay;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("ExecutorRotationWindowSet: min_delay={}, max_delay={}, event_seq={}", min_delay, max_delay, event_seq);
+        Ok(())
+    }
+
+    /// `active_since` of new executors must be within `(now + min_delay, now + max_delay)`
+    pub(crate) fn assert_active_since_in_window(
+        active_since: u64,
+        now: i64,
+        min_delay: u64,
+        max_delay: u64,
+    ) -> ProgramResult {
+        if (active_since as i64) <= now + min_delay as i64 {
+            Err(FreeTunnelError::ActiveSinceShouldAfter36h.into())
+        } else if (active_since as i64) >= now + max_delay as i64 {
+            Err(FreeTunnelError::ActiveSinceShouldWithin5d.into())
         } else { Ok(()) }
     }
 
@@ -45,33 +404,155 @@ impl Permissions {
         proposer: &Pubkey,
     ) -> ProgramResult {
         Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
-        if basic_storage.proposers.contains(&proposer) {
-            Err(FreeTunnelError::AlreadyProposer.into())
-        } else if basic_storage.proposers.len() >= Constants::MAX_PROPOSERS {
-            Err(FreeTunnelError::StorageLimitReached.into())
-        } else {
-            basic_storage.proposers.push(proposer.clone());
-            DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
-            msg!("ProposerAdded: {}", proposer);
-            Ok(())
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        match basic_storage.proposers.binary_search(proposer) {
+            Ok(_) => Err(FreeTunnelError::AlreadyProposer.into()),
+            Err(_) if basic_storage.proposers.len() >= basic_storage.max_proposers as usize => {
+                Err(FreeTunnelError::StorageLimitReached.into())
+            }
+            Err(position) => {
+                // Keep proposers sorted so lookups can binary search
+                basic_storage.proposers.insert(position, *proposer);
+                let event_seq = basic_storage.next_event_seq()?;
+                DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+                msg!("ProposerAdded: {}, event_seq={}", proposer, event_seq);
+                Ok(())
+            }
         }
     }
 
+    pub(crate) fn set_max_proposers<'a>(
+        system_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        max: u8,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if basic_storage.proposers.len() > max as usize {
+            return Err(FreeTunnelError::MaxProposersTooLow.into());
+        }
+
+        // Grow the account (paid by the admin) when the new limit exceeds the initial allocation
+        let required_length = Constants::basic_storage_length(max);
+        DataAccountUtils::grow_data_account(system_program, account_admin, data_account_basic_storage, required_length)?;
+
+        basic_storage.max_proposers = max;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("MaxProposersSet: max={}, event_seq={}", max, event_seq);
+        Ok(())
+    }
+
     pub(crate) fn remove_proposer(
         account_admin: &AccountInfo,
         data_account_basic_storage: &AccountInfo,
         proposer: &Pubkey,
     ) -> ProgramResult {
         Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
-        if !basic_storage.proposers.contains(proposer) {
-            Err(FreeTunnelError::NotExistingProposer.into())
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if let Ok(position) = basic_storage.proposers.binary_search(proposer) {
+            basic_storage.proposers.remove(position);
+            let event_seq = basic_storage.next_event_seq()?;
+            DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+            msg!("ProposerRemoved: {}, event_seq={}", proposer, event_seq);
+            Ok(())
         } else {
-            basic_storage.proposers.retain(|p| p != proposer);
+            Err(FreeTunnelError::NotExistingProposer.into())
+        }
+    }
+
+    /// A proposer removes itself, without waiting for the admin
+    pub(crate) fn renounce_proposer(
+        account_proposer: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+    ) -> ProgramResult {
+        Permissions::assert_only_proposer(data_account_basic_storage, account_proposer, true)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let position = basic_storage.proposers.binary_search(account_proposer.key).map_err(|_| FreeTunnelError::NotAProposer)?;
+        basic_storage.proposers.remove(position);
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("ProposerRenounced: {}, event_seq={}", account_proposer.key, event_seq);
+        Ok(())
+    }
+
+    /// Replaces the signing proposer with `new_key` in one step. The list length is unchanged, so
+    /// `max_proposers` cannot be exceeded. The last propose time carries over to the new key, so
+    /// rotating does not skip the rate limit
+    pub(crate) fn rotate_proposer_key(
+        account_proposer: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        new_key: &Pubkey,
+    ) -> ProgramResult {
+        Permissions::assert_only_proposer(data_account_basic_storage, account_proposer, true)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let new_position = match basic_storage.proposers.binary_search(new_key) {
+            Ok(_) => return Err(FreeTunnelError::AlreadyProposer.into()),
+            Err(position) => position,
+        };
+        let old_position = basic_storage.proposers.binary_search(account_proposer.key).map_err(|_| FreeTunnelError::NotAProposer)?;
+        basic_storage.proposers.remove(old_position);
+        // Removing the old key shifts the keys after it down by one
+        let new_position = if old_position < new_position { new_position - 1 } else { new_position };
+        basic_storage.proposers.insert(new_position, *new_key);
+
+        // Both keys may have proposed (`new_key` an open burn or lock), so keep the later time.
+        // Entries are in propose order, so that is the one further back
+        let last_propose = &mut basic_storage.proposer_last_propose;
+        if let Some(old) = last_propose.iter().position(|(proposer, _)| proposer == account_proposer.key) {
+            let later = match last_propose.iter().position(|(proposer, _)| proposer == new_key) {
+                Some(new) => {
+                    last_propose.remove(old.min(new));
+                    old.max(new) - 1
+                }
+                None => old,
+            };
+            last_propose[later].0 = *new_key;
+        }
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("ProposerKeyRotated: old={}, new={}, event_seq={}", account_proposer.key, new_key, event_seq);
+        Ok(())
+    }
+
+    pub(crate) fn add_relayer(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        relayer: &Pubkey,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        match basic_storage.relayers.binary_search(relayer) {
+            Ok(_) => Err(FreeTunnelError::AlreadyRelayer.into()),
+            Err(_) if basic_storage.relayers.len() >= Constants::MAX_RELAYERS => Err(FreeTunnelError::StorageLimitReached.into()),
+            Err(position) => {
+                basic_storage.relayers.insert(position, *relayer);
+                let event_seq = basic_storage.next_event_seq()?;
+                DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+                msg!("RelayerAdded: {}, event_seq={}", relayer, event_seq);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn remove_relayer(
+        account_admin: &AccountInfo,
+        data_account_basic_storage: &AccountInfo,
+        relayer: &Pubkey,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if let Ok(position) = basic_storage.relayers.binary_search(relayer) {
+            basic_storage.relayers.remove(position);
+            // Removing the last relayer makes executes permissionless again
+            let permissionless = basic_storage.relayers.is_empty();
+            let event_seq = basic_storage.next_event_seq()?;
             DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
-            msg!("ProposerRemoved: {}", proposer);
+            msg!("RelayerRemoved: {}, permissionless={}, event_seq={}", relayer, permissionless, event_seq);
             Ok(())
+        } else {
+            Err(FreeTunnelError::NotExistingRelayer.into())
         }
     }
 
@@ -79,13 +560,15 @@ impl Permissions {
         program_id: &Pubkey,
         system_program: &AccountInfo<'a>,
         account_admin: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
         data_account_basic_storage: &AccountInfo,
         data_account_executors: &AccountInfo<'a>,
+        bump_seed: u8,
         executors: &Vec<EthAddress>,
         threshold: u64,
         exe_index: u64,
     ) -> ProgramResult {
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         Self::assert_only_admin(data_account_basic_storage, account_admin)?;
 
         if executors.len() > Constants::MAX_EXECUTORS {
@@ -99,27 +582,44 @@ impl Permissions {
         } else {
             basic_storage.executors_group_length = exe_index + 1;
             SignatureUtils::assert_executors_not_duplicated(executors)?;
+            // Stored sorted so membership checks can binary search
+            let mut sorted_executors = executors.clone();
+            sorted_executors.sort();
+            let executors_list = SignatureUtils::join_address_list(&sorted_executors);
+            let event_seq = basic_storage.next_event_seq()?;
             DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
 
             // Write executors data
             DataAccountUtils::create_data_account(
                 program_id,
                 system_program,
-                account_admin,
+                account_payer,
                 data_account_executors,
                 Constants::PREFIX_EXECUTORS,
                 &exe_index.to_le_bytes(),
+                bump_seed,
                 Constants::SIZE_EXECUTORS_STORAGE + Constants::SIZE_LENGTH,
                 ExecutorsInfo {
                     index: exe_index,
                     threshold,
                     active_since: 1,
                     inactive_after: 0,
-                    executors: executors.clone(),
+                    executors: sorted_executors,
+                    ed25519_executors: Vec::new(),
                 },
             )?;
 
-            msg!("ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}", exe_index, threshold, 1, executors.len());
+            msg!(
+                "ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}, event_seq={}",
+                exe_index,
+                threshold,
+                1,
+                executors.len(),
+                event_seq,
+            );
+            for executor in String::from_utf8_lossy(&executors_list).lines() {
+                msg!("ExecutorListed: index={}, executor={}", exe_index, executor);
+            }
             Ok(())
         }
     }
@@ -131,7 +631,10 @@ impl Permissions {
         data_account_basic_storage: &AccountInfo<'a>,
         data_account_executors: &AccountInfo<'a>,
         data_account_new_executors: &AccountInfo<'a>,
+        new_executors_bump_seed: u8,
+        sysvar_instructions: Option<&AccountInfo<'a>>,
         new_executors: &Vec<EthAddress>,
+        new_ed25519_executors: &Vec<Pubkey>,
         threshold: u64,
         active_since: u64,
         signatures: &Vec<[u8; 64]>,
@@ -140,24 +643,32 @@ impl Permissions {
     ) -> ProgramResult {
         let now = Clock::get()?.unix_timestamp;
 
-        if new_executors.len() > Constants::MAX_EXECUTORS {
+        if new_executors.len() > Constants::MAX_EXECUTORS || new_ed25519_executors.len() > Constants::MAX_EXECUTORS {
             return Err(FreeTunnelError::StorageLimitReached.into());
         } else if threshold == 0 {
             return Err(FreeTunnelError::ThresholdMustBeGreaterThanZero.into());
-        } else if threshold > new_executors.len() as u64 {
+        } else if threshold > (new_executors.len() + new_ed25519_executors.len()) as u64 {
             return Err(FreeTunnelError::NotMeetThreshold.into());
-        } else if (active_since as i64) <= now + 36 * 3600 {
-            return Err(FreeTunnelError::ActiveSinceShouldAfter36h.into());
-        } else if (active_since as i64) >= now + 120 * 3600 {
-            return Err(FreeTunnelError::ActiveSinceShouldWithin5d.into());
         }
+        let BasicStorage { rotation_min_delay, rotation_max_delay, executors_group_length, .. } =
+            DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if exe_index >= executors_group_length {
+            return Err(FreeTunnelError::InvalidExeIndex.into());
+        }
+        Self::assert_active_since_in_window(active_since, now, rotation_min_delay, rotation_max_delay)?;
         SignatureUtils::assert_executors_not_duplicated(new_executors)?;
+        SignatureUtils::assert_executors_not_duplicated(new_ed25519_executors)?;
 
-        // Construct message
+        // Construct message (ed25519 section only present when non-empty, to keep EVM parity)
+        let ed25519_section_length = match new_ed25519_executors.is_empty() {
+            true => 0,
+            false => 19 + 67 * new_ed25519_executors.len(),
+        };
         let mut msg = Constants::ETH_SIGN_HEADER.to_vec();
         let length = 3
             + Constants::BRIDGE_CHANNEL.len()
             + (29 + 43 * new_executors.len())
+            + ed25519_section_length
             + (12 + SignatureUtils::log10(threshold) as usize + 1)
             + (15 + 10)
             + (25 + SignatureUtils::log10(exe_index) as usize + 1);
@@ -165,24 +676,37 @@ impl Permissions {
         msg.extend_from_slice(b"["); msg.extend_from_slice(Constants::BRIDGE_CHANNEL); msg.extend_from_slice(b"]\n");
         msg.extend_from_slice(b"Sign to update executors to:\n");
         msg.extend_from_slice(&SignatureUtils::join_address_list(new_executors));
+        if !new_ed25519_executors.is_empty() {
+            msg.extend_from_slice(b"Ed25519 executors:\n");
+            msg.extend_from_slice(&SignatureUtils::join_pubkey_list(new_ed25519_executors));
+        }
         msg.extend_from_slice(b"Threshold: "); msg.extend_from_slice(threshold.to_string().as_bytes()); msg.extend_from_slice(b"\n");
         msg.extend_from_slice(b"Active since: "); msg.extend_from_slice(active_since.to_string().as_bytes()); msg.extend_from_slice(b"\n");
         msg.extend_from_slice(b"Current executors index: "); msg.extend_from_slice(exe_index.to_string().as_bytes());
 
         // Check multi signatures
-        SignatureUtils::assert_multisig_valid(data_account_executors, &msg, signatures, executors)?;
+        SignatureUtils::assert_multisig_valid(data_account_executors, sysvar_instructions, &msg, signatures, executors)?;
 
-        // Update current executors' inactive_after
-        let mut current_executors_info: ExecutorsInfo = DataAccountUtils::read_account_data(data_account_executors)?;
+        // Update current executors' inactive_after. Groups created before `ed25519_executors` was
+        // added may be too short for the new layout, so the writes below grow them as needed
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let mut current_executors_info = DataAccountUtils::read_executors_info(data_account_executors)?;
         current_executors_info.inactive_after = active_since;
-        DataAccountUtils::write_account_data(data_account_executors, current_executors_info)?;
+        DataAccountUtils::write_account_data_growing(system_program, account_payer, data_account_executors, current_executors_info)?;
+        let event_seq = basic_storage.next_event_seq()?;
+        msg!("ExecutorsDeactivating: exe_index={}, inactive_after={}, event_seq={}", exe_index, active_since, event_seq);
 
-        // Add executors to storage
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        // Add executors to storage, sorted so membership checks can binary search
+        let mut sorted_new_executors = new_executors.clone();
+        sorted_new_executors.sort();
         let new_index = exe_index + 1;
-        if new_index == basic_storage.executors_group_length {
+        let is_new_group = new_index == basic_storage.executors_group_length;
+        if is_new_group {
             basic_storage.executors_group_length = new_index + 1;
-            DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        }
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        if is_new_group {
             DataAccountUtils::create_data_account(
                 program_id,
                 system_program,
@@ -190,45 +714,72 @@ impl Permissions {
                 data_account_new_executors,
                 Constants::PREFIX_EXECUTORS,
                 &new_index.to_le_bytes(),
+                new_executors_bump_seed,
                 Constants::SIZE_EXECUTORS_STORAGE + Constants::SIZE_LENGTH,
                 ExecutorsInfo {
                     index: new_index,
                     threshold,
                     active_since,
                     inactive_after: 0,
-                    executors: new_executors.clone(),
+                    executors: sorted_new_executors,
+                    ed25519_executors: new_ed25519_executors.clone(),
                 },
             )?;
 
-            msg!("ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}", new_index, threshold, active_since, new_executors.len());
+            msg!(
+                "ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}, event_seq={}",
+                new_index,
+                threshold,
+                active_since,
+                new_executors.len(),
+                event_seq,
+            );
             Ok(())
         } else {
-            let ExecutorsInfo {
-                index: _,
-                threshold: next_threshold,
-                active_since: next_active_since,
-                executors: next_executors,
-                ..
-            } = DataAccountUtils::read_account_data(data_account_new_executors)?;
-            if active_since < next_active_since
-                || threshold < next_threshold
-                || !SignatureUtils::cmp_addr_list(new_executors, &next_executors)
-            {
-                return Err(FreeTunnelError::FailedToOverwriteExistingExecutors.into());
-            }
-            DataAccountUtils::write_account_data(
+            let next_executors_info = DataAccountUtils::read_executors_info(data_account_new_executors)?;
+            Self::assert_executors_overwritable(&next_executors_info, threshold, active_since, &sorted_new_executors, new_ed25519_executors)?;
+            DataAccountUtils::write_account_data_growing(
+                system_program,
+                account_payer,
                 data_account_new_executors,
                 ExecutorsInfo {
                     index: new_index,
                     threshold,
                     active_since,
                     inactive_after: 0,
-                    executors: new_executors.clone(),
+                    executors: sorted_new_executors,
+                    ed25519_executors: new_ed25519_executors.clone(),
                 },
             )?;
 
-            msg!("ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}", new_index, threshold, active_since, new_executors.len());
+            msg!(
+                "ExecutorsUpdated: index={}, threshold={}, active_since={}, executors_len={}, event_seq={}",
+                new_index,
+                threshold,
+                active_since,
+                new_executors.len(),
+                event_seq,
+            );
             Ok(())
         }
     }
+
+    /// A pending executors group may only be overwritten by one activating no earlier, with no
+    /// lower threshold, and keeping every secp256k1 and ed25519 executor it already lists
+    pub(crate) fn assert_executors_overwritable(
+        next_executors_info: &ExecutorsInfo,
+        threshold: u64,
+        active_since: u64,
+        new_executors: &[EthAddress],
+        new_ed25519_executors: &[Pubkey],
+    ) -> ProgramResult {
+        if active_since < next_executors_info.active_since
+            || threshold < next_executors_info.threshold
+            || !SignatureUtils::is_superset_of(new_executors, &next_executors_info.executors)
+            || !SignatureUtils::is_superset_of(new_ed25519_executors, &next_executors_info.ed25519_executors)
+        {
+            return Err(FreeTunnelError::FailedToOverwriteExistingExecutors.into());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,71 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    constants::Constants,
+    logic::req_helpers::ReqId,
+    state::ProposerActivity,
+    utils::DataAccountUtils,
+};
+
+pub struct ProposerIndex;
+
+impl ProposerIndex {
+    /// Appends `req_id` to the proposer's index, creating it on first use. No-op without the account
+    pub(crate) fn record<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_proposer: &AccountInfo<'a>,
+        data_account_proposer_index: Option<&AccountInfo<'a>>,
+        req_id: &ReqId,
+    ) -> ProgramResult {
+        let Some(data_account_proposer_index) = data_account_proposer_index else { return Ok(()) };
+        let bump_seed = DataAccountUtils::assert_account_match(
+            program_id,
+            data_account_proposer_index,
+            Constants::PREFIX_PROPOSER_INDEX,
+            account_proposer.key.as_ref(),
+        )?;
+        if data_account_proposer_index.data_is_empty() {
+            let mut activity = ProposerActivity { proposer: *account_proposer.key, req_ids: Vec::new() };
+            activity.record(req_id.data);
+            DataAccountUtils::create_data_account(
+                program_id,
+                system_program,
+                account_proposer,
+                data_account_proposer_index,
+                Constants::PREFIX_PROPOSER_INDEX,
+                account_proposer.key.as_ref(),
+                bump_seed,
+                Constants::SIZE_PROPOSER_ACTIVITY + Constants::SIZE_LENGTH,
+                activity,
+            )
+        } else {
+            let mut activity: ProposerActivity = DataAccountUtils::read_account_data(data_account_proposer_index)?;
+            activity.record(req_id.data);
+            DataAccountUtils::write_account_data(data_account_proposer_index, activity)
+        }
+    }
+
+    /// Removes a finished `req_id` from the index it was recorded in. No-op without the account
+    pub(crate) fn prune(
+        program_id: &Pubkey,
+        data_account_proposer_index: Option<&AccountInfo>,
+        req_id: &ReqId,
+    ) -> ProgramResult {
+        let Some(data_account_proposer_index) = data_account_proposer_index else { return Ok(()) };
+        if data_account_proposer_index.data_is_empty() {
+            return Ok(());
+        }
+        let mut activity: ProposerActivity = DataAccountUtils::read_account_data(data_account_proposer_index)?;
+        DataAccountUtils::assert_account_match(
+            program_id,
+            data_account_proposer_index,
+            Constants::PREFIX_PROPOSER_INDEX,
+            activity.proposer.as_ref(),
+        )?;
+        if activity.prune(&req_id.data) {
+            DataAccountUtils::write_account_data(data_account_proposer_index, activity)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use solana_system_interface::instruction::transfer;
+
+use crate::{
+    constants::Constants,
+    error::FreeTunnelError,
+    logic::permissions::Permissions,
+    utils::DataAccountUtils,
+};
+
+pub struct RentVault;
+
+impl RentVault {
+    pub(crate) fn fund_rent_vault<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        account_rent_vault: &AccountInfo<'a>,
+        lamports: u64,
+    ) -> ProgramResult {
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        DataAccountUtils::assert_account_match(program_id, account_rent_vault, Constants::PREFIX_RENT_VAULT, b"")?;
+        if lamports == 0 { return Err(FreeTunnelError::AmountCannotBeZero.into()); }
+
+        invoke(
+            &transfer(account_admin.key, account_rent_vault.key, lamports),
+            &[account_admin.clone(), account_rent_vault.clone(), system_program.clone()],
+        )?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("RentVaultFunded: lamports={}, balance={}, event_seq={}", lamports, account_rent_vault.lamports(), event_seq);
+        Ok(())
+    }
+
+    /// Forwards the rent of a new proposal account from the rent vault to the payer, right before
+    /// the payer creates it. Returns `false`, leaving the rent to the payer, when no vault is
+    /// passed or the vault cannot cover it while staying rent-exempt itself
+    pub(crate) fn fund_proposal<'a, Data: BorshSerialize>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_rent_vault: Option<&AccountInfo<'a>>,
+        account_payer: &AccountInfo<'a>,
+        proposal: &Data,
+    ) -> Result<bool, ProgramError> {
+        let Some(account_rent_vault) = account_rent_vault else { return Ok(false) };
+        let bump_seed = DataAccountUtils::assert_account_match(program_id, account_rent_vault, Constants::PREFIX_RENT_VAULT, b"")?;
+
+        let rent = Rent::get()?;
+        let proposal_rent = rent.minimum_balance(DataAccountUtils::proposal_account_size(proposal)?);
+        if !Self::can_cover(account_rent_vault.lamports(), rent.minimum_balance(0), proposal_rent) {
+            return Ok(false);
+        }
+
+        invoke_signed(
+            &transfer(account_rent_vault.key, account_payer.key, proposal_rent),
+            &[account_rent_vault.clone(), account_payer.clone(), system_program.clone()],
+            &[&[Constants::PREFIX_RENT_VAULT, &[bump_seed]]],
+        )?;
+        Ok(true)
+    }
+
+    pub(crate) fn can_cover(vault_lamports: u64, vault_rent_exempt: u64, proposal_rent: u64) -> bool {
+        vault_lamports.checked_sub(proposal_rent).is_some_and(|left| left >= vault_rent_exempt)
+    }
+
+    /// The rent of a cancelled proposal returns to the rent vault if it paid, otherwise to `proposer`
+    pub(crate) fn assert_rent_refund(
+        program_id: &Pubkey,
+        account_refund: &AccountInfo,
+        rent_from_vault: bool,
+        proposer: &Pubkey,
+    ) -> ProgramResult {
+        if rent_from_vault {
+            DataAccountUtils::assert_account_match(program_id, account_refund, Constants::PREFIX_RENT_VAULT, b"")
+                .map_err(|_| FreeTunnelError::InvalidRefundAccount)?;
+            Ok(())
+        } else if account_refund.key != proposer {
+            Err(FreeTunnelError::InvalidProposer.into())
+        } else { Ok(()) }
+    }
+}
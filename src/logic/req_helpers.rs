@@ -1,6 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
     program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 use spl_token::state::{Account as TokenAccount, GenericTokenAccount};
@@ -10,7 +10,7 @@ use spl_token_2022::{
 };
 
 use crate::error::FreeTunnelError;
-use crate::state::BasicStorage;
+use crate::state::{BasicStorage, ProposalHeader, ProposalKind};
 use crate::utils::DataAccountUtils;
 use crate::constants::Constants;
 
@@ -63,7 +63,7 @@ impl ReqId {
     ) -> Result<(u8, u8, Pubkey), ProgramError> {
         let BasicStorage {
             tokens, decimals, ..
-        } = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        } = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         let token_index = self.token_index();
         let mint_pubkey = tokens.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
         let decimal = decimals.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
@@ -75,8 +75,9 @@ impl ReqId {
                 if token_account.owner == &spl_token::id() {
                     match TokenAccount::valid_account_data(&token_account_data) {
                         true => {
-                            let expected = TokenAccount::unpack_account_mint_unchecked(&token_account_data);
-                            if *mint_pubkey != *expected {
+                            let actual = TokenAccount::unpack_account_mint_unchecked(&token_account_data);
+                            if *mint_pubkey != *actual {
+                                msg!("TokenMismatch: expected={}, got={}", mint_pubkey, actual);
                                 return Err(FreeTunnelError::TokenMismatch.into());
                             }
                         }
@@ -85,8 +86,9 @@ impl ReqId {
                 } else if token_account.owner == &spl_token_2022::id() {
                     match Token2022Account::valid_account_data(&token_account_data) {
                         true => {
-                            let expected = Token2022Account::unpack_account_mint_unchecked(&token_account_data);
-                            if *mint_pubkey != *expected {
+                            let actual = Token2022Account::unpack_account_mint_unchecked(&token_account_data);
+                            if *mint_pubkey != *actual {
+                                msg!("TokenMismatch: expected={}, got={}", mint_pubkey, actual);
                                 return Err(FreeTunnelError::TokenMismatch.into());
                             }
                         }
@@ -109,9 +111,20 @@ impl ReqId {
         if amount == 0 {
             Err(FreeTunnelError::AmountCannotBeZero.into())
         } else if decimal > 6 {
-            let factor = Self::checked_pow10((decimal - 6) as u32)?;
-            amount = amount.checked_mul(factor).ok_or(FreeTunnelError::ArithmeticOverflow)?;
-            Ok(amount)
+            // Scaled in u128, so only a final amount past `u64::MAX` fails
+            let scaled = 10u128
+                .checked_pow((decimal - 6) as u32)
+                .and_then(|factor| (amount as u128).checked_mul(factor))
+                .and_then(|scaled| u64::try_from(scaled).ok());
+            scaled.ok_or_else(|| {
+                msg!(
+                    "AmountTooLarge: raw_amount={}, decimals={}, max_raw_amount={}",
+                    amount,
+                    decimal,
+                    Self::max_raw_amount(decimal)
+                );
+                FreeTunnelError::ArithmeticOverflow.into()
+            })
         } else if decimal < 6 {
             let factor = Self::checked_pow10((6 - decimal) as u32)?;
             amount /= factor;
@@ -119,6 +132,17 @@ impl ReqId {
         } else { Ok(amount) }
     }
 
+    /// Largest raw (6-decimal) amount a token with `decimal` decimals can bridge in one request
+    pub fn max_raw_amount(decimal: u8) -> u64 {
+        match decimal.checked_sub(6) {
+            None | Some(0) => u64::MAX,
+            Some(exp) => match 10u128.checked_pow(exp as u32) {
+                Some(factor) => (u64::MAX as u128 / factor) as u64,
+                None => 0,
+            },
+        }
+    }
+
     fn checked_pow10(exp: u32) -> Result<u64, ProgramError> {
         let mut value = 1u64;
         for _ in 0..exp {
@@ -159,15 +183,50 @@ impl ReqId {
         }
     }
 
-    pub fn assert_mint_opposite_side(&self) -> ProgramResult {
-        if self.data[16] != Constants::HUB_ID {
+    /// Message executors sign to let the admin cancel a proposal before it expires
+    pub fn msg_from_req_force_cancel_message(&self) -> Vec<u8> {
+        let mut msg = Constants::ETH_SIGN_HEADER.to_vec();
+        let length = 3 + Constants::BRIDGE_CHANNEL.len() + 22 + 66;
+        msg.extend_from_slice(length.to_string().as_bytes());
+        msg.extend_from_slice(b"["); msg.extend_from_slice(Constants::BRIDGE_CHANNEL); msg.extend_from_slice(b"]\n");
+        msg.extend_from_slice(b"Sign to force-cancel:\n");
+        msg.extend_from_slice(b"0x"); msg.extend_from_slice(hex::encode(&self.data).as_bytes());
+        msg
+    }
+
+    /// Header of a new proposal for this req_id, `amount` in the token's smallest unit
+    pub fn proposal_header(&self, kind: ProposalKind, party: Pubkey, amount: u64) -> ProposalHeader {
+        ProposalHeader {
+            discriminator: kind.discriminator(),
+            status: Constants::PROPOSAL_STATUS_PROPOSED,
+            token_index: self.token_index(),
+            created_time: self.created_time(),
+            party,
+            amount,
+        }
+    }
+
+    pub fn assert_mint_opposite_side(&self, hub_id: u8) -> ProgramResult {
+        if self.data[16] != hub_id {
             Err(FreeTunnelError::NotMintOppositeSide.into())
         } else { Ok(()) }
     }
 
-    pub fn assert_mint_side(&self) -> ProgramResult {
-        if self.data[17] != Constants::HUB_ID {
+    pub fn assert_mint_side(&self, hub_id: u8) -> ProgramResult {
+        if self.data[17] != hub_id {
             Err(FreeTunnelError::NotMintSide.into())
         } else { Ok(()) }
     }
+
+    /// Side checks of any proposal this req_id could have on a mint (`mint_or_lock`) or lock
+    /// contract. A burn-mint can be either proposed here as a mint or as a burn
+    pub fn assert_hub_side(&self, mint_or_lock: bool, hub_id: u8) -> ProgramResult {
+        match (mint_or_lock, self.action() & 0x0f) {
+            (true, 1) | (true, 2) => self.assert_mint_side(hub_id),
+            (true, 3) => self.assert_mint_side(hub_id).or_else(|_| self.assert_mint_opposite_side(hub_id)),
+            (false, 1) | (false, 2) => self.assert_mint_opposite_side(hub_id),
+            (false, 3) => Err(FreeTunnelError::NotMintContract.into()),
+            _ => Err(FreeTunnelError::NotLockMint.into()),
+        }
+    }
 }
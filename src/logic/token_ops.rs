@@ -1,18 +1,26 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke,
-    program::invoke_signed, program_error::ProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, program::invoke,
+    program::invoke_signed, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+    sysvar::Sysvar,
 };
 use spl_associated_token_account::{
     get_associated_token_address_with_program_id,
     instruction::create_associated_token_account_idempotent,
 };
-use spl_token::instruction as spl_instruction;
-use spl_token_2022::instruction as spl_2022_instruction;
+use spl_token::{
+    instruction as spl_instruction,
+    state::{Account as TokenAccount, AccountState},
+};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    instruction as spl_2022_instruction,
+    state::{Account as Token2022Account, AccountState as Token2022AccountState},
+};
 
 use crate::{
     constants::Constants,
     error::FreeTunnelError,
-    state::BasicStorage,
+    state::{BasicStorage, ReconciliationView, SparseArray},
     utils::DataAccountUtils,
 };
 
@@ -22,9 +30,13 @@ pub(crate) enum TokenProgramKind {
 }
 
 fn token_program_kind(token_program: &AccountInfo) -> Result<TokenProgramKind, ProgramError> {
-    if token_program.key == &spl_token::id() {
+    token_program_kind_of(token_program.key)
+}
+
+fn token_program_kind_of(token_program_id: &Pubkey) -> Result<TokenProgramKind, ProgramError> {
+    if token_program_id == &spl_token::id() {
         Ok(TokenProgramKind::Token)
-    } else if token_program.key == &spl_token_2022::id() {
+    } else if token_program_id == &spl_token_2022::id() {
         Ok(TokenProgramKind::Token2022)
     } else {
         Err(FreeTunnelError::InvalidTokenProgram.into())
@@ -60,12 +72,49 @@ pub(crate) fn assert_is_ata(
     Ok(())
 }
 
+pub(crate) fn assert_token_account_owner(
+    token_program: &AccountInfo,
+    token_account: &AccountInfo,
+    owner_pubkey: &Pubkey,
+) -> ProgramResult {
+    let token_account_data = token_account.data.borrow();
+    let owner = match token_program_kind(token_program)? {
+        TokenProgramKind::Token => TokenAccount::unpack(&token_account_data).map(|account| account.owner),
+        TokenProgramKind::Token2022 => StateWithExtensions::<Token2022Account>::unpack(&token_account_data)
+            .map(|account| account.base.owner),
+    }.map_err(|_| FreeTunnelError::InvalidTokenAccount)?;
+    if &owner != owner_pubkey {
+        msg!("TokenAccountOwnerMismatch: token_account={}, expected={}, got={}", token_account.key, owner_pubkey, owner);
+        return Err(FreeTunnelError::TokenAccountOwnerMismatch.into());
+    }
+    Ok(())
+}
+
+/// Cancelled deposits go back to the token account recorded at propose time. Proposals created
+/// before it was recorded (`source` is `None`) refund to the proposer's ATA
+pub(crate) fn assert_refund_token_account(
+    token_program: &AccountInfo,
+    token_account_proposer: &AccountInfo,
+    source: Option<Pubkey>,
+    proposer: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> ProgramResult {
+    match source {
+        Some(source) if token_account_proposer.key != &source => {
+            msg!("InvalidTokenAccount: expected={}, got={}", source, token_account_proposer.key);
+            Err(FreeTunnelError::InvalidTokenAccount.into())
+        }
+        Some(_) => Ok(()),
+        None => assert_is_ata(token_program, token_account_proposer, proposer, mint_pubkey),
+    }
+}
+
 pub(crate) fn assert_is_contract_ata<'a>(
     data_account_basic_storage: &AccountInfo<'a>,
     token_index: u8,
     token_account_contract: &AccountInfo<'a>,
 ) -> ProgramResult {
-    let basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+    let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
     let expected = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
     if token_account_contract.key != expected {
         return Err(FreeTunnelError::InvalidTokenAccount.into());
@@ -73,15 +122,266 @@ pub(crate) fn assert_is_contract_ata<'a>(
     Ok(())
 }
 
-pub(crate) fn create_token_account_contract<'a>(
-    system_program: &AccountInfo<'a>,
-    token_program: &AccountInfo<'a>,
-    payer: &AccountInfo<'a>,
+/// Checks `token_program` is the one `token_index` was added with, so a mismatched program fails
+/// here rather than with an owner error inside the CPI. Tokens added before the program was
+/// recorded accept either token program, as they did then
+pub(crate) fn assert_token_program_of(
+    data_account_basic_storage: &AccountInfo,
+    token_index: u8,
+    token_program: &AccountInfo,
+) -> ProgramResult {
+    let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+    let expected = match (basic_storage.token_programs.get(token_index), basic_storage.tokens.get(token_index)) {
+        (Some(expected), _) => expected,
+        (None, Some(_)) => return token_program_kind(token_program).map(|_| ()),
+        (None, None) => return Err(FreeTunnelError::TokenIndexNonExistent.into()),
+    };
+    if token_program.key != expected {
+        msg!("TokenProgramMismatch: token_index={}, expected={}, provided={}", token_index, expected, token_program.key);
+        return Err(FreeTunnelError::TokenProgramMismatch.into());
+    }
+    Ok(())
+}
+
+/// Returns the mint last removed from `token_index`, if any. Reusing the index for a different
+/// mint needs `confirm_reuse`, as executors signed its past req_ids against the retired mint
+pub(crate) fn assert_token_reuse_confirmed(
+    retired_tokens: &SparseArray<Pubkey>,
+    token_index: u8,
+    token_mint: &Pubkey,
+    confirm_reuse: bool,
+) -> Result<Option<Pubkey>, ProgramError> {
+    match retired_tokens.get(token_index) {
+        Some(retired_mint) if retired_mint != token_mint && !confirm_reuse => {
+            msg!("TokenIndexPreviouslyUsed: token_index={}, retired_mint={}, token_mint={}", token_index, retired_mint, token_mint);
+            Err(FreeTunnelError::TokenIndexPreviouslyUsed.into())
+        }
+        retired_mint => Ok(retired_mint.copied()),
+    }
+}
+
+/// Like `assert_is_contract_ata`, but also accepts the vault replaced by `RotateVault` until
+/// its grace period ends, so refunds built against the old vault keep working
+pub(crate) fn assert_is_current_or_previous_vault<'a>(
+    data_account_basic_storage: &AccountInfo<'a>,
+    token_index: u8,
     token_account_contract: &AccountInfo<'a>,
-    account_contract_signer: &AccountInfo<'a>,
-    token_mint: &AccountInfo<'a>,
-    rent_sysvar: &AccountInfo<'a>,
-) -> Result<(), ProgramError> {
+) -> ProgramResult {
+    let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+    let expected = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+    if token_account_contract.key == expected {
+        return Ok(());
+    }
+    match basic_storage.previous_vaults.get(token_index) {
+        Some((previous, grace_end)) if token_account_contract.key == previous => {
+            let now = Clock::get()?.unix_timestamp;
+            if now <= *grace_end as i64 {
+                Ok(())
+            } else {
+                Err(FreeTunnelError::InvalidTokenAccount.into())
+            }
+        }
+        _ => Err(FreeTunnelError::InvalidTokenAccount.into()),
+    }
+}
+
+/// Checks a non-ATA token account can serve as a vault: owned by the contract signer, same mint
+pub(crate) fn assert_is_contract_token_account(
+    token_program: &AccountInfo,
+    token_account: &AccountInfo,
+    contract_signer: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> ProgramResult {
+    if token_account.owner != token_program.key {
+        return Err(FreeTunnelError::InvalidNewVault.into());
+    }
+    let token_account_data = token_account.data.borrow();
+    let (owner, mint) = match token_program_kind(token_program)? {
+        TokenProgramKind::Token => {
+            let account = TokenAccount::unpack(&token_account_data)?;
+            (account.owner, account.mint)
+        }
+        TokenProgramKind::Token2022 => {
+            let account = StateWithExtensions::<Token2022Account>::unpack(&token_account_data)?.base;
+            (account.owner, account.mint)
+        }
+    };
+    if &owner != contract_signer || &mint != mint_pubkey {
+        return Err(FreeTunnelError::InvalidNewVault.into());
+    }
+    Ok(())
+}
+
+pub(crate) fn update_pending_proposals(
+    data_account_basic_storage: &AccountInfo,
+    token_index: u8,
+    is_add: bool,
+) -> ProgramResult {
+    let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+    let pending = basic_storage.pending_proposals.get_mut(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+    *pending = match is_add {
+        true => pending.checked_add(1),
+        false => pending.checked_sub(1),
+    }.ok_or(FreeTunnelError::ArithmeticOverflow)?;
+    DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)
+}
+
+/// Tracks the amount the vault holds for in-flight proposals outside `locked_balance`. Proposals
+/// created before it was tracked were never added, so removing saturates at zero
+pub(crate) fn update_pending_deposits(
+    data_account_basic_storage: &AccountInfo,
+    token_index: u8,
+    amount: u64,
+    is_add: bool,
+) -> ProgramResult {
+    let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+    let pending = basic_storage.pending_deposits.get_mut(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+    *pending = match is_add {
+        true => pending.checked_add(amount).ok_or(FreeTunnelError::ArithmeticOverflow)?,
+        false => pending.saturating_sub(amount),
+    };
+    DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)
+}
+
+pub(crate) fn token_account_balance(
+    token_program: &AccountInfo,
+    token_account: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    balance_of_kind(token_program_kind(token_program)?, token_account)
+}
+
+/// Same as `token_account_balance`, with the token program taken from the account owner
+pub(crate) fn token_account_balance_by_owner(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let kind = token_program_kind_of(token_account.owner).map_err(|_| FreeTunnelError::InvalidTokenAccount)?;
+    balance_of_kind(kind, token_account)
+}
+
+fn balance_of_kind(kind: TokenProgramKind, token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let token_account_data = token_account.data.borrow();
+    Ok(match kind {
+        TokenProgramKind::Token => TokenAccount::unpack(&token_account_data)?.amount,
+        TokenProgramKind::Token2022 => StateWithExtensions::<Token2022Account>::unpack(&token_account_data)?.base.amount,
+    })
+}
+
+/// Compares the stored balances of `token_index` with the actual `vault_balance`
+pub(crate) fn reconciliation(
+    basic_storage: &BasicStorage,
+    token_index: u8,
+    vault_balance: u64,
+) -> Result<ReconciliationView, ProgramError> {
+    let locked_balance = *basic_storage.locked_balance.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+    let pending_deposits = basic_storage.pending_deposits.get(token_index).copied().unwrap_or(0);
+    Ok(ReconciliationView {
+        token_index,
+        vault: *basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?,
+        locked_balance,
+        vault_balance,
+        pending_deposits,
+        drift: vault_balance as i128 - locked_balance as i128 - pending_deposits as i128,
+    })
+}
+
+/// Tripwire of the `invariants` feature: the current vault must hold at least the locked balance
+/// of its token. Previous vaults are skipped, the locked balance has moved to the current one
+#[cfg(feature = "invariants")]
+pub(crate) fn assert_vault_invariant(
+    token_program: &AccountInfo,
+    data_account_basic_storage: &AccountInfo,
+    token_index: u8,
+    token_account_contract: &AccountInfo,
+) -> ProgramResult {
+    let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+    if basic_storage.vaults.get(token_index) != Some(token_account_contract.key) {
+        return Ok(());
+    }
+    let locked_balance = basic_storage.locked_balance.get(token_index).copied().unwrap_or(0);
+    let vault_balance = token_account_balance(token_program, token_account_contract)?;
+    if vault_balance < locked_balance {
+        msg!(
+            "InvariantViolated: token_index={}, vault={}, vault_balance={}, locked_balance={}",
+            token_index, token_account_contract.key, vault_balance, locked_balance,
+        );
+        return Err(FreeTunnelError::InvariantViolated.into());
+    }
+    Ok(())
+}
+
+pub(crate) fn assert_vault_balance(
+    token_program: &AccountInfo,
+    token_account_contract: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    let balance = token_account_balance(token_program, token_account_contract)?;
+    if balance < amount {
+        msg!("Error: vault balance {} is less than required amount {}", balance, amount);
+        return Err(FreeTunnelError::VaultBalanceInsufficient.into());
+    }
+    Ok(())
+}
+
+/// Splits an unlock of `amount` into `(from_previous, from_current)`. The previous vault, when
+/// given, is drained first and the current vault must hold the rest
+pub(crate) fn split_unlock_amount(
+    token_program: &AccountInfo,
+    token_account_previous_vault: Option<&AccountInfo>,
+    token_account_contract: &AccountInfo,
+    amount: u64,
+) -> Result<(u64, u64), ProgramError> {
+    let from_previous = match token_account_previous_vault {
+        Some(previous_vault) => token_account_balance(token_program, previous_vault)?.min(amount),
+        None => 0,
+    };
+    let from_current = amount - from_previous;
+    assert_vault_balance(token_program, token_account_contract, from_current)?;
+    Ok((from_previous, from_current))
+}
+
+/// Uninitialized accounts pass here and are left for the token program to reject
+pub(crate) fn assert_not_frozen(
+    token_program: &AccountInfo,
+    token_account: &AccountInfo,
+) -> ProgramResult {
+    let token_account_data = token_account.data.borrow();
+    let frozen = match token_program_kind(token_program)? {
+        TokenProgramKind::Token => TokenAccount::unpack(&token_account_data)
+            .map(|account| account.state == AccountState::Frozen),
+        TokenProgramKind::Token2022 => StateWithExtensions::<Token2022Account>::unpack(&token_account_data)
+            .map(|account| account.base.state == Token2022AccountState::Frozen),
+    };
+    if let Ok(true) = frozen {
+        msg!("Error: token account {} is frozen", token_account.key);
+        return Err(FreeTunnelError::TokenAccountFrozen.into());
+    }
+    Ok(())
+}
+
+/// Accounts of the associated token program instruction creating the contract's vault
+pub(crate) struct VaultCreationAccounts<'b, 'a> {
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub payer: &'b AccountInfo<'a>,
+    pub token_account_contract: &'b AccountInfo<'a>,
+    pub account_contract_signer: &'b AccountInfo<'a>,
+    pub token_mint: &'b AccountInfo<'a>,
+    pub rent_sysvar: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+}
+
+pub(crate) fn create_token_account_contract(accounts: &VaultCreationAccounts) -> Result<(), ProgramError> {
+    let VaultCreationAccounts {
+        system_program,
+        token_program,
+        payer,
+        token_account_contract,
+        account_contract_signer,
+        token_mint,
+        rent_sysvar,
+        associated_token_program,
+    } = *accounts;
+    if associated_token_program.key != &spl_associated_token_account::id() {
+        return Err(FreeTunnelError::InvalidAssociatedTokenProgram.into());
+    }
     assert_is_ata(token_program, token_account_contract, account_contract_signer.key, token_mint.key)?;
 
     let ix = create_associated_token_account_idempotent(
@@ -101,12 +401,59 @@ pub(crate) fn create_token_account_contract<'a>(
             account_contract_signer.clone(),
             token_mint.clone(),
             rent_sysvar.clone(),
+            associated_token_program.clone(),
         ],
     )?;
 
     Ok(())
 }
 
+/// Same as `create_token_account_contract`, but the payer may also be the contract signer itself,
+/// which then pays the ATA rent from its own lamports by signing with its PDA seeds
+pub(crate) fn create_token_account_contract_signed(program_id: &Pubkey, accounts: &VaultCreationAccounts) -> Result<(), ProgramError> {
+    let VaultCreationAccounts {
+        system_program,
+        token_program,
+        payer,
+        token_account_contract,
+        account_contract_signer,
+        token_mint,
+        rent_sysvar,
+        associated_token_program,
+    } = *accounts;
+    if payer.key != account_contract_signer.key {
+        return create_token_account_contract(accounts);
+    }
+    let bump_seed = assert_contract_signer(program_id, account_contract_signer)?;
+    if associated_token_program.key != &spl_associated_token_account::id() {
+        return Err(FreeTunnelError::InvalidAssociatedTokenProgram.into());
+    }
+    assert_is_ata(token_program, token_account_contract, account_contract_signer.key, token_mint.key)?;
+
+    let ix = create_associated_token_account_idempotent(
+        account_contract_signer.key,
+        account_contract_signer.key,
+        token_mint.key,
+        token_program.key,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            system_program.clone(),
+            token_program.clone(),
+            token_account_contract.clone(),
+            account_contract_signer.clone(),
+            token_mint.clone(),
+            rent_sysvar.clone(),
+            associated_token_program.clone(),
+        ],
+        &[&[Constants::CONTRACT_SIGNER, &[bump_seed]]],
+    )?;
+
+    Ok(())
+}
+
 pub(crate) fn transfer_to_contract<'a>(
     token_program: &AccountInfo<'a>,
     contract: &AccountInfo<'a>,
@@ -167,32 +514,41 @@ pub(crate) fn transfer_from_contract<'a>(
     Ok(())
 }
 
-pub(crate) fn mint_token<'a>(
+/// Accounts of a `mint_to_checked` signed by the contract signer as a multisig member
+pub(crate) struct MintAccounts<'b, 'a> {
+    pub token_program: &'b AccountInfo<'a>,
+    pub token_mint: &'b AccountInfo<'a>,
+    pub contract_signer: &'b AccountInfo<'a>,
+    pub recipient: &'b AccountInfo<'a>,
+    pub multisig_owner: &'b AccountInfo<'a>,
+}
+
+pub(crate) fn mint_token(
     program_id: &Pubkey,
-    token_program: &AccountInfo<'a>,
-    token_mint: &AccountInfo<'a>,
-    contract_signer: &AccountInfo<'a>,
-    recipient: &AccountInfo<'a>,
-    multisig_owner: &AccountInfo<'a>,
+    accounts: &MintAccounts,
     amount: u64,
+    decimals: u8, // registered decimals, checked by the token program against the mint
 ) -> ProgramResult {
+    let MintAccounts { token_program, token_mint, contract_signer, recipient, multisig_owner } = *accounts;
     let bump_seed = assert_contract_signer(program_id, contract_signer)?;
     let ix = match token_program_kind(token_program)? {
-        TokenProgramKind::Token => spl_instruction::mint_to(
+        TokenProgramKind::Token => spl_instruction::mint_to_checked(
             token_program.key,
             token_mint.key,
             recipient.key,
             multisig_owner.key,
             &[contract_signer.key],
             amount,
+            decimals,
         )?,
-        TokenProgramKind::Token2022 => spl_2022_instruction::mint_to(
+        TokenProgramKind::Token2022 => spl_2022_instruction::mint_to_checked(
             token_program.key,
             token_mint.key,
             recipient.key,
             multisig_owner.key,
             &[contract_signer.key],
             amount,
+            decimals,
         )?,
     };
     invoke_signed(
@@ -215,24 +571,27 @@ pub(crate) fn burn_token<'a>(
     contract_signer: &AccountInfo<'a>,
     contract: &AccountInfo<'a>,
     amount: u64,
+    decimals: u8, // registered decimals, checked by the token program against the mint
 ) -> ProgramResult {
     let bump_seed = assert_contract_signer(program_id, contract_signer)?;
     let ix = match token_program_kind(token_program)? {
-        TokenProgramKind::Token => spl_instruction::burn(
+        TokenProgramKind::Token => spl_instruction::burn_checked(
             token_program.key,
             contract.key,
             token_mint.key,
             contract_signer.key,
             &[],
             amount,
+            decimals,
         )?,
-        TokenProgramKind::Token2022 => spl_2022_instruction::burn(
+        TokenProgramKind::Token2022 => spl_2022_instruction::burn_checked(
             token_program.key,
             contract.key,
             token_mint.key,
             contract_signer.key,
             &[],
             amount,
+            decimals,
         )?,
     };
     invoke_signed(&ix, &[contract.clone(), token_mint.clone(), contract_signer.clone()], &[&[Constants::CONTRACT_SIGNER, &[bump_seed]]])?;
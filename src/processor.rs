@@ -1,29 +1,46 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    program::set_return_data,
+    program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
 use solana_sdk_ids;
 
 use spl_token::state::{Account as TokenAccount, Mint};
-use spl_token_2022::state::{Account as Token2022Account, Mint as Token2022Mint};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account as Token2022Account, Mint as Token2022Mint},
+};
 
 use crate::{
-    constants::Constants,
+    constants::{Constants, EthAddress},
     error::FreeTunnelError,
     instruction::FreeTunnelInstruction,
     logic::{
-        atomic_lock::AtomicLock,
+        atomic_lock::{AtomicLock, DecommissionAccounts},
         atomic_mint::AtomicMint,
+        execution_commit::ExecutionCommits,
         permissions::Permissions,
+        proposer_index::ProposerIndex,
+        rent_vault::RentVault,
+        req_helpers::ReqId,
         token_ops,
     },
-    state::{BasicStorage, SparseArray},
-    utils::DataAccountUtils,
+    state::{
+        BasicStorage, ConfigView, ExecutorsInfo, ExecutorsInfoView, ProposalHeader, ProposalKind, ProposedMint,
+        ProposedUnlock, ProposerActivity, SignaturesView, SparseArray, TokenInfoView,
+    },
+    utils::{DataAccountUtils, SignatureUtils},
 };
 
+/// `(sysvar_instructions, data_account_proposer_index, token_account_previous_vault)` trailing an `ExecuteUnlock`
+type TrailingUnlockAccounts<'b, 'a> = (Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>);
+
 pub struct Processor;
 
 impl Processor {
@@ -33,6 +50,12 @@ impl Processor {
         instruction_data: &[u8],
     ) -> ProgramResult {
         let instruction = FreeTunnelInstruction::unpack(instruction_data)?;
+        if let Some(index) = instruction.required_signer() {
+            let account_signer = accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if !account_signer.is_signer {
+                return Err(FreeTunnelError::RequireSigner.into());
+            }
+        }
         let accounts_iter = &mut accounts.iter();
 
         match instruction {
@@ -41,41 +64,139 @@ impl Processor {
                 executors,
                 threshold,
                 exe_index,
+                max_proposers,
             } => {
+                Self::assert_initial_executors(&executors, threshold, exe_index)?;
                 let system_program = next_account_info(accounts_iter)?;
                 let account_admin = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_executors = next_account_info(accounts_iter)?;
+                let account_payer = next_account_info(accounts_iter).unwrap_or(account_admin);
                 Self::assert_system_program(system_program)?;
-                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
-                DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
 
-                // Create data accounts and write
-                DataAccountUtils::create_data_account(
+                Self::process_initialize_basic_storage(
                     program_id,
                     system_program,
                     account_admin,
+                    account_payer,
                     data_account_basic_storage,
-                    Constants::BASIC_STORAGE,
-                    b"",
-                    Constants::SIZE_BASIC_STORAGE + Constants::SIZE_LENGTH,
-                    BasicStorage {
-                        mint_or_lock: is_mint_contract,
-                        admin: *account_admin.key,
-                        proposers: Vec::new(),
-                        executors_group_length: 0,
-                        tokens: SparseArray::default(),
-                        vaults: SparseArray::default(),
-                        decimals: SparseArray::default(),
-                        locked_balance: SparseArray::default(),
-                    },
+                    is_mint_contract,
+                    max_proposers,
                 )?;
+                Self::process_initialize_executors(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    account_payer,
+                    data_account_basic_storage,
+                    data_account_executors,
+                    &executors,
+                    threshold,
+                    exe_index,
+                )
+            }
+            FreeTunnelInstruction::InitializeWithTokens {
+                is_mint_contract,
+                executors,
+                threshold,
+                exe_index,
+                tokens,
+            } => {
+                Self::assert_initial_executors(&executors, threshold, exe_index)?;
+                if tokens.len() > Constants::MAX_TOKENS {
+                    return Err(FreeTunnelError::StorageLimitReached.into());
+                }
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_executors = next_account_info(accounts_iter)?;
+                let account_contract_signer = next_account_info(accounts_iter)?;
+                let rent_sysvar = next_account_info(accounts_iter)?;
+                let associated_token_program = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
 
-                // Process internal logic
-                Permissions::init_executors(
+                Self::process_initialize_basic_storage(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    account_admin,
+                    data_account_basic_storage,
+                    is_mint_contract,
+                    0,
+                )?;
+                Self::process_initialize_executors(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    account_admin,
+                    data_account_basic_storage,
+                    data_account_executors,
+                    &executors,
+                    threshold,
+                    exe_index,
+                )?;
+                for (token_index, mint, decimals) in tokens {
+                    let token_program = next_account_info(accounts_iter)?;
+                    let token_mint = next_account_info(accounts_iter)?;
+                    let token_account_contract = next_account_info(accounts_iter)?;
+                    Self::assert_token_program(token_program)?;
+                    Self::assert_token_mint_valid(token_mint, token_program)?;
+                    if token_mint.key != &mint || Self::mint_decimals(token_mint, token_program)? != decimals {
+                        msg!("TokenMismatch: token_index={}, expected={} ({} decimals), got={}", token_index, mint, decimals, token_mint.key);
+                        return Err(FreeTunnelError::InvalidTokenMint.into());
+                    }
+                    Self::process_add_token(
+                        program_id,
+                        system_program,
+                        token_program,
+                        account_admin,
+                        token_account_contract,
+                        account_contract_signer,
+                        data_account_basic_storage,
+                        token_mint,
+                        rent_sysvar,
+                        associated_token_program,
+                        token_index,
+                        Constants::ETH_ZERO_ADDRESS,
+                        false,
+                        false,
+                    )?;
+                }
+                Ok(())
+            }
+            FreeTunnelInstruction::InitializeBasicStorage { is_mint_contract, max_proposers } => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let account_payer = next_account_info(accounts_iter).unwrap_or(account_admin);
+                Self::assert_system_program(system_program)?;
+                Self::process_initialize_basic_storage(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    account_payer,
+                    data_account_basic_storage,
+                    is_mint_contract,
+                    max_proposers,
+                )
+            }
+            FreeTunnelInstruction::InitializeExecutors { executors, threshold, exe_index } => {
+                // The first group must be 0, otherwise the groups before it could never be created
+                if exe_index != 0 {
+                    return Err(FreeTunnelError::InvalidExeIndex.into());
+                }
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_executors = next_account_info(accounts_iter)?;
+                let account_payer = next_account_info(accounts_iter).unwrap_or(account_admin);
+                Self::assert_system_program(system_program)?;
+                Self::process_initialize_executors(
                     program_id,
                     system_program,
                     account_admin,
+                    account_payer,
                     data_account_basic_storage,
                     data_account_executors,
                     &executors,
@@ -93,6 +214,32 @@ impl Processor {
                     &new_admin,
                 )
             }
+            FreeTunnelInstruction::InitiateAdminRenounce => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::initiate_admin_renounce(account_admin, data_account_basic_storage)
+            }
+            FreeTunnelInstruction::CancelAdminRenounce => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::cancel_admin_renounce(account_admin, data_account_basic_storage)
+            }
+            FreeTunnelInstruction::FinalizeAdminRenounce { force, signatures, executors, exe_index } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                let data_account_executors = match force {
+                    true => {
+                        let data_account_executors = next_account_info(accounts_iter)?;
+                        DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+                        Some(data_account_executors)
+                    }
+                    false => None,
+                };
+                Permissions::finalize_admin_renounce(account_admin, data_account_basic_storage, data_account_executors, &signatures, &executors)
+            }
             FreeTunnelInstruction::AddProposer { new_proposer } => {
                 let account_admin = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
@@ -105,22 +252,70 @@ impl Processor {
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
                 Permissions::remove_proposer(account_admin, data_account_basic_storage, &proposer)
             }
+            FreeTunnelInstruction::RenounceProposer => {
+                let account_proposer = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::renounce_proposer(account_proposer, data_account_basic_storage)
+            }
+            FreeTunnelInstruction::RotateProposerKey { new_key } => {
+                let account_proposer = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::rotate_proposer_key(account_proposer, data_account_basic_storage, &new_key)
+            }
+            FreeTunnelInstruction::AddRelayer { relayer } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::add_relayer(account_admin, data_account_basic_storage, &relayer)
+            }
+            FreeTunnelInstruction::RemoveRelayer { relayer } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::remove_relayer(account_admin, data_account_basic_storage, &relayer)
+            }
+            FreeTunnelInstruction::CommitExecution { req_id, commitment } => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_relayer = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                ExecutionCommits::commit(
+                    program_id,
+                    system_program,
+                    account_relayer,
+                    data_account_execution_commit,
+                    &req_id,
+                    &commitment,
+                )
+            }
+            FreeTunnelInstruction::CloseExecutionCommit { req_id } => {
+                let account_relayer = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                ExecutionCommits::close(program_id, account_relayer, data_account_execution_commit, data_account_basic_storage, &req_id)
+            }
             FreeTunnelInstruction::UpdateExecutors {
                 new_executors,
+                new_ed25519_executors,
                 threshold,
                 active_since,
                 signatures,
                 executors,
                 exe_index,
             } => {
+                Self::assert_compute_budget(signatures.len())?;
                 let system_program = next_account_info(accounts_iter)?;
                 let account_payer: &AccountInfo<'_> = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_executors = next_account_info(accounts_iter)?;
                 let data_account_new_executors = next_account_info(accounts_iter)?;
+                let sysvar_instructions = next_account_info(accounts_iter).ok();
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
-                DataAccountUtils::assert_account_match(program_id, data_account_new_executors, Constants::PREFIX_EXECUTORS, &(exe_index + 1).to_le_bytes())?;
+                let new_executors_bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_new_executors, Constants::PREFIX_EXECUTORS, &(exe_index + 1).to_le_bytes())?;
                 Permissions::update_executors(
                     program_id,
                     system_program,
@@ -128,7 +323,10 @@ impl Processor {
                     data_account_basic_storage,
                     data_account_executors,
                     data_account_new_executors,
+                    new_executors_bump_seed,
+                    sysvar_instructions,
                     &new_executors,
+                    &new_ed25519_executors,
                     threshold,
                     active_since,
                     &signatures,
@@ -138,6 +336,10 @@ impl Processor {
             }
             FreeTunnelInstruction::AddToken {
                 token_index,
+                peer_address,
+                is_token_2022,
+                confirm_reuse,
+                allow_high_decimals,
             } => {
                 let system_program = next_account_info(accounts_iter)?;
                 let token_program = next_account_info(accounts_iter)?;
@@ -147,13 +349,18 @@ impl Processor {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let token_mint = next_account_info(accounts_iter)?;
                 let rent_sysvar = next_account_info(accounts_iter)?;
+                let associated_token_program = next_account_info(accounts_iter)?;
                 Self::assert_system_program(system_program)?;
                 Self::assert_token_program(token_program)?;
+                if is_token_2022 != (token_program.key == &spl_token_2022::id()) {
+                    return Err(FreeTunnelError::InvalidTokenProgram.into());
+                }
                 Self::assert_token_mint_valid(token_mint, token_program)?;
                 DataAccountUtils::assert_account_match(program_id, &data_account_basic_storage, &Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
 
                 Self::process_add_token(
+                    program_id,
                     system_program,
                     token_program,
                     account_admin,
@@ -162,7 +369,11 @@ impl Processor {
                     data_account_basic_storage,
                     token_mint,
                     rent_sysvar,
+                    associated_token_program,
                     token_index,
+                    peer_address,
+                    confirm_reuse,
+                    allow_high_decimals,
                 )
             }
             FreeTunnelInstruction::RemoveToken { token_index } => {
@@ -177,23 +388,30 @@ impl Processor {
                     token_index,
                 )
             }
-            FreeTunnelInstruction::ProposeMint { req_id, recipient } => {
+            FreeTunnelInstruction::ProposeMint { req_id, recipient, pinned_exe_index } => {
                 let system_program = next_account_info(accounts_iter)?;
                 let account_proposer = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_mint = next_account_info(accounts_iter)?;
+                let (data_account_proposer_index, account_rent_vault) =
+                    Self::trailing_proposer_index_and_rent_vault(program_id, accounts_iter);
                 Self::assert_system_program(system_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, &Constants::BASIC_STORAGE, b"")?;
-                DataAccountUtils::assert_account_match(program_id, data_account_proposed_mint, Constants::PREFIX_MINT, &req_id.data)?;
+                let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_proposed_mint, Constants::PREFIX_MINT, &req_id.data)?;
+                Self::assert_pinned_exe_index_exists(data_account_basic_storage, pinned_exe_index)?;
                 AtomicMint::propose_mint(
                     program_id,
                     system_program,
                     account_proposer,
                     data_account_basic_storage,
                     data_account_proposed_mint,
+                    account_rent_vault,
+                    bump_seed,
                     &req_id,
                     &recipient,
-                )
+                    pinned_exe_index,
+                )?;
+                ProposerIndex::record(program_id, system_program, account_proposer, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::ExecuteMint {
                 req_id,
@@ -201,6 +419,7 @@ impl Processor {
                 executors,
                 exe_index,
             } => {
+                Self::assert_compute_budget(signatures.len())?;
                 let token_program = next_account_info(accounts_iter)?;
                 let account_contract_signer = next_account_info(accounts_iter)?;
                 let token_account_recipient = next_account_info(accounts_iter)?;
@@ -209,10 +428,17 @@ impl Processor {
                 let data_account_executors = next_account_info(accounts_iter)?;
                 let token_mint = next_account_info(accounts_iter)?;
                 let account_multisig_owner = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = Self::trailing_execution_commit(program_id, &req_id, accounts_iter);
+                let account_relayer = Self::trailing_relayer(accounts_iter);
+                let (sysvar_instructions, data_account_proposer_index) = Self::trailing_sysvar_and_proposer_index(accounts_iter);
                 Self::assert_token_program(token_program)?;
                 Self::assert_token_mint_valid(token_mint, token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::assert_only_relayer(data_account_basic_storage, account_relayer)?;
+                ExecutionCommits::assert_may_execute(program_id, data_account_execution_commit, account_relayer, &req_id)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_mint, Constants::PREFIX_MINT, &req_id.data)?;
+                Self::assert_exe_index_exists(data_account_basic_storage, exe_index)?;
+                Self::assert_pinned_exe_index_matches(data_account_proposed_mint, exe_index)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
                 DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
                 AtomicMint::execute_mint(
@@ -225,15 +451,18 @@ impl Processor {
                     data_account_executors,
                     token_mint,
                     account_multisig_owner,
+                    sysvar_instructions,
                     &req_id,
                     &signatures,
                     &executors,
-                )
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::CancelMint { req_id } => {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_mint = next_account_info(accounts_iter)?;
                 let account_refund = next_account_info(accounts_iter)?;
+                let data_account_proposer_index = next_account_info(accounts_iter).ok();
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_mint, Constants::PREFIX_MINT, &req_id.data)?;
                 AtomicMint::cancel_mint(
@@ -242,9 +471,11 @@ impl Processor {
                     data_account_proposed_mint,
                     account_refund,
                     &req_id,
-                )
+                    false,
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
-            FreeTunnelInstruction::ProposeBurn { req_id } => {
+            FreeTunnelInstruction::ProposeBurn { req_id, pinned_exe_index } => {
                 let system_program = next_account_info(accounts_iter)?;
                 let token_program = next_account_info(accounts_iter)?;
                 let account_proposer = next_account_info(accounts_iter)?;
@@ -252,10 +483,13 @@ impl Processor {
                 let token_account_proposer = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_burn = next_account_info(accounts_iter)?;
+                let (data_account_proposer_index, account_rent_vault) =
+                    Self::trailing_proposer_index_and_rent_vault(program_id, accounts_iter);
                 Self::assert_system_program(system_program)?;
                 Self::assert_token_program(token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
-                DataAccountUtils::assert_account_match(program_id, data_account_proposed_burn, Constants::PREFIX_BURN, &req_id.data)?;
+                let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_proposed_burn, Constants::PREFIX_BURN, &req_id.data)?;
+                Self::assert_pinned_exe_index_exists(data_account_basic_storage, pinned_exe_index)?;
                 AtomicMint::propose_burn(
                     program_id,
                     system_program,
@@ -265,8 +499,12 @@ impl Processor {
                     token_account_proposer,
                     data_account_basic_storage,
                     data_account_proposed_burn,
+                    account_rent_vault,
+                    bump_seed,
                     &req_id,
-                )
+                    pinned_exe_index,
+                )?;
+                ProposerIndex::record(program_id, system_program, account_proposer, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::ExecuteBurn {
                 req_id,
@@ -274,6 +512,7 @@ impl Processor {
                 executors,
                 exe_index,
             } => {
+                Self::assert_compute_budget(signatures.len())?;
                 let token_program = next_account_info(accounts_iter)?;
                 let account_contract_signer = next_account_info(accounts_iter)?;
                 let token_account_contract = next_account_info(accounts_iter)?;
@@ -281,10 +520,17 @@ impl Processor {
                 let data_account_proposed_burn = next_account_info(accounts_iter)?;
                 let data_account_executors = next_account_info(accounts_iter)?;
                 let token_mint = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = Self::trailing_execution_commit(program_id, &req_id, accounts_iter);
+                let account_relayer = Self::trailing_relayer(accounts_iter);
+                let (sysvar_instructions, data_account_proposer_index) = Self::trailing_sysvar_and_proposer_index(accounts_iter);
                 Self::assert_token_program(token_program)?;
                 Self::assert_token_mint_valid(token_mint, token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::assert_only_relayer(data_account_basic_storage, account_relayer)?;
+                ExecutionCommits::assert_may_execute(program_id, data_account_execution_commit, account_relayer, &req_id)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_burn, Constants::PREFIX_BURN, &req_id.data)?;
+                Self::assert_exe_index_exists(data_account_basic_storage, exe_index)?;
+                Self::assert_pinned_exe_index_matches(data_account_proposed_burn, exe_index)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
                 DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
                 AtomicMint::execute_burn(
@@ -296,10 +542,12 @@ impl Processor {
                     data_account_proposed_burn,
                     data_account_executors,
                     token_mint,
+                    sysvar_instructions,
                     &req_id,
                     &signatures,
                     &executors,
-                )
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::CancelBurn { req_id } => {
                 let token_program = next_account_info(accounts_iter)?;
@@ -309,6 +557,7 @@ impl Processor {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_burn = next_account_info(accounts_iter)?;
                 let account_refund = next_account_info(accounts_iter)?;
+                let data_account_proposer_index = next_account_info(accounts_iter).ok();
                 Self::assert_token_program(token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_burn, Constants::PREFIX_BURN, &req_id.data)?;
@@ -323,9 +572,11 @@ impl Processor {
                     data_account_proposed_burn,
                     account_refund,
                     &req_id,
-                )
+                    false,
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
-            FreeTunnelInstruction::ProposeLock { req_id } => {
+            FreeTunnelInstruction::ProposeLock { req_id, pinned_exe_index } => {
                 let system_program = next_account_info(accounts_iter)?;
                 let token_program = next_account_info(accounts_iter)?;
                 let account_proposer = next_account_info(accounts_iter)?;
@@ -333,10 +584,13 @@ impl Processor {
                 let token_account_proposer = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_lock = next_account_info(accounts_iter)?;
+                let (data_account_proposer_index, account_rent_vault) =
+                    Self::trailing_proposer_index_and_rent_vault(program_id, accounts_iter);
                 Self::assert_system_program(system_program)?;
                 Self::assert_token_program(token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
-                DataAccountUtils::assert_account_match(program_id, data_account_proposed_lock, Constants::PREFIX_LOCK, &req_id.data)?;
+                let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_proposed_lock, Constants::PREFIX_LOCK, &req_id.data)?;
+                Self::assert_pinned_exe_index_exists(data_account_basic_storage, pinned_exe_index)?;
                 AtomicLock::propose_lock(
                     program_id,
                     system_program,
@@ -346,8 +600,12 @@ impl Processor {
                     token_account_proposer,
                     data_account_basic_storage,
                     data_account_proposed_lock,
+                    account_rent_vault,
+                    bump_seed,
                     &req_id,
-                )
+                    pinned_exe_index,
+                )?;
+                ProposerIndex::record(program_id, system_program, account_proposer, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::ExecuteLock {
                 req_id,
@@ -355,21 +613,31 @@ impl Processor {
                 executors,
                 exe_index,
             } => {
+                Self::assert_compute_budget(signatures.len())?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_lock = next_account_info(accounts_iter)?;
                 let data_account_executors = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = Self::trailing_execution_commit(program_id, &req_id, accounts_iter);
+                let account_relayer = Self::trailing_relayer(accounts_iter);
+                let (sysvar_instructions, data_account_proposer_index) = Self::trailing_sysvar_and_proposer_index(accounts_iter);
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::assert_only_relayer(data_account_basic_storage, account_relayer)?;
+                ExecutionCommits::assert_may_execute(program_id, data_account_execution_commit, account_relayer, &req_id)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_lock, Constants::PREFIX_LOCK, &req_id.data)?;
+                Self::assert_exe_index_exists(data_account_basic_storage, exe_index)?;
+                Self::assert_pinned_exe_index_matches(data_account_proposed_lock, exe_index)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
                 AtomicLock::execute_lock(
                     program_id,
                     data_account_basic_storage,
                     data_account_proposed_lock,
                     data_account_executors,
+                    sysvar_instructions,
                     &req_id,
                     &signatures,
                     &executors,
-                )
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::CancelLock { req_id } => {
                 let token_program = next_account_info(accounts_iter)?;
@@ -379,6 +647,7 @@ impl Processor {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_lock = next_account_info(accounts_iter)?;
                 let account_refund = next_account_info(accounts_iter)?;
+                let data_account_proposer_index = next_account_info(accounts_iter).ok();
                 Self::assert_token_program(token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, &Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_lock, Constants::PREFIX_LOCK, &req_id.data)?;
@@ -393,25 +662,34 @@ impl Processor {
                     data_account_proposed_lock,
                     account_refund,
                     &req_id,
-                )
+                    false,
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
-            FreeTunnelInstruction::ProposeUnlock { req_id, recipient } => {
+            FreeTunnelInstruction::ProposeUnlock { req_id, recipient, pinned_exe_index } => {
                 let system_program = next_account_info(accounts_iter)?;
                 let account_proposer = next_account_info(accounts_iter)?;
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_unlock = next_account_info(accounts_iter)?;
+                let (data_account_proposer_index, account_rent_vault) =
+                    Self::trailing_proposer_index_and_rent_vault(program_id, accounts_iter);
                 Self::assert_system_program(system_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
-                DataAccountUtils::assert_account_match(program_id, data_account_proposed_unlock, Constants::PREFIX_UNLOCK, &req_id.data)?;
+                let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_proposed_unlock, Constants::PREFIX_UNLOCK, &req_id.data)?;
+                Self::assert_pinned_exe_index_exists(data_account_basic_storage, pinned_exe_index)?;
                 AtomicLock::propose_unlock(
                     program_id,
                     system_program,
                     account_proposer,
                     data_account_basic_storage,
                     data_account_proposed_unlock,
+                    account_rent_vault,
+                    bump_seed,
                     &req_id,
                     &recipient,
-                )
+                    pinned_exe_index,
+                )?;
+                ProposerIndex::record(program_id, system_program, account_proposer, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::ExecuteUnlock {
                 req_id,
@@ -419,6 +697,7 @@ impl Processor {
                 executors,
                 exe_index,
             } => {
+                Self::assert_compute_budget(signatures.len())?;
                 let token_program = next_account_info(accounts_iter)?;
                 let account_contract_signer = next_account_info(accounts_iter)?;
                 let token_account_contract = next_account_info(accounts_iter)?;
@@ -426,11 +705,19 @@ impl Processor {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_unlock = next_account_info(accounts_iter)?;
                 let data_account_executors = next_account_info(accounts_iter)?;
+                let data_account_execution_commit = Self::trailing_execution_commit(program_id, &req_id, accounts_iter);
+                let account_relayer = Self::trailing_relayer(accounts_iter);
                 Self::assert_token_program(token_program)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::assert_only_relayer(data_account_basic_storage, account_relayer)?;
+                ExecutionCommits::assert_may_execute(program_id, data_account_execution_commit, account_relayer, &req_id)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_unlock, Constants::PREFIX_UNLOCK, &req_id.data)?;
+                Self::assert_exe_index_exists(data_account_basic_storage, exe_index)?;
+                Self::assert_pinned_exe_index_matches(data_account_proposed_unlock, exe_index)?;
                 DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
                 DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                let (sysvar_instructions, data_account_proposer_index, token_account_previous_vault) =
+                    Self::trailing_unlock_accounts(data_account_basic_storage, req_id.token_index(), accounts_iter)?;
                 AtomicLock::execute_unlock(
                     program_id,
                     token_program,
@@ -440,15 +727,19 @@ impl Processor {
                     data_account_basic_storage,
                     data_account_proposed_unlock,
                     data_account_executors,
+                    sysvar_instructions,
+                    token_account_previous_vault,
                     &req_id,
                     &signatures,
                     &executors,
-                )
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
             }
             FreeTunnelInstruction::CancelUnlock { req_id } => {
                 let data_account_basic_storage = next_account_info(accounts_iter)?;
                 let data_account_proposed_unlock = next_account_info(accounts_iter)?;
                 let account_refund = next_account_info(accounts_iter)?;
+                let data_account_proposer_index = next_account_info(accounts_iter).ok();
                 DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
                 DataAccountUtils::assert_account_match(program_id, data_account_proposed_unlock, Constants::PREFIX_UNLOCK, &req_id.data)?;
                 AtomicLock::cancel_unlock(
@@ -457,35 +748,472 @@ impl Processor {
                     data_account_proposed_unlock,
                     account_refund,
                     &req_id,
+                    false,
+                )?;
+                ProposerIndex::prune(program_id, data_account_proposer_index, &req_id)
+            }
+            FreeTunnelInstruction::ClaimStuckProposal { req_id, kind } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_proposal = next_account_info(accounts_iter)?;
+                let account_refund = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                DataAccountUtils::assert_account_match(program_id, data_account_proposal, kind.prefix(), &req_id.data)?;
+                Self::process_claim_stuck_proposal(
+                    program_id,
+                    account_admin,
+                    data_account_basic_storage,
+                    data_account_proposal,
+                    account_refund,
+                    &req_id,
+                    kind,
                 )
             }
-        }
-    }
+            FreeTunnelInstruction::SetMinProposeInterval { interval } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::set_min_propose_interval(account_admin, data_account_basic_storage, interval)
+            }
+            FreeTunnelInstruction::SetOpenLock { open_lock } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::set_open_lock(account_admin, data_account_basic_storage, open_lock)
+            }
+            FreeTunnelInstruction::VerifySignatures {
+                req_id,
+                signatures,
+                executors,
+                exe_index,
+            } => {
+                Self::assert_compute_budget(signatures.len())?;
+                let data_account_executors = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+                Self::process_verify_signatures(
+                    data_account_executors,
+                    &req_id,
+                    &signatures,
+                    &executors,
+                )
+            }
+            FreeTunnelInstruction::SetMaxProposers { max } => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::set_max_proposers(system_program, account_admin, data_account_basic_storage, max)
+            }
+            FreeTunnelInstruction::MigrateBasicStorage => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_payer = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_migrate_basic_storage(program_id, system_program, account_payer, data_account_basic_storage)
+            }
+            FreeTunnelInstruction::SetPaused { paused } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::set_paused(account_admin, data_account_basic_storage, paused)
+            }
+            FreeTunnelInstruction::DecommissionWithdraw {
+                token_index,
+                amount,
+                recipient,
+                signatures,
+                executors,
+                exe_index,
+            } => {
+                Self::assert_compute_budget(signatures.len())?;
+                let accounts = DecommissionAccounts {
+                    token_program: next_account_info(accounts_iter)?,
+                    account_admin: next_account_info(accounts_iter)?,
+                    account_contract_signer: next_account_info(accounts_iter)?,
+                    data_account_basic_storage: next_account_info(accounts_iter)?,
+                    data_account_executors: next_account_info(accounts_iter)?,
+                    token_account_contract: next_account_info(accounts_iter)?,
+                    token_account_recipient: next_account_info(accounts_iter)?,
+                    sysvar_instructions: next_account_info(accounts_iter).ok(),
+                };
+                Self::assert_token_program(accounts.token_program)?;
+                DataAccountUtils::assert_account_match(program_id, accounts.account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                DataAccountUtils::assert_account_match(program_id, accounts.data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                DataAccountUtils::assert_account_match(program_id, accounts.data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+                if accounts.token_account_recipient.key != &recipient {
+                    return Err(FreeTunnelError::InvalidTokenAccount.into());
+                }
+                AtomicLock::decommission_withdraw(program_id, &accounts, token_index, amount, &signatures, &executors)
+            }
+            FreeTunnelInstruction::SweepExpired { kind, req_ids } => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                let token_accounts = match kind {
+                    ProposalKind::Burn | ProposalKind::Lock => {
+                        let token_program = next_account_info(accounts_iter)?;
+                        let account_contract_signer = next_account_info(accounts_iter)?;
+                        Self::assert_token_program(token_program)?;
+                        DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                        Some((token_program, account_contract_signer))
+                    }
+                    ProposalKind::Mint | ProposalKind::Unlock => None,
+                };
+                Self::process_sweep_expired(
+                    program_id,
+                    data_account_basic_storage,
+                    token_accounts,
+                    accounts_iter.as_slice(),
+                    kind,
+                    &req_ids,
+                )
+            }
+            FreeTunnelInstruction::GetConfig => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_get_config(data_account_basic_storage)
+            }
+            FreeTunnelInstruction::RotateVault { token_index } => {
+                let token_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let account_contract_signer = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let token_account_contract = next_account_info(accounts_iter)?;
+                let token_account_new_vault = next_account_info(accounts_iter)?;
+                Self::assert_token_program(token_program)?;
+                DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_rotate_vault(
+                    program_id,
+                    token_program,
+                    account_admin,
+                    account_contract_signer,
+                    data_account_basic_storage,
+                    token_account_contract,
+                    token_account_new_vault,
+                    token_index,
+                )
+            }
+            FreeTunnelInstruction::TransferTokens { token_index, amount } => {
+                let token_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let account_contract_signer = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let token_account_contract = next_account_info(accounts_iter)?;
+                let token_account_recovery = next_account_info(accounts_iter)?;
+                Self::assert_token_program(token_program)?;
+                DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_transfer_tokens(
+                    program_id,
+                    token_program,
+                    account_admin,
+                    account_contract_signer,
+                    data_account_basic_storage,
+                    token_account_contract,
+                    token_account_recovery,
+                    token_index,
+                    amount,
+                )
+            }
+            FreeTunnelInstruction::ForceCancel {
+                req_id,
+                kind,
+                signatures,
+                executors,
+                exe_index,
+            } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_executors = next_account_info(accounts_iter)?;
+                let data_account_proposal = next_account_info(accounts_iter)?;
+                let account_refund = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+                DataAccountUtils::assert_account_match(program_id, data_account_proposal, kind.prefix(), &req_id.data)?;
 
-    fn process_transfer_admin<'a>(
-        account_admin: &AccountInfo<'a>,
-        data_account_basic_storage: &AccountInfo<'a>,
-        new_admin: &Pubkey,
-    ) -> ProgramResult {
-        // Check permissions
-        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+                // Check permissions
+                Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+                let message = req_id.msg_from_req_force_cancel_message();
+                SignatureUtils::assert_multisig_valid(data_account_executors, None, &message, &signatures, &executors)?;
 
-        // Update storage
+                match kind {
+                    ProposalKind::Mint => AtomicMint::cancel_mint(
+                        program_id,
+                        data_account_basic_storage,
+                        data_account_proposal,
+                        account_refund,
+                        &req_id,
+                        true,
+                    )?,
+                    ProposalKind::Unlock => AtomicLock::cancel_unlock(
+                        program_id,
+                        data_account_basic_storage,
+                        data_account_proposal,
+                        account_refund,
+                        &req_id,
+                        true,
+                    )?,
+                    ProposalKind::Burn | ProposalKind::Lock => {
+                        let token_program = next_account_info(accounts_iter)?;
+                        let account_contract_signer = next_account_info(accounts_iter)?;
+                        let token_account_contract = next_account_info(accounts_iter)?;
+                        let token_account_proposer = next_account_info(accounts_iter)?;
+                        Self::assert_token_program(token_program)?;
+                        DataAccountUtils::assert_account_match(program_id, account_contract_signer, Constants::CONTRACT_SIGNER, b"")?;
+                        match kind {
+                            ProposalKind::Burn => AtomicMint::cancel_burn(
+                            program_id,
+                            token_program,
+                            account_contract_signer,
+                            token_account_contract,
+                            token_account_proposer,
+                            data_account_basic_storage,
+                            data_account_proposal,
+                            account_refund,
+                            &req_id,
+                            true,
+                            )?,
+                            _ => AtomicLock::cancel_lock(
+                            program_id,
+                            token_program,
+                            account_contract_signer,
+                            token_account_contract,
+                            token_account_proposer,
+                            data_account_basic_storage,
+                            data_account_proposal,
+                            account_refund,
+                            &req_id,
+                            true,
+                            )?,
+                        }
+                    }
+                }
+
+                let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+                msg!("ProposalForceCancelled: req_id={}, kind={:?}, event_seq={}", hex::encode(req_id.data), kind, event_seq);
+                Ok(())
+            }
+            FreeTunnelInstruction::GetTokenInfo { token_index } => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_get_token_info(data_account_basic_storage, token_index)
+            }
+            FreeTunnelInstruction::GetReconciliation { token_index } => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let token_account_contract = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                token_ops::assert_is_contract_ata(data_account_basic_storage, token_index, token_account_contract)?;
+                Self::process_get_reconciliation(data_account_basic_storage, token_account_contract, token_index)
+            }
+            FreeTunnelInstruction::GetLockedBalance { token_index } => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_get_locked_balance(data_account_basic_storage, token_index)
+            }
+            FreeTunnelInstruction::VerifyReqId { req_id } => {
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Self::process_verify_req_id(data_account_basic_storage, &req_id)
+            }
+            FreeTunnelInstruction::GetExecutorsInfo { exe_index } => {
+                let data_account_executors = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+                Self::process_get_executors_info(data_account_executors)
+            }
+            FreeTunnelInstruction::ProposeParamChange { parameter_id, new_value } => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_pending_change = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_pending_change, Constants::PREFIX_PENDING_CHANGE, account_admin.key.as_ref())?;
+                Permissions::propose_param_change(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    data_account_basic_storage,
+                    data_account_pending_change,
+                    bump_seed,
+                    parameter_id,
+                    new_value,
+                )
+            }
+            FreeTunnelInstruction::ApplyParamChange => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let data_account_pending_change = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                DataAccountUtils::assert_account_match(program_id, data_account_pending_change, Constants::PREFIX_PENDING_CHANGE, account_admin.key.as_ref())?;
+                Permissions::apply_param_change(program_id, account_admin, data_account_basic_storage, data_account_pending_change)
+            }
+            FreeTunnelInstruction::SetExecutorRotationWindow { min_delay, max_delay } => {
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                Permissions::set_executor_rotation_window(account_admin, data_account_basic_storage, min_delay, max_delay)
+            }
+            FreeTunnelInstruction::FundRentVault { lamports } => {
+                let system_program = next_account_info(accounts_iter)?;
+                let account_admin = next_account_info(accounts_iter)?;
+                let data_account_basic_storage = next_account_info(accounts_iter)?;
+                let account_rent_vault = next_account_info(accounts_iter)?;
+                Self::assert_system_program(system_program)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+                RentVault::fund_rent_vault(
+                    program_id,
+                    system_program,
+                    account_admin,
+                    data_account_basic_storage,
+                    account_rent_vault,
+                    lamports,
+                )
+            }
+            FreeTunnelInstruction::GetProposerActivity { proposer } => {
+                let data_account_proposer_index = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_proposer_index, Constants::PREFIX_PROPOSER_INDEX, proposer.as_ref())?;
+                let activity = match data_account_proposer_index.data_is_empty() {
+                    true => ProposerActivity { proposer, req_ids: Vec::new() },
+                    false => DataAccountUtils::read_account_data(data_account_proposer_index)?,
+                };
+                set_return_data(&borsh::to_vec(&activity)?);
+                Ok(())
+            }
+            FreeTunnelInstruction::QueryProposalState { req_id, kind } => {
+                let data_account_proposal = next_account_info(accounts_iter)?;
+                DataAccountUtils::assert_account_match(program_id, data_account_proposal, kind.prefix(), &req_id.data)?;
+                Self::process_query_proposal_state(data_account_proposal, &req_id, kind)
+            }
+        }
+    }
+
+    fn process_initialize_basic_storage<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        is_mint_contract: bool,
+        max_proposers: u8,
+    ) -> ProgramResult {
+        let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+        DataAccountUtils::create_data_account(
+            program_id,
+            system_program,
+            account_payer,
+            data_account_basic_storage,
+            Constants::BASIC_STORAGE,
+            b"",
+            bump_seed,
+            Constants::SIZE_BASIC_STORAGE + Constants::SIZE_LENGTH,
+            BasicStorage {
+                mint_or_lock: is_mint_contract,
+                admin: *account_admin.key,
+                proposers: Vec::new(),
+                executors_group_length: 0,
+                tokens: SparseArray::default(),
+                vaults: SparseArray::default(),
+                decimals: SparseArray::default(),
+                locked_balance: SparseArray::default(),
+                min_propose_interval: 0,
+                proposer_last_propose: vec![],
+                max_proposers: match max_proposers {
+                    0 => Constants::MAX_PROPOSERS as u8,
+                    max => max,
+                },
+                pending_proposals: SparseArray::default(),
+                previous_vaults: SparseArray::default(),
+                peer_token_addresses: SparseArray::default(),
+                rotation_min_delay: Constants::EXECUTORS_ROTATION_MIN_DELAY,
+                rotation_max_delay: Constants::EXECUTORS_ROTATION_MAX_DELAY,
+                retired_tokens: SparseArray::default(),
+                relayers: vec![],
+                token_programs: SparseArray::default(),
+                pending_deposits: SparseArray::default(),
+                event_seq: 0,
+                renounce_after: 0,
+                open_lock: false,
+                paused_since: 0,
+            },
+        )
+    }
+
+    /// Fields missing from older storage were read as their defaults, see `BasicStorage`'s
+    /// `deserialize_reader`, and are written out here
+    fn process_migrate_basic_storage<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        DataAccountUtils::assert_owned_by_program(program_id, data_account_basic_storage)?;
+        let mut basic_storage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let required_length = Constants::basic_storage_length(basic_storage.max_proposers);
+        DataAccountUtils::grow_data_account(system_program, account_payer, data_account_basic_storage, required_length)?;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+        msg!("BasicStorageMigrated: length={}, event_seq={}", data_account_basic_storage.data_len(), event_seq);
+        Ok(())
+    }
+
+    /// Can be retried on its own as long as no executors group exists yet
+    fn process_initialize_executors<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        data_account_executors: &AccountInfo<'a>,
+        executors: &Vec<EthAddress>,
+        threshold: u64,
+        exe_index: u64,
+    ) -> ProgramResult {
+        DataAccountUtils::assert_account_match(program_id, data_account_basic_storage, Constants::BASIC_STORAGE, b"")?;
+        let bump_seed = DataAccountUtils::assert_account_match(program_id, data_account_executors, Constants::PREFIX_EXECUTORS, &exe_index.to_le_bytes())?;
+        Permissions::init_executors(
+            program_id,
+            system_program,
+            account_admin,
+            account_payer,
+            data_account_basic_storage,
+            data_account_executors,
+            bump_seed,
+            executors,
+            threshold,
+            exe_index,
+        )
+    }
+
+    fn process_transfer_admin<'a>(
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        new_admin: &Pubkey,
+    ) -> ProgramResult {
+        // Check permissions
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        Permissions::assert_new_admin_valid(new_admin)?;
+
+        // Update storage
         let mut basic_storage: BasicStorage =
-            DataAccountUtils::read_account_data(data_account_basic_storage)?;
+            DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         let prev_admin = basic_storage.admin;
         basic_storage.admin = *new_admin;
+        let event_seq = basic_storage.next_event_seq()?;
         DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
 
         msg!(
-            "AdminTransferred: prev_admin={}, new_admin={}",
+            "AdminTransferred: prev_admin={}, new_admin={}, event_seq={}",
             prev_admin,
-            new_admin
+            new_admin,
+            event_seq
         );
         Ok(())
     }
 
     fn process_add_token<'a>(
+        program_id: &Pubkey,
         system_program: &AccountInfo<'a>,
         token_program: &AccountInfo<'a>,
         account_admin: &AccountInfo<'a>,
@@ -494,11 +1222,15 @@ impl Processor {
         data_account_basic_storage: &AccountInfo<'a>,
         token_mint: &AccountInfo<'a>,
         rent_sysvar: &AccountInfo<'a>,
+        associated_token_program: &AccountInfo<'a>,
         token_index: u8,
+        peer_address: EthAddress,
+        confirm_reuse: bool,
+        allow_high_decimals: bool,
     ) -> ProgramResult {
         Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
 
-        let mut basic_storage: BasicStorage = DataAccountUtils::read_account_data(data_account_basic_storage)?;
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         if basic_storage.tokens.get(token_index) != Option::None {
             Err(FreeTunnelError::TokenIndexOccupied.into())
         } else if token_index == 0 {
@@ -506,36 +1238,44 @@ impl Processor {
         } else if basic_storage.tokens.len() >= Constants::MAX_TOKENS {
             Err(FreeTunnelError::StorageLimitReached.into())
         } else {
-            token_ops::create_token_account_contract(
-                system_program,
-                token_program,
-                account_admin,
-                token_account_contract,
-                account_contract_signer,
-                token_mint,
-                rent_sysvar,
+            let retired_mint =
+                token_ops::assert_token_reuse_confirmed(&basic_storage.retired_tokens, token_index, token_mint.key, confirm_reuse)?;
+            let decimals = Self::mint_decimals(token_mint, token_program)?;
+            Self::assert_decimals_bridgeable(decimals, allow_high_decimals)?;
+            token_ops::create_token_account_contract_signed(
+                program_id,
+                &token_ops::VaultCreationAccounts {
+                    system_program,
+                    token_program,
+                    payer: account_admin,
+                    token_account_contract,
+                    account_contract_signer,
+                    token_mint,
+                    rent_sysvar,
+                    associated_token_program,
+                },
             )?;
 
-            let mint_data = token_mint.data.borrow();
-            let decimals = if token_program.key == &spl_token::id() {
-                Mint::unpack(&mint_data)?.decimals
-            } else if token_program.key == &spl_token_2022::id() {
-                Token2022Mint::unpack(&mint_data)?.decimals
-            } else {
-                return Err(FreeTunnelError::InvalidTokenProgram.into());
-            };
-
             basic_storage.tokens.insert(token_index, *token_mint.key)?;
             basic_storage.vaults.insert(token_index, *token_account_contract.key)?;
             basic_storage.decimals.insert(token_index, decimals)?;
             basic_storage.locked_balance.insert(token_index, 0)?;
+            basic_storage.pending_proposals.insert(token_index, 0)?;
+            basic_storage.peer_token_addresses.insert(token_index, peer_address)?;
+            basic_storage.retired_tokens.remove(token_index);
+            basic_storage.token_programs.insert(token_index, *token_program.key)?;
+            basic_storage.pending_deposits.insert(token_index, 0)?;
+            let event_seq = basic_storage.next_event_seq()?;
             DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
 
             msg!(
-                "TokenAdded: token_index={}, token_mint={}, decimals={}",
+                "TokenAdded: token_index={}, token_mint={}, decimals={}, peer_address=0x{}, retired_mint={}, event_seq={}",
                 token_index,
                 token_mint.key,
-                decimals
+                decimals,
+                hex::encode(peer_address),
+                retired_mint.map_or("none".to_string(), |mint| mint.to_string()),
+                event_seq
             );
             Ok(())
         }
@@ -552,7 +1292,7 @@ impl Processor {
 
         // Process
         let mut basic_storage: BasicStorage =
-            DataAccountUtils::read_account_data(data_account_basic_storage)?;
+            DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
         if basic_storage.tokens.get(token_index) == Option::None {
             Err(FreeTunnelError::TokenIndexNonExistent.into())
         } else if token_index == 0 {
@@ -564,6 +1304,8 @@ impl Processor {
             != 0
         {
             Err(FreeTunnelError::LockedBalanceMustBeZero.into())
+        } else if basic_storage.pending_proposals.get(token_index).is_some_and(|pending| *pending != 0) {
+            Err(FreeTunnelError::PendingProposalsExist.into())
         } else {
             let vault = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
             if token_account_contract.key != vault {
@@ -588,17 +1330,667 @@ impl Processor {
                 return Err(FreeTunnelError::VaultBalanceMustBeZero.into());
             }
 
-            basic_storage.tokens.remove(token_index);
+            let token_mint = basic_storage.tokens.remove(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+            basic_storage.retired_tokens.insert(token_index, token_mint)?;
             basic_storage.vaults.remove(token_index);
             basic_storage.decimals.remove(token_index);
             basic_storage.locked_balance.remove(token_index);
+            basic_storage.pending_proposals.remove(token_index);
+            basic_storage.previous_vaults.remove(token_index);
+            basic_storage.peer_token_addresses.remove(token_index);
+            basic_storage.token_programs.remove(token_index);
+            basic_storage.pending_deposits.remove(token_index);
+            let event_seq = basic_storage.next_event_seq()?;
             DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
 
-            msg!("TokenRemoved: token_index={}", token_index);
+            msg!("TokenRemoved: token_index={}, token_mint={}, event_seq={}", token_index, token_mint, event_seq);
             Ok(())
         }
     }
 
+    fn process_rotate_vault<'a>(
+        program_id: &Pubkey,
+        token_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        account_contract_signer: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        token_account_contract: &AccountInfo<'a>,
+        token_account_new_vault: &AccountInfo<'a>,
+        token_index: u8,
+    ) -> ProgramResult {
+        // Check permissions
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+        token_ops::assert_token_program_of(data_account_basic_storage, token_index, token_program)?;
+
+        // Check vaults
+        let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let mint_pubkey = *basic_storage.tokens.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        let vault = *basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        if token_account_contract.key != &vault {
+            return Err(FreeTunnelError::InvalidTokenAccount.into());
+        }
+        if token_account_new_vault.key == &vault {
+            return Err(FreeTunnelError::InvalidNewVault.into());
+        }
+        token_ops::assert_is_contract_token_account(
+            token_program,
+            token_account_new_vault,
+            account_contract_signer.key,
+            &mint_pubkey,
+        )?;
+
+        // Move the full balance
+        let amount = token_ops::token_account_balance(token_program, token_account_contract)?;
+        if amount > 0 {
+            token_ops::transfer_from_contract(
+                program_id,
+                token_program,
+                account_contract_signer,
+                token_account_contract,
+                token_account_new_vault,
+                amount,
+            )?;
+        }
+
+        // Update storage
+        let grace_end = Clock::get()?.unix_timestamp as u64 + Constants::VAULT_ROTATION_GRACE_PERIOD;
+        basic_storage.vaults.insert(token_index, *token_account_new_vault.key)?;
+        basic_storage.previous_vaults.insert(token_index, (vault, grace_end))?;
+        let event_seq = basic_storage.next_event_seq()?;
+        DataAccountUtils::write_account_data(data_account_basic_storage, basic_storage)?;
+
+        msg!(
+            "VaultRotated: token_index={}, old_vault={}, new_vault={}, amount={}, event_seq={}",
+            token_index,
+            vault,
+            token_account_new_vault.key,
+            amount,
+            event_seq
+        );
+        Ok(())
+    }
+
+    fn process_transfer_tokens<'a>(
+        program_id: &Pubkey,
+        token_program: &AccountInfo<'a>,
+        account_admin: &AccountInfo<'a>,
+        account_contract_signer: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        token_account_contract: &AccountInfo<'a>,
+        token_account_recovery: &AccountInfo<'a>,
+        token_index: u8,
+        amount: u64,
+    ) -> ProgramResult {
+        // Check permissions
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+
+        // Only tokens no proposal or lock can claim may leave the vault
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if *basic_storage
+            .locked_balance
+            .get(token_index)
+            .ok_or(FreeTunnelError::TokenIndexNonExistent)?
+            != 0
+        {
+            return Err(FreeTunnelError::LockedBalanceMustBeZero.into());
+        } else if basic_storage.pending_proposals.get(token_index).is_some_and(|pending| *pending != 0) {
+            return Err(FreeTunnelError::PendingProposalsExist.into());
+        } else if amount == 0 {
+            return Err(FreeTunnelError::AmountCannotBeZero.into());
+        }
+        let vault = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        if token_account_contract.key != vault {
+            return Err(FreeTunnelError::InvalidTokenAccount.into());
+        }
+        token_ops::assert_vault_balance(token_program, token_account_contract, amount)?;
+        token_ops::transfer_from_contract(
+            program_id,
+            token_program,
+            account_contract_signer,
+            token_account_contract,
+            token_account_recovery,
+            amount,
+        )?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!(
+            "TokensTransferred: token_index={}, vault={}, recipient={}, amount={}, event_seq={}",
+            token_index,
+            vault,
+            token_account_recovery.key,
+            amount,
+            event_seq
+        );
+        Ok(())
+    }
+
+    fn process_claim_stuck_proposal<'a>(
+        program_id: &Pubkey,
+        account_admin: &AccountInfo<'a>,
+        data_account_basic_storage: &AccountInfo<'a>,
+        data_account_proposal: &AccountInfo<'a>,
+        account_refund: &AccountInfo<'a>,
+        req_id: &ReqId,
+        kind: ProposalKind,
+    ) -> ProgramResult {
+        // Check permissions
+        Permissions::assert_only_admin(data_account_basic_storage, account_admin)?;
+
+        // All proposal structs start with `inner: Pubkey`; only a proposal that cannot be decoded
+        // or holds the default pubkey is considered stuck. Executed proposals must never be
+        // closed, otherwise the req_id could be proposed again.
+        match DataAccountUtils::read_proposal_inner(data_account_proposal) {
+            Ok(inner) if inner != Pubkey::default() => {
+                return Err(FreeTunnelError::ProposalNotStuck.into());
+            }
+            _ => {}
+        }
+
+        // The fixed header still tells what the proposal holds back, so release it like a cancel
+        // would. Proposals created before the header leave their counters behind, see the log
+        let header = DataAccountUtils::read_proposal_header(data_account_proposal)
+            .ok()
+            .flatten()
+            .filter(|header| header.discriminator == kind.discriminator() && header.token_index == req_id.token_index());
+        match header {
+            Some(header) if header.status == Constants::PROPOSAL_STATUS_EXECUTED => {
+                return Err(FreeTunnelError::ProposalNotStuck.into());
+            }
+            Some(ProposalHeader { token_index, amount, .. }) => {
+                if kind == ProposalKind::Unlock {
+                    AtomicLock::update_locked_balance(data_account_basic_storage, token_index, amount, true)?;
+                }
+                token_ops::update_pending_proposals(data_account_basic_storage, token_index, false)?;
+                if kind != ProposalKind::Mint {
+                    token_ops::update_pending_deposits(data_account_basic_storage, token_index, amount, false)?;
+                }
+            }
+            None => msg!(
+                "StuckProposalOrphaned: req_id={}, kind={:?}, token_index={}, pending counters and any amount it holds are not released",
+                hex::encode(req_id.data),
+                kind,
+                req_id.token_index()
+            ),
+        }
+
+        let raw_data = hex::encode(&data_account_proposal.data.borrow()[..]);
+        DataAccountUtils::close_account(program_id, data_account_proposal, account_refund, kind.prefix(), &req_id.data, false)?;
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!(
+            "StuckProposalClaimed: req_id={}, raw_data={}, released={}, event_seq={}",
+            hex::encode(req_id.data),
+            raw_data,
+            header.is_some(),
+            event_seq
+        );
+        Ok(())
+    }
+
+    fn process_verify_signatures(
+        data_account_executors: &AccountInfo,
+        req_id: &ReqId,
+        signatures: &Vec<[u8; 64]>,
+        executors: &Vec<EthAddress>,
+    ) -> ProgramResult {
+        // One bit per signature, and no group holds more executors than that
+        if signatures.len() > Constants::MAX_EXECUTORS {
+            return Err(FreeTunnelError::StorageLimitReached.into());
+        }
+        let message = req_id.msg_from_req_signing_message();
+        let bitmask = SignatureUtils::signature_bitmask(&message, signatures, executors);
+        // Threshold, executors group and membership problems are reported, not raised, so the
+        // simulation still returns the bitmask
+        let multisig_error = SignatureUtils::assert_multisig_valid(data_account_executors, None, &message, signatures, executors)
+            .err()
+            .map(u64::from);
+        set_return_data(&borsh::to_vec(&SignaturesView { bitmask, multisig_error })?);
+
+        msg!(
+            "SignaturesVerified: req_id={}, bitmask={:#x}, multisig_error={:?}",
+            hex::encode(req_id.data),
+            bitmask,
+            multisig_error
+        );
+        Ok(())
+    }
+
+    fn process_sweep_expired<'a>(
+        program_id: &Pubkey,
+        data_account_basic_storage: &AccountInfo<'a>,
+        token_accounts: Option<(&AccountInfo<'a>, &AccountInfo<'a>)>,
+        proposal_accounts: &[AccountInfo<'a>],
+        kind: ProposalKind,
+        req_ids: &Vec<ReqId>,
+    ) -> ProgramResult {
+        if req_ids.len() > Constants::MAX_SWEEP_PROPOSALS {
+            return Err(FreeTunnelError::SweepLimitExceeded.into());
+        }
+        let group_size = match token_accounts {
+            Some(_) => 4,
+            None => 2,
+        };
+        if proposal_accounts.len() != req_ids.len() * group_size {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut bitmap = 0u64;
+        for (i, (req_id, group)) in req_ids.iter().zip(proposal_accounts.chunks(group_size)).enumerate() {
+            let data_account_proposal = &group[0];
+            let account_refund = &group[group_size - 1];
+            if !Self::is_sweepable(program_id, data_account_proposal, kind, req_id, now) {
+                continue;
+            }
+            // A group passed with the wrong refund accounts is skipped like an unsweepable one,
+            // rather than failing the whole sweep
+            if let Err(error) = Self::assert_sweep_refund_valid(program_id, data_account_basic_storage, token_accounts, group, kind, req_id) {
+                msg!("SweepSkipped: req_id={}, error={}", hex::encode(req_id.data), error);
+                continue;
+            }
+
+            // Same logic as the individual cancels; past the checks above, only state shared by the
+            // whole sweep can fail
+            match (kind, token_accounts) {
+                (ProposalKind::Mint, _) => AtomicMint::cancel_mint(
+                    program_id,
+                    data_account_basic_storage,
+                    data_account_proposal,
+                    account_refund,
+                    req_id,
+                    false,
+                )?,
+                (ProposalKind::Unlock, _) => AtomicLock::cancel_unlock(
+                    program_id,
+                    data_account_basic_storage,
+                    data_account_proposal,
+                    account_refund,
+                    req_id,
+                    false,
+                )?,
+                (ProposalKind::Burn, Some((token_program, account_contract_signer))) => AtomicMint::cancel_burn(
+                    program_id,
+                    token_program,
+                    account_contract_signer,
+                    &group[1],
+                    &group[2],
+                    data_account_basic_storage,
+                    data_account_proposal,
+                    account_refund,
+                    req_id,
+                    false,
+                )?,
+                (ProposalKind::Lock, Some((token_program, account_contract_signer))) => AtomicLock::cancel_lock(
+                    program_id,
+                    token_program,
+                    account_contract_signer,
+                    &group[1],
+                    &group[2],
+                    data_account_basic_storage,
+                    data_account_proposal,
+                    account_refund,
+                    req_id,
+                    false,
+                )?,
+                _ => return Err(ProgramError::NotEnoughAccountKeys),
+            }
+            bitmap |= 1 << i;
+        }
+        set_return_data(&bitmap.to_le_bytes());
+
+        let event_seq = DataAccountUtils::next_event_seq(data_account_basic_storage)?;
+        msg!("ExpiredSwept: kind={:?}, bitmap={:#x}, event_seq={}", kind, bitmap, event_seq);
+        Ok(())
+    }
+
+    fn process_get_config(data_account_basic_storage: &AccountInfo) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let config = ConfigView {
+            version: Constants::VERSION.to_string(),
+            hub_id: Constants::HUB_ID,
+            channel: String::from_utf8_lossy(Constants::BRIDGE_CHANNEL).into_owned(),
+            propose_period: Constants::PROPOSE_PERIOD,
+            expire_period: Constants::EXPIRE_PERIOD,
+            expire_extra_period: Constants::EXPIRE_EXTRA_PERIOD,
+            max_tokens: Constants::MAX_TOKENS as u8,
+            max_proposers: basic_storage.max_proposers,
+            max_executors: Constants::MAX_EXECUTORS as u8,
+            mint_or_lock: basic_storage.mint_or_lock,
+        };
+        set_return_data(&borsh::to_vec(&config)?);
+        Ok(())
+    }
+
+    fn process_get_token_info(data_account_basic_storage: &AccountInfo, token_index: u8) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let token_info = TokenInfoView {
+            token_mint: *basic_storage.tokens.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?,
+            vault: *basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?,
+            decimals: *basic_storage.decimals.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?,
+            locked_balance: *basic_storage.locked_balance.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?,
+            pending_proposals: basic_storage.pending_proposals.get(token_index).copied().unwrap_or(0),
+            peer_address: basic_storage.peer_token_addresses.get(token_index).copied().unwrap_or(Constants::ETH_ZERO_ADDRESS),
+        };
+        set_return_data(&borsh::to_vec(&token_info)?);
+        Ok(())
+    }
+
+    fn process_get_reconciliation(
+        data_account_basic_storage: &AccountInfo,
+        token_account_contract: &AccountInfo,
+        token_index: u8,
+    ) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let vault_balance = token_ops::token_account_balance_by_owner(token_account_contract)?;
+        let reconciliation = token_ops::reconciliation(&basic_storage, token_index, vault_balance)?;
+        msg!(
+            "Reconciliation: token_index={}, locked_balance={}, vault_balance={}, pending_deposits={}, drift={}",
+            token_index, reconciliation.locked_balance, vault_balance, reconciliation.pending_deposits, reconciliation.drift,
+        );
+        set_return_data(&borsh::to_vec(&reconciliation)?);
+        Ok(())
+    }
+
+    fn process_get_locked_balance(data_account_basic_storage: &AccountInfo, token_index: u8) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let balance = basic_storage.locked_balance.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        let vault = basic_storage.vaults.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        let token_mint = basic_storage.tokens.get(token_index).ok_or(FreeTunnelError::TokenIndexNonExistent)?;
+        msg!(
+            "LockedBalance: token_index={}, balance={}, vault={}, token_mint={}",
+            token_index, balance, vault, token_mint,
+        );
+        Ok(())
+    }
+
+    fn process_get_executors_info(data_account_executors: &AccountInfo) -> ProgramResult {
+        let ExecutorsInfo {
+            index,
+            threshold,
+            active_since,
+            inactive_after,
+            executors,
+            ed25519_executors,
+        } = DataAccountUtils::read_executors_info(data_account_executors)?;
+        let now = Clock::get()?.unix_timestamp as u64;
+        let executors_info = ExecutorsInfoView {
+            index,
+            threshold,
+            active_since,
+            inactive_after,
+            executors,
+            ed25519_executors,
+            seconds_until_inactive: match inactive_after {
+                0 => None,
+                _ => Some(inactive_after.saturating_sub(now)),
+            },
+        };
+        set_return_data(&borsh::to_vec(&executors_info)?);
+        Ok(())
+    }
+
+    fn process_verify_req_id(data_account_basic_storage: &AccountInfo, req_id: &ReqId) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        msg!(
+            "VerifyReqId: req_id={}, action={}, token_index={}, created_time={}",
+            hex::encode(req_id.data),
+            req_id.action(),
+            req_id.token_index(),
+            req_id.created_time()
+        );
+        let token = req_id.get_checked_token(data_account_basic_storage, None);
+        // Unknown tokens are checked in bridge units
+        let decimal = token.as_ref().map_or(6, |(_, decimal, _)| *decimal);
+        let checks: [(&str, ProgramResult); 4] = [
+            ("created_time", req_id.checked_created_time().map(|_| ())),
+            ("hub_side", req_id.assert_hub_side(basic_storage.mint_or_lock, Constants::HUB_ID)),
+            ("token_index", token.map(|_| ())),
+            ("amount", req_id.get_checked_amount(decimal).map(|_| ())),
+        ];
+        let mut outcome = Ok(());
+        for (check, result) in checks {
+            match result {
+                Ok(()) => msg!("VerifyReqIdCheck: check={}, ok=true", check),
+                Err(e) => {
+                    msg!("VerifyReqIdCheck: check={}, ok=false, error={}", check, e);
+                    // Report the first failing check
+                    if outcome.is_ok() {
+                        outcome = Err(e);
+                    }
+                }
+            }
+        }
+        outcome
+    }
+
+    fn process_query_proposal_state(
+        data_account_proposal: &AccountInfo,
+        req_id: &ReqId,
+        kind: ProposalKind,
+    ) -> ProgramResult {
+        if data_account_proposal.data_is_empty() {
+            return Err(FreeTunnelError::ReqIdNotFound.into());
+        }
+        // All proposal kinds start with the same `inner` pubkey
+        let inner = DataAccountUtils::read_proposal_inner(data_account_proposal)?;
+        msg!(
+            "ProposalState: req_id={}, kind={:?}, inner={}, executed={}, expire_at={}",
+            hex::encode(req_id.data),
+            kind,
+            inner,
+            inner == Constants::EXECUTED_PLACEHOLDER,
+            req_id.created_time() + kind.expire_period()
+        );
+        Ok(())
+    }
+
+    /// A proposal qualifies for sweeping if it exists, is not executed, and is expired
+    fn is_sweepable(
+        program_id: &Pubkey,
+        data_account_proposal: &AccountInfo,
+        kind: ProposalKind,
+        req_id: &ReqId,
+        now: i64,
+    ) -> bool {
+        DataAccountUtils::assert_account_match(program_id, data_account_proposal, kind.prefix(), &req_id.data).is_ok()
+            && data_account_proposal.owner == program_id
+            && !data_account_proposal.data_is_empty()
+            && matches!(
+                DataAccountUtils::read_proposal_inner(data_account_proposal),
+                Ok(inner) if inner != Constants::EXECUTED_PLACEHOLDER
+            )
+            && now > (req_id.created_time() + kind.expire_period()) as i64
+    }
+
+    /// The refund checks of the cancel for `kind`, made before anything is written
+    fn assert_sweep_refund_valid<'a>(
+        program_id: &Pubkey,
+        data_account_basic_storage: &AccountInfo<'a>,
+        token_accounts: Option<(&AccountInfo<'a>, &AccountInfo<'a>)>,
+        group: &[AccountInfo<'a>],
+        kind: ProposalKind,
+        req_id: &ReqId,
+    ) -> ProgramResult {
+        let data_account_proposal = &group[0];
+        let account_refund = &group[group.len() - 1];
+        let (proposer, rent_from_vault, source) = match kind {
+            ProposalKind::Mint => match DataAccountUtils::read_proposal_data::<ProposedMint>(data_account_proposal) {
+                Ok(ProposedMint { proposer, rent_from_vault, .. }) => (proposer, rent_from_vault, None),
+                Err(_) => return Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false),
+            },
+            ProposalKind::Unlock => match DataAccountUtils::read_proposal_data::<ProposedUnlock>(data_account_proposal) {
+                Ok(ProposedUnlock { proposer, rent_from_vault, .. }) => (proposer, rent_from_vault, None),
+                Err(_) => return Permissions::assert_only_proposer(data_account_basic_storage, account_refund, false),
+            },
+            ProposalKind::Burn => {
+                let (rent_from_vault, source) = AtomicMint::burn_refund_of(data_account_proposal);
+                (DataAccountUtils::read_proposal_inner(data_account_proposal)?, rent_from_vault, source)
+            }
+            ProposalKind::Lock => {
+                let (rent_from_vault, source) = AtomicLock::lock_refund_of(data_account_proposal);
+                (DataAccountUtils::read_proposal_inner(data_account_proposal)?, rent_from_vault, source)
+            }
+        };
+        RentVault::assert_rent_refund(program_id, account_refund, rent_from_vault, &proposer)?;
+
+        if let (ProposalKind::Burn | ProposalKind::Lock, Some((token_program, _))) = (kind, token_accounts) {
+            let (token_index, decimal, mint_pubkey) = req_id.get_checked_token(data_account_basic_storage, None)?;
+            let amount = req_id.get_checked_amount(decimal)?;
+            token_ops::assert_token_program_of(data_account_basic_storage, req_id.token_index(), token_program)?;
+            token_ops::assert_is_current_or_previous_vault(data_account_basic_storage, token_index, &group[1])?;
+            token_ops::assert_vault_balance(token_program, &group[1], amount)?;
+            token_ops::assert_refund_token_account(token_program, &group[2], source, &proposer, &mint_pubkey)?;
+        }
+        Ok(())
+    }
+
+    /// Execute instructions take an optional relayer before their other trailing accounts, told
+    /// apart by its signature: the sysvar and PDAs that may follow never sign
+    fn trailing_relayer<'a, 'b>(
+        accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> Option<&'b AccountInfo<'a>> {
+        match accounts_iter.as_slice().first() {
+            Some(account) if account.is_signer => accounts_iter.next(),
+            _ => None,
+        }
+    }
+
+    /// The execution commitment follows the fixed accounts of an execute, told apart by its PDA
+    /// address, so account lists from before commitments were added still execute
+    fn trailing_execution_commit<'a, 'b>(
+        program_id: &Pubkey,
+        req_id: &ReqId,
+        accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> Option<&'b AccountInfo<'a>> {
+        let (execution_commit, _) = Pubkey::find_program_address(&[Constants::PREFIX_EXECUTION_COMMIT, &req_id.data], program_id);
+        match accounts_iter.as_slice().first() {
+            Some(account) if account.key == &execution_commit => accounts_iter.next(),
+            _ => None,
+        }
+    }
+
+    /// Execute instructions take two optional trailing accounts: the instructions sysvar and the
+    /// proposer index. They are told apart by the sysvar address, so either can be omitted
+    fn trailing_sysvar_and_proposer_index<'a, 'b>(
+        accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> (Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>) {
+        match next_account_info(accounts_iter).ok() {
+            Some(account) if account.key != &solana_sdk_ids::sysvar::instructions::ID => (None, Some(account)),
+            sysvar_instructions => (sysvar_instructions, next_account_info(accounts_iter).ok()),
+        }
+    }
+
+    /// `ExecuteUnlock` also takes the previous vault of the token as an optional trailing account,
+    /// told apart by its address recorded in `previous_vaults`. Any order of the three is accepted
+    fn trailing_unlock_accounts<'a, 'b>(
+        data_account_basic_storage: &AccountInfo<'a>,
+        token_index: u8,
+        accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> Result<TrailingUnlockAccounts<'b, 'a>, ProgramError> {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        let previous_vault = basic_storage.previous_vaults.get(token_index).map(|(vault, _)| *vault);
+        let (mut sysvar_instructions, mut data_account_proposer_index, mut token_account_previous_vault) = (None, None, None);
+        for account in accounts_iter {
+            if account.key == &solana_sdk_ids::sysvar::instructions::ID {
+                sysvar_instructions = Some(account);
+            } else if Some(*account.key) == previous_vault {
+                token_account_previous_vault = Some(account);
+            } else if data_account_proposer_index.is_none() {
+                data_account_proposer_index = Some(account);
+            }
+        }
+        Ok((sysvar_instructions, data_account_proposer_index, token_account_previous_vault))
+    }
+
+    /// Propose instructions take two optional trailing accounts: the proposer index and the rent
+    /// vault. They are told apart by the rent vault address, so either can be omitted
+    fn trailing_proposer_index_and_rent_vault<'a, 'b>(
+        program_id: &Pubkey,
+        accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> (Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>) {
+        let (rent_vault, _) = Pubkey::find_program_address(&[Constants::PREFIX_RENT_VAULT], program_id);
+        match next_account_info(accounts_iter).ok() {
+            Some(account) if account.key == &rent_vault => (None, Some(account)),
+            data_account_proposer_index => (data_account_proposer_index, next_account_info(accounts_iter).ok()),
+        }
+    }
+
+    /// An `exe_index` past the stored groups would otherwise fail as a PDA mismatch of the
+    /// executors account, which hides the actual mistake
+    fn assert_pinned_exe_index_exists(data_account_basic_storage: &AccountInfo, pinned_exe_index: Option<u64>) -> ProgramResult {
+        match pinned_exe_index {
+            Some(exe_index) => Self::assert_exe_index_exists(data_account_basic_storage, exe_index),
+            None => Ok(()),
+        }
+    }
+
+    /// A pinned proposal is only executed with signatures of the executors group it was pinned to
+    fn assert_pinned_exe_index_matches(data_account_proposal: &AccountInfo, exe_index: u64) -> ProgramResult {
+        match DataAccountUtils::read_proposal_pinned_exe_index(data_account_proposal)? {
+            Some(pinned_exe_index) if pinned_exe_index != exe_index => {
+                msg!("PinnedExeIndexMismatch: pinned_exe_index={}, exe_index={}", pinned_exe_index, exe_index);
+                Err(FreeTunnelError::PinnedExeIndexMismatch.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn assert_exe_index_exists(data_account_basic_storage: &AccountInfo, exe_index: u64) -> ProgramResult {
+        let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(data_account_basic_storage)?;
+        if exe_index >= basic_storage.executors_group_length {
+            msg!("InvalidExeIndex: exe_index={}, executors_group_length={}", exe_index, basic_storage.executors_group_length);
+            return Err(FreeTunnelError::InvalidExeIndex.into());
+        }
+        Ok(())
+    }
+
+    /// Rejects signature bundles whose estimated cost exceeds `Constants::CU_SOFT_CAP`, before any
+    /// verification spends compute units on them
+    fn assert_compute_budget(signature_count: usize) -> ProgramResult {
+        let expected_cu = Constants::expected_compute_units(signature_count);
+        msg!("ExpectedComputeUnits: signatures={}, expected_cu={}", signature_count, expected_cu);
+        if expected_cu > Constants::CU_SOFT_CAP {
+            Err(FreeTunnelError::TooManySignaturesForBudget.into())
+        } else { Ok(()) }
+    }
+
+    /// Fail fast before creating basic storage, so every input `init_executors` could reject is
+    /// rejected before any account is touched. `init_executors` checks these again
+    fn assert_initial_executors(executors: &[EthAddress], threshold: u64, exe_index: u64) -> ProgramResult {
+        if exe_index != 0 {
+            Err(FreeTunnelError::InvalidExeIndex.into())
+        } else if executors.len() > Constants::MAX_EXECUTORS {
+            Err(FreeTunnelError::StorageLimitReached.into())
+        } else if threshold == 0 {
+            Err(FreeTunnelError::ThresholdMustBeGreaterThanZero.into())
+        } else if threshold > executors.len() as u64 {
+            Err(FreeTunnelError::NotMeetThreshold.into())
+        } else {
+            SignatureUtils::assert_executors_not_duplicated(executors)
+        }
+    }
+
+    /// Above `Constants::MAX_DECIMALS` a single request can bridge little (about 18 whole tokens
+    /// at 18 decimals), so such mints need the explicit flag
+    fn assert_decimals_bridgeable(decimals: u8, allow_high_decimals: bool) -> ProgramResult {
+        if decimals > Constants::MAX_DECIMALS && !allow_high_decimals {
+            msg!("DecimalsTooHigh: decimals={}, max_raw_amount={}", decimals, ReqId::max_raw_amount(decimals));
+            return Err(FreeTunnelError::DecimalsTooHigh.into());
+        }
+        Ok(())
+    }
+
+    fn mint_decimals(token_mint: &AccountInfo, token_program: &AccountInfo) -> Result<u8, ProgramError> {
+        let mint_data = token_mint.data.borrow();
+        if token_program.key == &spl_token::id() {
+            Ok(Mint::unpack(&mint_data)?.decimals)
+        } else if token_program.key == &spl_token_2022::id() {
+            Ok(Token2022Mint::unpack(&mint_data)?.decimals)
+        } else {
+            Err(FreeTunnelError::InvalidTokenProgram.into())
+        }
+    }
+
     fn assert_system_program(system_program: &AccountInfo) -> ProgramResult {
         if system_program.key != &solana_sdk_ids::system_program::ID {
             Err(FreeTunnelError::InvalidSystemProgram.into())
@@ -616,7 +2008,17 @@ impl Processor {
     }
 
     fn assert_token_mint_valid(token_mint: &AccountInfo, token_program: &AccountInfo) -> ProgramResult {
-        if token_mint.owner == token_program.key {
+        if token_mint.owner != token_program.key {
+            return Err(FreeTunnelError::InvalidTokenMint.into());
+        }
+        // Reject allocated but uninitialized mint accounts
+        let mint_data = token_mint.data.borrow();
+        let initialized = if token_program.key == &spl_token::id() {
+            Mint::unpack(&mint_data).is_ok()
+        } else {
+            StateWithExtensions::<Token2022Mint>::unpack(&mint_data).is_ok()
+        };
+        if initialized {
             Ok(())
         } else {
             Err(FreeTunnelError::InvalidTokenMint.into())
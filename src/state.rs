@@ -1,3 +1,4 @@
+use std::io::{self, Read};
 use std::ops::{Index, IndexMut};
 
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -8,7 +9,17 @@ use crate::{
     error::FreeTunnelError,
 };
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+/// Reads a field appended to a stored struct after its accounts were first created, or
+/// `default` when the data ends before it
+fn deserialize_appended<T: BorshDeserialize, R: Read>(reader: &mut R, default: T) -> io::Result<T> {
+    let mut first = [0u8; 1];
+    match reader.read(&mut first)? {
+        0 => Ok(default),
+        _ => T::deserialize_reader(&mut first.as_slice().chain(reader)),
+    }
+}
+
+#[derive(BorshSerialize, Debug)]
 pub struct BasicStorage {
     pub mint_or_lock: bool, // true for mint, false for lock
     pub admin: Pubkey,
@@ -18,35 +29,290 @@ pub struct BasicStorage {
     pub vaults: SparseArray<Pubkey>, // contract ATA per token
     pub decimals: SparseArray<u8>, // decimals of each token
     pub locked_balance: SparseArray<u64>, // locked balance of each token
+    // Appended since the first release, so older storage may end before any of them
+    pub min_propose_interval: u64, // seconds between two proposals of the same proposer, 0 to disable
+    pub proposer_last_propose: Vec<(Pubkey, u64)>, // proposers still inside the interval, by last propose time
+    pub max_proposers: u8, // can exceed MAX_PROPOSERS once the account is resized
+    pub pending_proposals: SparseArray<u16>, // in-flight proposals of each token
+    pub previous_vaults: SparseArray<(Pubkey, u64)>, // vault replaced by `RotateVault`, with its grace period end
+    pub peer_token_addresses: SparseArray<EthAddress>, // EVM token address of each token index
+    pub rotation_min_delay: u64, // `active_since` of new executors must be later than now + this
+    pub rotation_max_delay: u64, // and earlier than now + this
+    pub retired_tokens: SparseArray<Pubkey>, // mint last removed from each index, until the index is reused
+    pub relayers: Vec<Pubkey>, // sorted; only they may land executes, empty for permissionless
+    pub token_programs: SparseArray<Pubkey>, // token program each token was added with
+    pub pending_deposits: SparseArray<u64>, // held by the vault for in-flight lock/burn/unlock proposals
+    pub event_seq: u64, // sequence number of the last logged event, see `next_event_seq`
+    pub renounce_after: u64, // when a pending admin renounce can be finalized, 0 if none is pending
+    pub open_lock: bool, // any signer can `ProposeLock`, not only listed proposers
+    pub paused_since: u64, // when the admin paused proposals with `SetPaused`, 0 while running
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+impl BorshDeserialize for BasicStorage {
+    /// Storage created by an earlier version ends after `locked_balance` or one of the fields
+    /// appended since, which read as their defaults. Its tokens start with nothing pending
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mint_or_lock = bool::deserialize_reader(reader)?;
+        let admin = Pubkey::deserialize_reader(reader)?;
+        let proposers = Vec::deserialize_reader(reader)?;
+        let executors_group_length = u64::deserialize_reader(reader)?;
+        let tokens = SparseArray::deserialize_reader(reader)?;
+        let vaults = SparseArray::deserialize_reader(reader)?;
+        let decimals = SparseArray::deserialize_reader(reader)?;
+        let locked_balance = SparseArray::deserialize_reader(reader)?;
+        let nothing_pending = SparseArray::zero_for_each(&tokens);
+        let no_pending_deposits = SparseArray::zero_for_each(&tokens);
+        Ok(Self {
+            mint_or_lock,
+            admin,
+            proposers,
+            executors_group_length,
+            tokens,
+            vaults,
+            decimals,
+            locked_balance,
+            min_propose_interval: deserialize_appended(reader, 0)?,
+            proposer_last_propose: deserialize_appended(reader, vec![])?,
+            max_proposers: deserialize_appended(reader, Constants::MAX_PROPOSERS as u8)?,
+            pending_proposals: deserialize_appended(reader, nothing_pending)?,
+            previous_vaults: deserialize_appended(reader, SparseArray::default())?,
+            peer_token_addresses: deserialize_appended(reader, SparseArray::default())?,
+            rotation_min_delay: deserialize_appended(reader, Constants::EXECUTORS_ROTATION_MIN_DELAY)?,
+            rotation_max_delay: deserialize_appended(reader, Constants::EXECUTORS_ROTATION_MAX_DELAY)?,
+            retired_tokens: deserialize_appended(reader, SparseArray::default())?,
+            relayers: deserialize_appended(reader, vec![])?,
+            token_programs: deserialize_appended(reader, SparseArray::default())?,
+            pending_deposits: deserialize_appended(reader, no_pending_deposits)?,
+            event_seq: deserialize_appended(reader, 0)?,
+            renounce_after: deserialize_appended(reader, 0)?,
+            open_lock: deserialize_appended(reader, false)?,
+            paused_since: deserialize_appended(reader, 0)?,
+        })
+    }
+}
+
+impl BasicStorage {
+    /// Advances the event counter and returns the sequence number for the event about to be logged
+    pub fn next_event_seq(&mut self) -> Result<u64, ProgramError> {
+        self.event_seq = self.event_seq.checked_add(1).ok_or(FreeTunnelError::ArithmeticOverflow)?;
+        Ok(self.event_seq)
+    }
+}
+
+#[derive(BorshSerialize, Debug)]
 pub struct ExecutorsInfo {
     pub index: u64,
     pub threshold: u64,
     pub active_since: u64,
     pub inactive_after: u64, // 0 means never inactive
     pub executors: Vec<EthAddress>,
+    pub ed25519_executors: Vec<Pubkey>, // Solana-native executors, signing via `ed25519_program`; absent in older accounts
+}
+
+impl BorshDeserialize for ExecutorsInfo {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            index: u64::deserialize_reader(reader)?,
+            threshold: u64::deserialize_reader(reader)?,
+            active_since: u64::deserialize_reader(reader)?,
+            inactive_after: u64::deserialize_reader(reader)?,
+            executors: Vec::deserialize_reader(reader)?,
+            ed25519_executors: deserialize_appended(reader, vec![])?,
+        })
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ProposedLock {
     pub inner: Pubkey,
+    pub rent_from_vault: bool, // rent paid by the rent vault, which gets it back on cancel
+    pub token_account: Pubkey, // source of the deposit, refunded on cancel; absent in older proposals
+    pub pinned_exe_index: Option<u64>, // executors group the execute must use, `None` for any; absent in older proposals
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ProposedUnlock {
     pub inner: Pubkey,
+    pub proposer: Pubkey, // receives the rent on cancel; absent in proposals created before it was added
+    pub rent_from_vault: bool, // rent paid by the rent vault, which gets it back on cancel instead
+    pub pinned_exe_index: Option<u64>, // executors group the execute must use, `None` for any; absent in older proposals
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ProposedMint {
     pub inner: Pubkey,
+    pub proposer: Pubkey, // receives the rent on cancel; absent in proposals created before it was added
+    pub rent_from_vault: bool, // rent paid by the rent vault, which gets it back on cancel instead
+    pub pinned_exe_index: Option<u64>, // executors group the execute must use, `None` for any; absent in older proposals
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ProposedBurn {
     pub inner: Pubkey,
+    pub rent_from_vault: bool, // rent paid by the rent vault, which gets it back on cancel
+    pub token_account: Pubkey, // source of the deposit, refunded on cancel; absent in older proposals
+    pub pinned_exe_index: Option<u64>, // executors group the execute must use, `None` for any; absent in older proposals
+}
+
+/// Fixed-layout header at the start of every proposal account, so indexers can filter them with
+/// memcmp at the `Constants::PROPOSAL_OFFSET_*` offsets. Borsh encodes it without padding or
+/// length prefixes, in exactly `Constants::SIZE_PROPOSAL_HEADER` bytes
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ProposalHeader {
+    pub discriminator: u8,
+    pub status: u8, // `Constants::PROPOSAL_STATUS_*`
+    pub token_index: u8,
+    pub created_time: u64,
+    pub party: Pubkey,
+    pub amount: u64,
+}
+
+/// Best-effort index of the req_ids a proposer has in flight, stored per proposer. Entries are
+/// pruned on execute/cancel only when the index account is passed, and the oldest entry is
+/// dropped once `Constants::MAX_PROPOSER_ACTIVITY` is reached
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq, Eq)]
+pub struct ProposerActivity {
+    pub proposer: Pubkey,
+    pub req_ids: Vec<[u8; 32]>, // oldest first
+}
+
+impl ProposerActivity {
+    pub fn record(&mut self, req_id: [u8; 32]) {
+        if self.req_ids.len() >= Constants::MAX_PROPOSER_ACTIVITY {
+            self.req_ids.remove(0);
+        }
+        self.req_ids.push(req_id);
+    }
+
+    pub fn prune(&mut self, req_id: &[u8; 32]) -> bool {
+        let len = self.req_ids.len();
+        self.req_ids.retain(|id| id != req_id);
+        self.req_ids.len() != len
+    }
+}
+
+/// Exclusivity claimed by a relayer through `CommitExecution`, stored per req_id
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ExecutionCommit {
+    pub relayer: Pubkey,
+    pub slot: u64, // slot of the commitment, exclusive for `Constants::EXECUTION_COMMIT_SLOTS` after it
+}
+
+impl ExecutionCommit {
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot >= self.slot.saturating_add(Constants::EXECUTION_COMMIT_SLOTS)
+    }
+}
+
+/// Admin parameter change waiting for `Constants::PARAM_CHANGE_DELAY`, stored per admin
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PendingAdminChange {
+    pub parameter_id: u8, // one of `Constants::PARAM_*`
+    pub new_value: Vec<u8>, // borsh-encoded value for the parameter
+    pub effective_at: u64,
+}
+
+/// Returned by `GetTokenInfo` through return data, for clients to decode
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct TokenInfoView {
+    pub token_mint: Pubkey,
+    pub vault: Pubkey,
+    pub decimals: u8,
+    pub locked_balance: u64,
+    pub pending_proposals: u16,
+    pub peer_address: EthAddress,
+}
+
+/// Returned by `VerifySignatures` through return data, for clients to decode
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct SignaturesView {
+    pub bitmask: u64, // bit `i` is set when `signatures[i]` is valid for `executors[i]`
+    pub multisig_error: Option<u64>, // why an execute with this bundle would fail (`u64::from(ProgramError)`), `None` if it would pass
+}
+
+/// Returned by `GetReconciliation` through return data, for clients to decode
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ReconciliationView {
+    pub token_index: u8,
+    pub vault: Pubkey,
+    pub locked_balance: u64,
+    pub vault_balance: u64,
+    pub pending_deposits: u64,
+    pub drift: i128, // vault_balance - locked_balance - pending_deposits, positive for surplus
+}
+
+/// Returned by `GetConfig` through return data, for clients to decode
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ConfigView {
+    pub version: String,
+    pub hub_id: u8,
+    pub channel: String,
+    pub propose_period: u64,
+    pub expire_period: u64,
+    pub expire_extra_period: u64,
+    pub max_tokens: u8,
+    pub max_proposers: u8, // stored in `BasicStorage`
+    pub max_executors: u8,
+    pub mint_or_lock: bool, // stored in `BasicStorage`
+}
+
+/// Returned by `GetExecutorsInfo` through return data, for clients to decode
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ExecutorsInfoView {
+    pub index: u64,
+    pub threshold: u64,
+    pub active_since: u64,
+    pub inactive_after: u64, // 0 means never inactive
+    pub executors: Vec<EthAddress>,
+    pub ed25519_executors: Vec<Pubkey>,
+    pub seconds_until_inactive: Option<u64>, // `None` if never inactive, `Some(0)` once inactive
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalKind {
+    Mint,
+    Burn,
+    Lock,
+    Unlock,
+}
+
+impl ProposalKind {
+    pub fn prefix(&self) -> &'static [u8] {
+        match self {
+            ProposalKind::Mint => Constants::PREFIX_MINT,
+            ProposalKind::Burn => Constants::PREFIX_BURN,
+            ProposalKind::Lock => Constants::PREFIX_LOCK,
+            ProposalKind::Unlock => Constants::PREFIX_UNLOCK,
+        }
+    }
+
+    /// First byte of the proposal account, see `ProposalHeader`
+    pub fn discriminator(&self) -> u8 {
+        match self {
+            ProposalKind::Mint => 1,
+            ProposalKind::Burn => 2,
+            ProposalKind::Lock => 3,
+            ProposalKind::Unlock => 4,
+        }
+    }
+
+    pub fn from_discriminator(discriminator: u8) -> Option<Self> {
+        match discriminator {
+            1 => Some(ProposalKind::Mint),
+            2 => Some(ProposalKind::Burn),
+            3 => Some(ProposalKind::Lock),
+            4 => Some(ProposalKind::Unlock),
+            _ => None,
+        }
+    }
+
+    /// Seconds after the req_id created time when the proposal can be cancelled
+    pub fn expire_period(&self) -> u64 {
+        match self {
+            ProposalKind::Mint | ProposalKind::Unlock => Constants::EXPIRE_EXTRA_PERIOD,
+            ProposalKind::Burn | ProposalKind::Lock => Constants::EXPIRE_PERIOD,
+        }
+    }
 }
 
 // Implement for `TokensAndProposers`
@@ -61,6 +327,13 @@ impl<Value> Default for SparseArray<Value> {
     }
 }
 
+impl<Value: Default> SparseArray<Value> {
+    /// A default value at every id of `other`
+    pub fn zero_for_each<Other>(other: &SparseArray<Other>) -> Self {
+        Self { inner: other.inner.iter().map(|(id, _)| (*id, Value::default())).collect() }
+    }
+}
+
 impl<Value> SparseArray<Value> {
     pub fn insert(&mut self, id: u8, value: Value) -> Result<Option<Value>, ProgramError> {
         match self.inner.binary_search_by_key(&id, |&(k, _)| k) {
@@ -102,6 +375,14 @@ impl<Value> SparseArray<Value> {
     pub fn len(&self) -> usize {
         self.inner.len()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &Value)> {
+        self.inner.iter().map(|(id, value)| (*id, value))
+    }
+
+    pub fn retain<F: FnMut(u8, &Value) -> bool>(&mut self, mut f: F) {
+        self.inner.retain(|(id, value)| f(*id, value));
+    }
 }
 
 impl<Value> Index<u8> for SparseArray<Value> {
@@ -0,0 +1,438 @@
+#[cfg(test)]
+mod atomic_lock_test {
+    use borsh::BorshSerialize;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::Account as TokenAccount;
+
+    use crate::constants::{Constants, EthAddress};
+    use crate::error::FreeTunnelError;
+    use crate::logic::atomic_lock::AtomicLock;
+    use crate::logic::req_helpers::ReqId;
+    use crate::processor::Processor;
+    use crate::state::ProposalKind;
+    use crate::test::runtime_stubs;
+    use crate::test::test_fixtures::{
+        account_data, basic_storage, build_secp256k1_ix_data, executors_info, instructions_sysvar, token_account_data, FixtureAccount,
+    };
+    use crate::utils::DataAccountUtils;
+
+    const NOW: i64 = 1_700_000_000;
+    const TOKEN_INDEX: u8 = 3;
+    const AMOUNT: u64 = 2_500_000; // raw amount, the token has 6 decimals
+    const LAMPORTS: u64 = 1_000_000_000;
+
+    fn instruction_data(variant: u8, fields: impl BorshSerialize) -> Vec<u8> {
+        let mut data = vec![variant];
+        data.extend(borsh::to_vec(&fields).unwrap());
+        data
+    }
+
+    fn lock_req_id(created_time: i64) -> ReqId {
+        let mut data = [0u8; 32];
+        data[1..6].copy_from_slice(&created_time.to_be_bytes()[3..]);
+        data[6] = 1;
+        data[7] = TOKEN_INDEX;
+        data[8..16].copy_from_slice(&AMOUNT.to_be_bytes());
+        data[16] = Constants::HUB_ID;
+        ReqId::new(data)
+    }
+
+    fn token_balance(token_account: &mut FixtureAccount) -> u64 {
+        TokenAccount::unpack(&token_account.to_account_info().data.borrow()).unwrap().amount
+    }
+
+    /// A proposer and its ATA for the bridged token
+    struct Locker {
+        account: FixtureAccount,
+        token_account: FixtureAccount,
+    }
+
+    /// A lock contract with one SPL token, its vault, and the PDA of one lock proposal
+    struct LockContract {
+        program_id: Pubkey,
+        mint: Pubkey,
+        system_program: FixtureAccount,
+        token_program: FixtureAccount,
+        contract_signer: FixtureAccount,
+        vault: FixtureAccount,
+        storage: FixtureAccount,
+        proposed_lock: FixtureAccount,
+        req_id: ReqId,
+    }
+
+    impl LockContract {
+        fn new(proposers: Vec<Pubkey>, open_lock: bool) -> Self {
+            let (program_id, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let contract_signer = FixtureAccount::pda(&program_id, Constants::CONTRACT_SIGNER, b"");
+            let vault_key = get_associated_token_address(&contract_signer.key(), &mint);
+            let vault = FixtureAccount::new(vault_key)
+                .owner(spl_token::id())
+                .writable()
+                .data(token_account_data(mint, contract_signer.key(), 0));
+
+            let mut storage = basic_storage(Pubkey::new_unique(), proposers);
+            storage.mint_or_lock = false;
+            storage.open_lock = open_lock;
+            storage.tokens.insert(TOKEN_INDEX, mint).unwrap();
+            storage.decimals.insert(TOKEN_INDEX, 6).unwrap();
+            storage.vaults.insert(TOKEN_INDEX, vault_key).unwrap();
+            storage.token_programs.insert(TOKEN_INDEX, spl_token::id()).unwrap();
+            storage.locked_balance.insert(TOKEN_INDEX, 0).unwrap();
+            storage.pending_proposals.insert(TOKEN_INDEX, 0).unwrap();
+            storage.pending_deposits.insert(TOKEN_INDEX, 0).unwrap();
+            let mut storage_data = account_data(&storage);
+            storage_data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+
+            let req_id = lock_req_id(NOW);
+            Self {
+                program_id,
+                mint,
+                system_program: FixtureAccount::new(solana_sdk_ids::system_program::ID).executable(),
+                token_program: FixtureAccount::new(spl_token::id()).executable(),
+                contract_signer,
+                vault,
+                storage: FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(storage_data),
+                proposed_lock: FixtureAccount::pda(&program_id, Constants::PREFIX_LOCK, &req_id.data)
+                    .owner(solana_sdk_ids::system_program::ID),
+                req_id,
+            }
+        }
+
+        fn locker(&self, key: Pubkey) -> Locker {
+            Locker {
+                account: FixtureAccount::new(key).signer().writable().lamports(LAMPORTS),
+                token_account: FixtureAccount::new(get_associated_token_address(&key, &self.mint))
+                    .owner(spl_token::id())
+                    .writable()
+                    .data(token_account_data(self.mint, key, AMOUNT)),
+            }
+        }
+
+        fn propose_lock(&mut self, locker: &mut Locker) -> solana_program::entrypoint::ProgramResult {
+            let accounts = [
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+                locker.account.to_account_info(),
+                self.vault.to_account_info(),
+                locker.token_account.to_account_info(),
+                self.storage.to_account_info(),
+                self.proposed_lock.to_account_info(),
+            ];
+            let data = instruction_data(13, (self.req_id.data, None::<u64>));
+            Processor::process_instruction(&self.program_id, &accounts, &data)
+        }
+
+        fn cancel_lock(&mut self, refund: &mut FixtureAccount, token_account: &mut FixtureAccount) -> solana_program::entrypoint::ProgramResult {
+            let accounts = [
+                self.token_program.to_account_info(),
+                self.contract_signer.to_account_info(),
+                self.vault.to_account_info(),
+                token_account.to_account_info(),
+                self.storage.to_account_info(),
+                self.proposed_lock.to_account_info(),
+                refund.to_account_info(),
+            ];
+            Processor::process_instruction(&self.program_id, &accounts, &instruction_data(15, self.req_id.data))
+        }
+
+        /// Executes with a bundle of executor `[1; 20]`, verified by a `secp256k1_program`
+        /// instruction. `execution_commit` is passed after the fixed accounts when set
+        fn execute_lock(&mut self, execution_commit: Option<&mut FixtureAccount>) -> solana_program::entrypoint::ProgramResult {
+            let (executor, signature) = ([1u8; 20], [2u8; 64]);
+            let mut executors = FixtureAccount::pda(&self.program_id, Constants::PREFIX_EXECUTORS, &0u64.to_le_bytes())
+                .data(account_data(&executors_info(vec![executor], 1)));
+            let message = self.req_id.msg_from_req_signing_message();
+            let secp256k1_ix = build_secp256k1_ix_data(executor, signature, 0, &message, 0);
+            let mut sysvar_instructions = instructions_sysvar(&[(solana_sdk_ids::secp256k1_program::ID, secp256k1_ix)]);
+
+            let mut accounts = vec![
+                self.storage.to_account_info(),
+                self.proposed_lock.to_account_info(),
+                executors.to_account_info(),
+            ];
+            accounts.extend(execution_commit.map(|account| account.to_account_info()));
+            accounts.push(sysvar_instructions.to_account_info());
+            let data = instruction_data(14, (self.req_id.data, vec![signature], vec![executor], 0u64));
+            Processor::process_instruction(&self.program_id, &accounts, &data)
+        }
+
+        fn set_paused(&mut self, paused: bool) -> solana_program::entrypoint::ProgramResult {
+            let admin = DataAccountUtils::read_basic_storage(&self.storage.to_account_info()).unwrap().admin;
+            let mut account_admin = FixtureAccount::new(admin).signer();
+            let accounts = [account_admin.to_account_info(), self.storage.to_account_info()];
+            Processor::process_instruction(&self.program_id, &accounts, &instruction_data(54, paused))
+        }
+
+        /// Withdraws `AMOUNT` to `recipient`, signed by `signers` of the group `executors`, each
+        /// verified by its own `secp256k1_program` instruction
+        fn decommission_withdraw(
+            &mut self,
+            executors: Vec<EthAddress>,
+            signers: &[(EthAddress, [u8; 64])],
+            recipient: &mut FixtureAccount,
+        ) -> solana_program::entrypoint::ProgramResult {
+            let basic_storage = DataAccountUtils::read_basic_storage(&self.storage.to_account_info()).unwrap();
+            let message = AtomicLock::msg_from_decommission(TOKEN_INDEX, &self.mint, AMOUNT, &recipient.key(), basic_storage.paused_since);
+            let secp256k1_ixs: Vec<_> = signers
+                .iter()
+                .enumerate()
+                .map(|(i, (executor, signature))| {
+                    (solana_sdk_ids::secp256k1_program::ID, build_secp256k1_ix_data(*executor, *signature, 0, &message, i as u8))
+                })
+                .collect();
+            let mut sysvar_instructions = instructions_sysvar(&secp256k1_ixs);
+            let mut data_account_executors = FixtureAccount::pda(&self.program_id, Constants::PREFIX_EXECUTORS, &0u64.to_le_bytes())
+                .data(account_data(&executors_info(executors, 1)));
+            let mut account_admin = FixtureAccount::new(basic_storage.admin).signer();
+            let (signed_executors, signatures): (Vec<EthAddress>, Vec<[u8; 64]>) = signers.iter().copied().unzip();
+            let data = instruction_data(55, (TOKEN_INDEX, AMOUNT, recipient.key(), signatures, signed_executors, 0u64));
+
+            let accounts = [
+                self.token_program.to_account_info(),
+                account_admin.to_account_info(),
+                self.contract_signer.to_account_info(),
+                self.storage.to_account_info(),
+                data_account_executors.to_account_info(),
+                self.vault.to_account_info(),
+                recipient.to_account_info(),
+                sysvar_instructions.to_account_info(),
+            ];
+            Processor::process_instruction(&self.program_id, &accounts, &data)
+        }
+
+        fn pending_proposals(&mut self) -> u16 {
+            let basic_storage = DataAccountUtils::read_basic_storage(&self.storage.to_account_info()).unwrap();
+            *basic_storage.pending_proposals.get(TOKEN_INDEX).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_unlisted_locker_cancels_expired_lock() {
+        runtime_stubs::set_clock(NOW);
+        let mut contract = LockContract::new(vec![Pubkey::new_unique()], true);
+        let mut locker = contract.locker(Pubkey::new_unique());
+
+        contract.propose_lock(&mut locker).unwrap();
+        assert_eq!((token_balance(&mut locker.token_account), token_balance(&mut contract.vault)), (0, AMOUNT));
+        assert_eq!(contract.pending_proposals(), 1);
+        assert!(locker.account.to_account_info().lamports() < LAMPORTS);
+
+        // Once expired, the locker gets its tokens and rent back although it is not listed
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_PERIOD as i64 + 1);
+        contract.cancel_lock(&mut locker.account, &mut locker.token_account).unwrap();
+        assert_eq!((token_balance(&mut locker.token_account), token_balance(&mut contract.vault)), (AMOUNT, 0));
+        assert_eq!(contract.pending_proposals(), 0);
+        assert!(contract.proposed_lock.to_account_info().data_is_empty());
+        assert_eq!(locker.account.to_account_info().lamports(), LAMPORTS);
+    }
+
+    #[test]
+    fn test_rotated_proposer_cancels_lock_of_old_key() {
+        runtime_stubs::set_clock(NOW);
+        let (old_key, new_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut contract = LockContract::new(vec![old_key], false);
+        let mut locker = contract.locker(old_key);
+        contract.propose_lock(&mut locker).unwrap();
+
+        // The old key leaked and is rotated out while its lock is in flight
+        let accounts = [locker.account.to_account_info(), contract.storage.to_account_info()];
+        Processor::process_instruction(&contract.program_id, &accounts, &instruction_data(48, new_key)).unwrap();
+        let basic_storage = DataAccountUtils::read_basic_storage(&contract.storage.to_account_info()).unwrap();
+        assert_eq!(basic_storage.proposers, vec![new_key]);
+
+        // The lock still refunds the old key, which recorded it
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_PERIOD as i64 + 1);
+        contract.cancel_lock(&mut locker.account, &mut locker.token_account).unwrap();
+        assert_eq!((token_balance(&mut locker.token_account), token_balance(&mut contract.vault)), (AMOUNT, 0));
+        assert_eq!(contract.pending_proposals(), 0);
+        assert_eq!(locker.account.to_account_info().lamports(), LAMPORTS);
+    }
+
+    #[test]
+    fn test_execute_lock_with_and_without_execution_commit_account() {
+        runtime_stubs::set_clock(NOW);
+        let proposer = Pubkey::new_unique();
+        let locked_balance = |contract: &mut LockContract| {
+            let basic_storage = DataAccountUtils::read_basic_storage(&contract.storage.to_account_info()).unwrap();
+            *basic_storage.locked_balance.get(TOKEN_INDEX).unwrap()
+        };
+
+        // Account lists from before execution commitments still execute
+        let mut contract = LockContract::new(vec![proposer], false);
+        let mut locker = contract.locker(proposer);
+        contract.propose_lock(&mut locker).unwrap();
+        contract.execute_lock(None).unwrap();
+        assert_eq!((locked_balance(&mut contract), contract.pending_proposals()), (AMOUNT, 0));
+
+        // So do those passing the commitment PDA, which need not exist
+        let mut contract = LockContract::new(vec![proposer], false);
+        let mut locker = contract.locker(proposer);
+        contract.propose_lock(&mut locker).unwrap();
+        let mut execution_commit = FixtureAccount::pda(&contract.program_id, Constants::PREFIX_EXECUTION_COMMIT, &contract.req_id.data)
+            .owner(solana_sdk_ids::system_program::ID);
+        contract.execute_lock(Some(&mut execution_commit)).unwrap();
+        assert_eq!((locked_balance(&mut contract), contract.pending_proposals()), (AMOUNT, 0));
+    }
+
+    #[test]
+    fn test_sweep_skips_group_with_wrong_refund_account() {
+        runtime_stubs::set_clock(NOW);
+        let (proposer_1, proposer_2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut contract = LockContract::new(vec![proposer_1, proposer_2], false);
+        let mut locker_1 = contract.locker(proposer_1);
+        contract.propose_lock(&mut locker_1).unwrap();
+        let req_id_1 = std::mem::replace(&mut contract.req_id, lock_req_id(NOW - 1));
+        let proposed_lock_2 = FixtureAccount::pda(&contract.program_id, Constants::PREFIX_LOCK, &contract.req_id.data)
+            .owner(solana_sdk_ids::system_program::ID);
+        let mut proposed_lock_1 = std::mem::replace(&mut contract.proposed_lock, proposed_lock_2);
+        let mut locker_2 = contract.locker(proposer_2);
+        contract.propose_lock(&mut locker_2).unwrap();
+
+        // The first group refunds to someone other than its proposer, the second is correct
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_PERIOD as i64 + 1);
+        let mut wrong_refund = FixtureAccount::new(Pubkey::new_unique()).writable();
+        let vault = contract.vault.to_account_info();
+        let accounts = [
+            contract.storage.to_account_info(),
+            contract.token_program.to_account_info(),
+            contract.contract_signer.to_account_info(),
+            proposed_lock_1.to_account_info(),
+            vault.clone(),
+            locker_1.token_account.to_account_info(),
+            wrong_refund.to_account_info(),
+            contract.proposed_lock.to_account_info(),
+            vault,
+            locker_2.token_account.to_account_info(),
+            locker_2.account.to_account_info(),
+        ];
+        let data = instruction_data(23, (ProposalKind::Lock, vec![req_id_1.data, contract.req_id.data]));
+        Processor::process_instruction(&contract.program_id, &accounts, &data).unwrap();
+        drop(accounts);
+
+        assert_eq!(runtime_stubs::return_data(), 0b10u64.to_le_bytes());
+        assert!(!proposed_lock_1.to_account_info().data_is_empty());
+        assert!(contract.proposed_lock.to_account_info().data_is_empty());
+        assert_eq!((token_balance(&mut locker_1.token_account), token_balance(&mut locker_2.token_account)), (0, AMOUNT));
+        assert_eq!(contract.pending_proposals(), 1);
+    }
+
+    #[test]
+    fn test_claim_stuck_unlock_releases_header_amount() {
+        runtime_stubs::set_clock(NOW);
+        let mut contract = LockContract::new(vec![], false);
+        let storage = contract.storage.to_account_info();
+        let mut basic_storage = DataAccountUtils::read_basic_storage(&storage).unwrap();
+        let admin = basic_storage.admin;
+        // As left by a proposed unlock of `AMOUNT` out of a locked balance of `AMOUNT`
+        *basic_storage.pending_proposals.get_mut(TOKEN_INDEX).unwrap() = 1;
+        *basic_storage.pending_deposits.get_mut(TOKEN_INDEX).unwrap() = AMOUNT;
+        DataAccountUtils::write_account_data(&storage, basic_storage).unwrap();
+
+        let req_id = lock_req_id(NOW);
+        let claim = |contract: &mut LockContract, proposal_data: Vec<u8>| {
+            let mut fixtures = [
+                FixtureAccount::new(admin).signer(),
+                FixtureAccount::pda(&contract.program_id, Constants::PREFIX_UNLOCK, &req_id.data).data(proposal_data),
+                FixtureAccount::new(admin).writable().lamports(LAMPORTS),
+            ];
+            let [account_admin, proposal, refund] = &mut fixtures;
+            let accounts = [account_admin.to_account_info(), contract.storage.to_account_info(), proposal.to_account_info(), refund.to_account_info()];
+            Processor::process_instruction(&contract.program_id, &accounts, &instruction_data(19, (req_id.data, ProposalKind::Unlock)))
+        };
+        let balances = |contract: &mut LockContract| {
+            let basic_storage = DataAccountUtils::read_basic_storage(&contract.storage.to_account_info()).unwrap();
+            (
+                *basic_storage.locked_balance.get(TOKEN_INDEX).unwrap(),
+                *basic_storage.pending_proposals.get(TOKEN_INDEX).unwrap(),
+                *basic_storage.pending_deposits.get(TOKEN_INDEX).unwrap(),
+            )
+        };
+        let undecodable = vec![0xff; 3];
+
+        // Created before the header: nothing tells the amount, so the counters stay
+        claim(&mut contract, account_data(&undecodable)).unwrap();
+        assert_eq!(balances(&mut contract), (0, 1, AMOUNT));
+
+        // The header holds the amount back from the locked balance until released
+        let mut proposal_data = borsh::to_vec(&req_id.proposal_header(ProposalKind::Unlock, Pubkey::default(), AMOUNT)).unwrap();
+        proposal_data.extend(account_data(&undecodable));
+        claim(&mut contract, proposal_data.clone()).unwrap();
+        assert_eq!(balances(&mut contract), (AMOUNT, 0, 0));
+
+        // An executed header is never claimed, whatever follows it
+        proposal_data[Constants::PROPOSAL_OFFSET_STATUS] = Constants::PROPOSAL_STATUS_EXECUTED;
+        assert_eq!(claim(&mut contract, proposal_data), Err(FreeTunnelError::ProposalNotStuck.into()));
+    }
+
+    #[test]
+    fn test_pause_blocks_proposals_but_not_executes() {
+        runtime_stubs::set_clock(NOW);
+        let proposer = Pubkey::new_unique();
+        let mut contract = LockContract::new(vec![proposer], false);
+        let mut locker = contract.locker(proposer);
+        contract.propose_lock(&mut locker).unwrap();
+
+        contract.set_paused(true).unwrap();
+        contract.execute_lock(None).unwrap();
+        contract.req_id = lock_req_id(NOW - 1);
+        contract.proposed_lock = FixtureAccount::pda(&contract.program_id, Constants::PREFIX_LOCK, &contract.req_id.data)
+            .owner(solana_sdk_ids::system_program::ID);
+        let mut locker = contract.locker(proposer);
+        assert_eq!(contract.propose_lock(&mut locker), Err(FreeTunnelError::BridgePaused.into()));
+
+        contract.set_paused(false).unwrap();
+        contract.propose_lock(&mut locker).unwrap();
+    }
+
+    #[test]
+    fn test_decommission_withdraw_needs_long_pause_and_every_executor() {
+        runtime_stubs::set_clock(NOW);
+        let proposer = Pubkey::new_unique();
+        let mut contract = LockContract::new(vec![proposer], false);
+        let mut locker = contract.locker(proposer);
+        contract.propose_lock(&mut locker).unwrap();
+        contract.execute_lock(None).unwrap();
+
+        let (executor_1, executor_2) = ([1u8; 20], [3u8; 20]);
+        let (signer_1, signer_2) = ((executor_1, [2u8; 64]), (executor_2, [4u8; 64]));
+        let executors = vec![executor_1, executor_2];
+        let mut recipient = FixtureAccount::new(Pubkey::new_unique())
+            .owner(spl_token::id())
+            .writable()
+            .data(token_account_data(contract.mint, Pubkey::new_unique(), 0));
+        let locked_balance = |contract: &mut LockContract| {
+            *DataAccountUtils::read_basic_storage(&contract.storage.to_account_info()).unwrap().locked_balance.get(TOKEN_INDEX).unwrap()
+        };
+        let not_ready = Err(FreeTunnelError::DecommissionNotReady.into());
+
+        // Never paused
+        assert_eq!(contract.decommission_withdraw(executors.clone(), &[signer_1, signer_2], &mut recipient), not_ready);
+
+        // Paused, but a proposal from just before the pause could still be live
+        contract.set_paused(true).unwrap();
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_EXTRA_PERIOD as i64 - 1);
+        assert_eq!(contract.decommission_withdraw(executors.clone(), &[signer_1, signer_2], &mut recipient), not_ready);
+
+        // A threshold of executors is not enough, nor is a signer outside the group
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_EXTRA_PERIOD as i64);
+        assert_eq!(
+            contract.decommission_withdraw(executors.clone(), &[signer_2], &mut recipient),
+            Err(FreeTunnelError::DecommissionQuorumIncomplete.into()),
+        );
+        assert_eq!(
+            contract.decommission_withdraw(vec![executor_1], &[signer_1, signer_2], &mut recipient),
+            Err(FreeTunnelError::NonExecutors.into()),
+        );
+        assert_eq!(locked_balance(&mut contract), AMOUNT);
+
+        contract.decommission_withdraw(executors.clone(), &[signer_1, signer_2], &mut recipient).unwrap();
+        assert_eq!(locked_balance(&mut contract), 0);
+        assert_eq!((token_balance(&mut recipient), token_balance(&mut contract.vault)), (AMOUNT, 0));
+
+        // Unpausing restarts the wait
+        contract.set_paused(false).unwrap();
+        contract.set_paused(true).unwrap();
+        assert_eq!(contract.decommission_withdraw(executors, &[signer_1, signer_2], &mut recipient), not_ready);
+    }
+}
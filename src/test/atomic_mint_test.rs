@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod atomic_mint_test {
+    use borsh::BorshSerialize;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::Account as TokenAccount;
+
+    use crate::constants::Constants;
+    use crate::logic::req_helpers::ReqId;
+    use crate::processor::Processor;
+    use crate::test::runtime_stubs;
+    use crate::test::test_fixtures::{account_data, basic_storage, token_account_data, FixtureAccount};
+    use crate::utils::DataAccountUtils;
+
+    const NOW: i64 = 1_700_000_000;
+    const TOKEN_INDEX: u8 = 5;
+    const AMOUNT: u64 = 700_000; // raw amount, the token has 6 decimals
+    const LAMPORTS: u64 = 1_000_000_000;
+
+    fn instruction_data(variant: u8, fields: impl BorshSerialize) -> Vec<u8> {
+        let mut data = vec![variant];
+        data.extend(borsh::to_vec(&fields).unwrap());
+        data
+    }
+
+    /// Burn-unlock back to the mint side
+    fn burn_req_id(created_time: i64) -> ReqId {
+        let mut data = [0u8; 32];
+        data[1..6].copy_from_slice(&created_time.to_be_bytes()[3..]);
+        data[6] = 2;
+        data[7] = TOKEN_INDEX;
+        data[8..16].copy_from_slice(&AMOUNT.to_be_bytes());
+        data[17] = Constants::HUB_ID;
+        ReqId::new(data)
+    }
+
+    fn token_balance(token_account: &mut FixtureAccount) -> u64 {
+        TokenAccount::unpack(&token_account.to_account_info().data.borrow()).unwrap().amount
+    }
+
+    #[test]
+    fn test_unlisted_burner_cancels_expired_burn() {
+        runtime_stubs::set_clock(NOW);
+        let (program_id, mint, burner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let req_id = burn_req_id(NOW);
+
+        let mut contract_signer = FixtureAccount::pda(&program_id, Constants::CONTRACT_SIGNER, b"");
+        let vault_key = get_associated_token_address(&contract_signer.key(), &mint);
+        let mut vault = FixtureAccount::new(vault_key)
+            .owner(spl_token::id())
+            .writable()
+            .data(token_account_data(mint, contract_signer.key(), 0));
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![Pubkey::new_unique()]);
+        storage.tokens.insert(TOKEN_INDEX, mint).unwrap();
+        storage.decimals.insert(TOKEN_INDEX, 6).unwrap();
+        storage.vaults.insert(TOKEN_INDEX, vault_key).unwrap();
+        storage.token_programs.insert(TOKEN_INDEX, spl_token::id()).unwrap();
+        storage.pending_proposals.insert(TOKEN_INDEX, 0).unwrap();
+        storage.pending_deposits.insert(TOKEN_INDEX, 0).unwrap();
+        let mut storage_data = account_data(&storage);
+        storage_data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(storage_data);
+        let mut proposed_burn = FixtureAccount::pda(&program_id, Constants::PREFIX_BURN, &req_id.data)
+            .owner(solana_sdk_ids::system_program::ID);
+
+        let mut system_program = FixtureAccount::new(solana_sdk_ids::system_program::ID).executable();
+        let mut token_program = FixtureAccount::new(spl_token::id()).executable();
+        let mut account_burner = FixtureAccount::new(burner).signer().writable().lamports(LAMPORTS);
+        let mut token_account_burner = FixtureAccount::new(get_associated_token_address(&burner, &mint))
+            .owner(spl_token::id())
+            .writable()
+            .data(token_account_data(mint, burner, AMOUNT));
+
+        let accounts = [
+            system_program.to_account_info(),
+            token_program.to_account_info(),
+            account_burner.to_account_info(),
+            vault.to_account_info(),
+            token_account_burner.to_account_info(),
+            storage.to_account_info(),
+            proposed_burn.to_account_info(),
+        ];
+        Processor::process_instruction(&program_id, &accounts, &instruction_data(10, (req_id.data, None::<u64>))).unwrap();
+        assert_eq!((token_balance(&mut token_account_burner), token_balance(&mut vault)), (0, AMOUNT));
+
+        // Once expired, the burner gets its tokens and rent back although it is not listed
+        runtime_stubs::set_clock(NOW + Constants::EXPIRE_PERIOD as i64 + 1);
+        let accounts = [
+            token_program.to_account_info(),
+            contract_signer.to_account_info(),
+            vault.to_account_info(),
+            token_account_burner.to_account_info(),
+            storage.to_account_info(),
+            proposed_burn.to_account_info(),
+            account_burner.to_account_info(),
+        ];
+        Processor::process_instruction(&program_id, &accounts, &instruction_data(12, req_id.data)).unwrap();
+        assert_eq!((token_balance(&mut token_account_burner), token_balance(&mut vault)), (AMOUNT, 0));
+        assert!(proposed_burn.to_account_info().data_is_empty());
+        assert_eq!(account_burner.to_account_info().lamports(), LAMPORTS);
+        let basic_storage = DataAccountUtils::read_basic_storage(&storage.to_account_info()).unwrap();
+        assert_eq!(basic_storage.pending_proposals.get(TOKEN_INDEX), Some(&0));
+    }
+}
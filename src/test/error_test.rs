@@ -0,0 +1,13 @@
+#[cfg(test)]
+mod error_test {
+    use crate::error::FreeTunnelError;
+    use solana_program::program_error::ProgramError;
+
+    // Relayers branch on these numbers, so they must never shift
+    #[test]
+    fn test_req_id_error_codes() {
+        assert_eq!(ProgramError::from(FreeTunnelError::ReqIdOccupied), ProgramError::Custom(50));
+        assert_eq!(ProgramError::from(FreeTunnelError::ReqIdExecuted), ProgramError::Custom(57));
+        assert_eq!(ProgramError::from(FreeTunnelError::ReqIdNotFound), ProgramError::Custom(62));
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod execution_commit_test {
+    use solana_program::{keccak, pubkey::Pubkey};
+
+    use crate::constants::Constants;
+    use crate::error::{DataAccountError, FreeTunnelError};
+    use crate::logic::execution_commit::ExecutionCommits;
+    use crate::logic::req_helpers::ReqId;
+    use crate::state::ExecutionCommit;
+    use crate::test::test_fixtures::{account_data, FixtureAccount};
+
+    const SLOT: u64 = 250_000_000;
+
+    #[test]
+    fn test_commitment_binds_relayer_and_req_id() {
+        let (relayer, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let req_id = ReqId::new([7; 32]);
+        let expected = keccak::hashv(&[relayer.as_ref(), &[7; 32]]).to_bytes();
+        assert_eq!(ExecutionCommits::commitment_of(&relayer, &req_id), expected);
+        assert_ne!(ExecutionCommits::commitment_of(&other, &req_id), expected);
+        assert_ne!(ExecutionCommits::commitment_of(&relayer, &ReqId::new([8; 32])), expected);
+    }
+
+    #[test]
+    fn test_no_commitment_lets_anyone_execute() {
+        let program_id = Pubkey::new_unique();
+        let req_id = ReqId::new([7; 32]);
+        let mut commit = FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTION_COMMIT, &req_id.data)
+            .owner(solana_sdk_ids::system_program::ID);
+        let mut anyone = FixtureAccount::new(Pubkey::new_unique()).signer();
+        let commit = commit.to_account_info();
+
+        assert!(ExecutionCommits::assert_may_execute(&program_id, None, None, &req_id).is_ok());
+        assert!(ExecutionCommits::assert_may_execute(&program_id, Some(&commit), None, &req_id).is_ok());
+        assert!(ExecutionCommits::assert_may_execute(&program_id, Some(&commit), Some(&anyone.to_account_info()), &req_id).is_ok());
+
+        // The commitment PDA of another req_id is rejected
+        assert_eq!(
+            ExecutionCommits::assert_may_execute(&program_id, Some(&commit), None, &ReqId::new([8; 32])).unwrap_err(),
+            DataAccountError::PdaAccountMismatch.into(),
+        );
+    }
+
+    #[test]
+    fn test_commitment_window_is_exclusive() {
+        let (relayer, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let execution_commit = ExecutionCommit { relayer, slot: SLOT };
+        let check = |key: Option<&Pubkey>, is_signer: bool, slot: u64| {
+            let mut account = key.map(|key| {
+                let account = FixtureAccount::new(*key);
+                if is_signer { account.signer() } else { account }
+            });
+            let account = account.as_mut().map(|account| account.to_account_info());
+            ExecutionCommits::assert_commit_allows(&execution_commit, account.as_ref(), slot)
+        };
+        let last_exclusive_slot = SLOT + Constants::EXECUTION_COMMIT_SLOTS - 1;
+
+        assert!(check(Some(&relayer), true, SLOT).is_ok());
+        assert!(check(Some(&relayer), true, last_exclusive_slot).is_ok());
+        for slot in [SLOT, last_exclusive_slot] {
+            assert_eq!(check(Some(&other), true, slot).unwrap_err(), FreeTunnelError::NotCommittingRelayer.into());
+            assert_eq!(check(Some(&relayer), false, slot).unwrap_err(), FreeTunnelError::NotCommittingRelayer.into());
+            assert_eq!(check(None, false, slot).unwrap_err(), FreeTunnelError::NotCommittingRelayer.into());
+        }
+    }
+
+    #[test]
+    fn test_expired_commitment_can_be_taken_over() {
+        let (relayer, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let execution_commit = ExecutionCommit { relayer, slot: SLOT };
+        let expiry = SLOT + Constants::EXECUTION_COMMIT_SLOTS;
+        assert!(!execution_commit.is_expired(expiry - 1));
+        assert!(execution_commit.is_expired(expiry));
+
+        let mut other = FixtureAccount::new(other).signer();
+        assert!(ExecutionCommits::assert_commit_allows(&execution_commit, Some(&other.to_account_info()), expiry).is_ok());
+        assert!(ExecutionCommits::assert_commit_allows(&execution_commit, None, expiry).is_ok());
+    }
+
+    #[test]
+    fn test_commitment_account_layout() {
+        let execution_commit = ExecutionCommit { relayer: Pubkey::new_unique(), slot: SLOT };
+        let data = account_data(&execution_commit);
+        assert_eq!(data.len(), Constants::SIZE_LENGTH + Constants::SIZE_EXECUTION_COMMIT);
+    }
+}
@@ -0,0 +1,252 @@
+#[cfg(test)]
+mod instruction_test {
+    use crate::instruction::FreeTunnelInstruction;
+    use crate::logic::req_helpers::ReqId;
+    use crate::state::ProposalKind;
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+
+    fn req_id() -> ReqId {
+        ReqId::new([0; 32])
+    }
+
+    #[test]
+    fn test_required_signer_admin_instructions() {
+        let initialize = FreeTunnelInstruction::Initialize {
+            is_mint_contract: true,
+            executors: vec![],
+            threshold: 1,
+            exe_index: 0,
+            max_proposers: 0,
+        };
+        assert_eq!(initialize.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::InitializeBasicStorage { is_mint_contract: true, max_proposers: 0 }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::InitializeExecutors { executors: vec![], threshold: 1, exe_index: 0 }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::TransferAdmin { new_admin: Pubkey::default() }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::AddProposer { new_proposer: Pubkey::default() }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::RemoveProposer { proposer: Pubkey::default() }.required_signer(), Some(0));
+        let add_token = FreeTunnelInstruction::AddToken {
+            token_index: 1,
+            peer_address: [0; 20],
+            is_token_2022: false,
+            confirm_reuse: false,
+            allow_high_decimals: false,
+        };
+        assert_eq!(add_token.required_signer(), Some(2));
+        assert_eq!(FreeTunnelInstruction::RemoveToken { token_index: 1 }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::ClaimStuckProposal { req_id: req_id(), kind: ProposalKind::Mint }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::SetMinProposeInterval { interval: 0 }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::SetMaxProposers { max: 32 }.required_signer(), Some(1));
+        let force_cancel = FreeTunnelInstruction::ForceCancel {
+            req_id: req_id(),
+            kind: ProposalKind::Unlock,
+            signatures: vec![],
+            executors: vec![],
+            exe_index: 0,
+        };
+        assert_eq!(force_cancel.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::ProposeParamChange { parameter_id: 0, new_value: vec![] }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::ApplyParamChange.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::SetExecutorRotationWindow { min_delay: 3600, max_delay: 7200 }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::RotateVault { token_index: 1 }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::FundRentVault { lamports: 1 }.required_signer(), Some(1));
+        let initialize_with_tokens = FreeTunnelInstruction::InitializeWithTokens {
+            is_mint_contract: true,
+            executors: vec![],
+            threshold: 1,
+            exe_index: 0,
+            tokens: vec![(1, Pubkey::default(), 6)],
+        };
+        assert_eq!(initialize_with_tokens.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::GetLockedBalance { token_index: 1 }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::VerifyReqId { req_id: ReqId::new([0; 32]) }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::TransferTokens { token_index: 1, amount: 1 }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::AddRelayer { relayer: Pubkey::default() }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::RemoveRelayer { relayer: Pubkey::default() }.required_signer(), Some(0));
+        let commit_execution = FreeTunnelInstruction::CommitExecution { req_id: req_id(), commitment: [0; 32] };
+        assert_eq!(commit_execution.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::CloseExecutionCommit { req_id: req_id() }.required_signer(), Some(0));
+    }
+
+    #[test]
+    fn test_required_signer_update_executors() {
+        let update_executors = FreeTunnelInstruction::UpdateExecutors {
+            new_executors: vec![],
+            new_ed25519_executors: vec![],
+            threshold: 1,
+            active_since: 0,
+            signatures: vec![],
+            executors: vec![],
+            exe_index: 0,
+        };
+        assert_eq!(update_executors.required_signer(), Some(1));
+    }
+
+    #[test]
+    fn test_required_signer_propose_instructions() {
+        assert_eq!(FreeTunnelInstruction::ProposeMint { req_id: req_id(), recipient: Pubkey::default(), pinned_exe_index: None }.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::ProposeBurn { req_id: req_id(), pinned_exe_index: None }.required_signer(), Some(2));
+        assert_eq!(FreeTunnelInstruction::ProposeLock { req_id: req_id(), pinned_exe_index: None }.required_signer(), Some(2));
+        assert_eq!(FreeTunnelInstruction::ProposeUnlock { req_id: req_id(), recipient: Pubkey::default(), pinned_exe_index: None }.required_signer(), Some(1));
+    }
+
+    #[test]
+    fn test_required_signer_permissionless_instructions() {
+        let execute_mint = FreeTunnelInstruction::ExecuteMint { req_id: req_id(), signatures: vec![], executors: vec![], exe_index: 0 };
+        let execute_burn = FreeTunnelInstruction::ExecuteBurn { req_id: req_id(), signatures: vec![], executors: vec![], exe_index: 0 };
+        let execute_lock = FreeTunnelInstruction::ExecuteLock { req_id: req_id(), signatures: vec![], executors: vec![], exe_index: 0 };
+        let execute_unlock = FreeTunnelInstruction::ExecuteUnlock { req_id: req_id(), signatures: vec![], executors: vec![], exe_index: 0 };
+        let verify_signatures = FreeTunnelInstruction::VerifySignatures { req_id: req_id(), signatures: vec![], executors: vec![], exe_index: 0 };
+        assert_eq!(execute_mint.required_signer(), None);
+        assert_eq!(execute_burn.required_signer(), None);
+        assert_eq!(execute_lock.required_signer(), None);
+        assert_eq!(execute_unlock.required_signer(), None);
+        assert_eq!(verify_signatures.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::CancelMint { req_id: req_id() }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::CancelBurn { req_id: req_id() }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::CancelLock { req_id: req_id() }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::CancelUnlock { req_id: req_id() }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::SweepExpired { kind: ProposalKind::Lock, req_ids: vec![] }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::GetConfig.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::QueryProposalState { req_id: req_id(), kind: ProposalKind::Mint }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::GetTokenInfo { token_index: 1 }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::GetReconciliation { token_index: 1 }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::RenounceProposer.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::RotateProposerKey { new_key: Pubkey::default() }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::InitiateAdminRenounce.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::CancelAdminRenounce.required_signer(), Some(0));
+        let finalize = FreeTunnelInstruction::FinalizeAdminRenounce { force: false, signatures: vec![], executors: vec![], exe_index: 0 };
+        assert_eq!(finalize.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::SetOpenLock { open_lock: true }.required_signer(), Some(0));
+        assert_eq!(FreeTunnelInstruction::MigrateBasicStorage.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::SetPaused { paused: true }.required_signer(), Some(0));
+        let decommission_withdraw = FreeTunnelInstruction::DecommissionWithdraw {
+            token_index: 1,
+            amount: 1,
+            recipient: Pubkey::default(),
+            signatures: vec![],
+            executors: vec![],
+            exe_index: 0,
+        };
+        assert_eq!(decommission_withdraw.required_signer(), Some(1));
+        assert_eq!(FreeTunnelInstruction::GetExecutorsInfo { exe_index: 0 }.required_signer(), None);
+        assert_eq!(FreeTunnelInstruction::GetProposerActivity { proposer: Pubkey::default() }.required_signer(), None);
+    }
+
+    fn instruction_data(variant: u8, fields: impl BorshSerialize) -> Vec<u8> {
+        let mut data = vec![variant];
+        data.extend(borsh::to_vec(&fields).unwrap());
+        data
+    }
+
+    #[test]
+    fn test_unpack_discriminants() {
+        use FreeTunnelInstruction as Ix;
+        let req_id = [0u8; 32];
+        let no_signatures: Vec<[u8; 64]> = vec![];
+        let no_executors: Vec<[u8; 20]> = vec![];
+        let execute = (req_id, no_signatures.clone(), no_executors.clone(), 0u64);
+        let cases: Vec<(Vec<u8>, fn(&Ix) -> bool)> = vec![
+            (instruction_data(0, (true, no_executors.clone(), 1u64, 0u64, 0u8)), |ix| matches!(ix, Ix::Initialize { .. })),
+            (instruction_data(1, Pubkey::default()), |ix| matches!(ix, Ix::TransferAdmin { .. })),
+            (instruction_data(2, Pubkey::default()), |ix| matches!(ix, Ix::AddProposer { .. })),
+            (instruction_data(3, Pubkey::default()), |ix| matches!(ix, Ix::RemoveProposer { .. })),
+            (
+                instruction_data(4, (no_executors.clone(), Vec::<Pubkey>::new(), 1u64, 0u64, no_signatures.clone(), no_executors.clone(), 0u64)),
+                |ix| matches!(ix, Ix::UpdateExecutors { .. }),
+            ),
+            (instruction_data(5, (1u8, [0u8; 20], false, false, false)), |ix| matches!(ix, Ix::AddToken { .. })),
+            (instruction_data(6, 1u8), |ix| matches!(ix, Ix::RemoveToken { .. })),
+            (instruction_data(7, (req_id, Pubkey::default(), None::<u64>)), |ix| matches!(ix, Ix::ProposeMint { .. })),
+            (instruction_data(8, execute.clone()), |ix| matches!(ix, Ix::ExecuteMint { .. })),
+            (instruction_data(9, req_id), |ix| matches!(ix, Ix::CancelMint { .. })),
+            (instruction_data(10, (req_id, Some(2u64))), |ix| matches!(ix, Ix::ProposeBurn { pinned_exe_index: Some(2), .. })),
+            (instruction_data(11, execute.clone()), |ix| matches!(ix, Ix::ExecuteBurn { .. })),
+            (instruction_data(12, req_id), |ix| matches!(ix, Ix::CancelBurn { .. })),
+            (instruction_data(13, (req_id, None::<u64>)), |ix| matches!(ix, Ix::ProposeLock { pinned_exe_index: None, .. })),
+            (instruction_data(14, execute.clone()), |ix| matches!(ix, Ix::ExecuteLock { .. })),
+            (instruction_data(15, req_id), |ix| matches!(ix, Ix::CancelLock { .. })),
+            (instruction_data(16, (req_id, Pubkey::default(), Some(0u64))), |ix| matches!(ix, Ix::ProposeUnlock { pinned_exe_index: Some(0), .. })),
+            (instruction_data(17, execute.clone()), |ix| matches!(ix, Ix::ExecuteUnlock { .. })),
+            (instruction_data(18, req_id), |ix| matches!(ix, Ix::CancelUnlock { .. })),
+            (instruction_data(19, (req_id, ProposalKind::Burn)), |ix| matches!(ix, Ix::ClaimStuckProposal { kind: ProposalKind::Burn, .. })),
+            (instruction_data(20, 60u64), |ix| matches!(ix, Ix::SetMinProposeInterval { interval: 60 })),
+            (instruction_data(21, execute.clone()), |ix| matches!(ix, Ix::VerifySignatures { .. })),
+            (instruction_data(22, 16u8), |ix| matches!(ix, Ix::SetMaxProposers { max: 16 })),
+            (instruction_data(23, (ProposalKind::Lock, vec![req_id])), |ix| matches!(ix, Ix::SweepExpired { kind: ProposalKind::Lock, .. })),
+            (vec![24], |ix| matches!(ix, Ix::GetConfig)),
+            (instruction_data(25, (req_id, ProposalKind::Unlock)), |ix| matches!(ix, Ix::QueryProposalState { kind: ProposalKind::Unlock, .. })),
+            (instruction_data(26, 3u8), |ix| matches!(ix, Ix::GetTokenInfo { token_index: 3 })),
+            (instruction_data(27, (false, 0u8)), |ix| matches!(ix, Ix::InitializeBasicStorage { is_mint_contract: false, .. })),
+            (instruction_data(28, (no_executors.clone(), 1u64, 0u64)), |ix| matches!(ix, Ix::InitializeExecutors { threshold: 1, .. })),
+            (
+                instruction_data(29, (req_id, ProposalKind::Lock, no_signatures.clone(), no_executors.clone(), 0u64)),
+                |ix| matches!(ix, Ix::ForceCancel { kind: ProposalKind::Lock, .. }),
+            ),
+            (instruction_data(30, 2u8), |ix| matches!(ix, Ix::RotateVault { token_index: 2 })),
+            (instruction_data(31, 5u64), |ix| matches!(ix, Ix::GetExecutorsInfo { exe_index: 5 })),
+            (instruction_data(32, (0u8, vec![0u8; 8])), |ix| matches!(ix, Ix::ProposeParamChange { parameter_id: 0, .. })),
+            (vec![33], |ix| matches!(ix, Ix::ApplyParamChange)),
+            (instruction_data(34, (3600u64, 7200u64)), |ix| matches!(ix, Ix::SetExecutorRotationWindow { min_delay: 3600, max_delay: 7200 })),
+            (instruction_data(35, Pubkey::default()), |ix| matches!(ix, Ix::GetProposerActivity { .. })),
+            (instruction_data(36, 1_000_000u64), |ix| matches!(ix, Ix::FundRentVault { lamports: 1_000_000 })),
+            (
+                instruction_data(37, (false, no_executors.clone(), 1u64, 0u64, vec![(1u8, Pubkey::default(), 6u8)])),
+                |ix| matches!(ix, Ix::InitializeWithTokens { is_mint_contract: false, tokens, .. } if tokens.len() == 1),
+            ),
+            (instruction_data(38, 4u8), |ix| matches!(ix, Ix::GetLockedBalance { token_index: 4 })),
+            (instruction_data(39, [7u8; 32]), |ix| matches!(ix, Ix::VerifyReqId { req_id } if req_id.data == [7; 32])),
+            (instruction_data(41, (2u8, 500u64)), |ix| matches!(ix, Ix::TransferTokens { token_index: 2, amount: 500 })),
+            (instruction_data(42, Pubkey::default()), |ix| matches!(ix, Ix::AddRelayer { .. })),
+            (instruction_data(43, Pubkey::default()), |ix| matches!(ix, Ix::RemoveRelayer { .. })),
+            (instruction_data(44, ([7u8; 32], [9u8; 32])), |ix| matches!(ix, Ix::CommitExecution { commitment, .. } if commitment == &[9; 32])),
+            (instruction_data(45, [7u8; 32]), |ix| matches!(ix, Ix::CloseExecutionCommit { req_id } if req_id.data == [7; 32])),
+            (instruction_data(46, 3u8), |ix| matches!(ix, Ix::GetReconciliation { token_index: 3 })),
+            (vec![47], |ix| matches!(ix, Ix::RenounceProposer)),
+            (instruction_data(48, Pubkey::default()), |ix| matches!(ix, Ix::RotateProposerKey { .. })),
+            (vec![49], |ix| matches!(ix, Ix::InitiateAdminRenounce)),
+            (vec![50], |ix| matches!(ix, Ix::CancelAdminRenounce)),
+            (
+                instruction_data(51, (true, no_signatures.clone(), no_executors.clone(), 2u64)),
+                |ix| matches!(ix, Ix::FinalizeAdminRenounce { force: true, exe_index: 2, .. }),
+            ),
+            (instruction_data(52, true), |ix| matches!(ix, Ix::SetOpenLock { open_lock: true })),
+            (vec![53], |ix| matches!(ix, Ix::MigrateBasicStorage)),
+            (instruction_data(54, true), |ix| matches!(ix, Ix::SetPaused { paused: true })),
+            (
+                instruction_data(55, (2u8, 500u64, Pubkey::default(), no_signatures.clone(), no_executors.clone(), 1u64)),
+                |ix| matches!(ix, Ix::DecommissionWithdraw { token_index: 2, amount: 500, exe_index: 1, .. }),
+            ),
+        ];
+        // Every variant once, in order, skipping 40 (the immediate `RenounceAdmin`, now removed)
+        let variants = (0..40).chain(41..56);
+        assert!(cases.iter().map(|(data, _)| data[0]).eq(variants));
+        for (data, is_expected) in cases.iter() {
+            let ix = FreeTunnelInstruction::unpack(data).unwrap();
+            assert!(is_expected(&ix), "variant {} unpacked as {:?}", data[0], ix);
+        }
+        assert!(FreeTunnelInstruction::unpack(&[40]).is_err());
+        assert!(FreeTunnelInstruction::unpack(&[56]).is_err());
+        assert!(FreeTunnelInstruction::unpack(&[]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_add_token_peer_address() {
+        let peer_address = [0xab; 20];
+        let ix = FreeTunnelInstruction::unpack(&instruction_data(5, (7u8, peer_address, true, true, false))).unwrap();
+        match ix {
+            FreeTunnelInstruction::AddToken { token_index, peer_address: unpacked, is_token_2022, confirm_reuse, allow_high_decimals } => {
+                assert_eq!(token_index, 7);
+                assert_eq!(unpacked, peer_address);
+                assert!(is_token_2022);
+                assert!(confirm_reuse);
+                assert!(!allow_high_decimals);
+            }
+            _ => panic!("unexpected instruction {:?}", ix),
+        }
+        // The peer address and all flags are required
+        assert!(FreeTunnelInstruction::unpack(&instruction_data(5, (7u8, peer_address))).is_err());
+        assert!(FreeTunnelInstruction::unpack(&instruction_data(5, (7u8, peer_address, true))).is_err());
+        assert!(FreeTunnelInstruction::unpack(&instruction_data(5, (7u8, peer_address, true, true))).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+#[cfg(test)]
+mod permissions_test {
+    use solana_program::{keccak, pubkey::Pubkey};
+
+    use crate::constants::Constants;
+    use crate::error::FreeTunnelError;
+    use crate::logic::permissions::Permissions;
+    use crate::logic::rent_vault::RentVault;
+    use crate::state::{BasicStorage, ExecutorsInfo};
+    use crate::test::runtime_stubs;
+    use crate::test::test_fixtures::{account_data, basic_storage, executors_info, FixtureAccount};
+    use crate::utils::DataAccountUtils;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn test_assert_only_admin_errors() {
+        let (admin, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&basic_storage(admin, vec![])));
+        let storage = storage.to_account_info();
+
+        let check = |key: &Pubkey, is_signer: bool| {
+            let mut account = FixtureAccount::new(*key);
+            if is_signer { account = account.signer(); }
+            Permissions::assert_only_admin(&storage, &account.to_account_info())
+        };
+        assert!(check(&admin, true).is_ok());
+        assert_eq!(check(&admin, false).unwrap_err(), FreeTunnelError::AdminSignatureMissing.into());
+        assert_eq!(check(&other, true).unwrap_err(), FreeTunnelError::AdminMismatch.into());
+        assert_eq!(check(&other, false).unwrap_err(), FreeTunnelError::AdminMismatch.into());
+    }
+
+    #[test]
+    fn test_renounced_admin_cannot_act() {
+        let storage_data = account_data(&basic_storage(Constants::RENOUNCED_ADMIN, vec![]));
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(storage_data);
+        let mut account = FixtureAccount::new(Constants::RENOUNCED_ADMIN).signer();
+        assert_eq!(
+            Permissions::assert_only_admin(&storage.to_account_info(), &account.to_account_info()).unwrap_err(),
+            FreeTunnelError::AdminRenounced.into(),
+        );
+    }
+
+    #[test]
+    fn test_admin_renounce_delay_and_locked_balance_guard() {
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+        let ready = |storage: &BasicStorage, now: u64, forced: bool| Permissions::assert_admin_renounce_ready(storage, now, forced);
+        assert_eq!(ready(&storage, NOW as u64, false).unwrap_err(), FreeTunnelError::AdminRenounceNotPending.into());
+
+        storage.renounce_after = NOW as u64 + Constants::ADMIN_RENOUNCE_DELAY;
+        assert_eq!(ready(&storage, NOW as u64, false).unwrap_err(), FreeTunnelError::AdminRenounceNotReady.into());
+        assert_eq!(ready(&storage, storage.renounce_after - 1, true).unwrap_err(), FreeTunnelError::AdminRenounceNotReady.into());
+        assert!(ready(&storage, storage.renounce_after, false).is_ok());
+
+        // Locked funds block the renounce unless executors co-signed a forced one
+        storage.locked_balance.insert(3, 0).unwrap();
+        assert!(ready(&storage, storage.renounce_after, false).is_ok());
+        storage.locked_balance.insert(4, 1).unwrap();
+        assert_eq!(ready(&storage, storage.renounce_after, false).unwrap_err(), FreeTunnelError::LockedBalanceMustBeZero.into());
+        assert!(ready(&storage, storage.renounce_after, true).is_ok());
+    }
+
+    #[test]
+    fn test_cancel_admin_renounce() {
+        let admin = Pubkey::new_unique();
+        let mut pending = basic_storage(admin, vec![]);
+        pending.renounce_after = NOW as u64 + Constants::ADMIN_RENOUNCE_DELAY;
+        let mut data = account_data(&pending);
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let mut account_admin = FixtureAccount::new(admin).signer();
+        let account_admin = account_admin.to_account_info();
+        let mut other = FixtureAccount::new(Pubkey::new_unique()).signer();
+
+        assert_eq!(
+            Permissions::cancel_admin_renounce(&other.to_account_info(), &storage).unwrap_err(),
+            FreeTunnelError::AdminMismatch.into(),
+        );
+        Permissions::cancel_admin_renounce(&account_admin, &storage).unwrap();
+        let basic_storage = DataAccountUtils::read_basic_storage(&storage).unwrap();
+        assert_eq!((basic_storage.admin, basic_storage.renounce_after, basic_storage.event_seq), (admin, 0, 1));
+        assert_eq!(
+            Permissions::cancel_admin_renounce(&account_admin, &storage).unwrap_err(),
+            FreeTunnelError::AdminRenounceNotPending.into(),
+        );
+    }
+
+    #[test]
+    fn test_force_admin_renounce_message_names_the_pending_renounce() {
+        let admin = Pubkey::new_unique();
+        let message = Permissions::msg_from_force_admin_renounce(&admin, 1_700_604_800);
+        let body = format!("[SolvBTC Bridge]\nSign to force admin renounce:\nAdmin: {}\nRenounce after: 1700604800", admin);
+        assert_eq!(message, format!("\x19Ethereum Signed Message:\n{}{}", body.len(), body).into_bytes());
+        assert_ne!(message, Permissions::msg_from_force_admin_renounce(&admin, 1_700_604_801));
+    }
+
+    #[test]
+    fn test_assert_only_proposer_errors() {
+        let (proposer, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let basic_storage = basic_storage(Pubkey::new_unique(), vec![proposer]);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&basic_storage));
+        let storage = storage.to_account_info();
+
+        let check = |key: &Pubkey, is_signer: bool, check_signer: bool| {
+            let mut account = FixtureAccount::new(*key);
+            if is_signer { account = account.signer(); }
+            Permissions::assert_only_proposer(&storage, &account.to_account_info(), check_signer)
+        };
+        assert!(check(&proposer, true, true).is_ok());
+        assert!(check(&proposer, false, false).is_ok());
+        assert_eq!(check(&proposer, false, true).unwrap_err(), FreeTunnelError::ProposerSignatureMissing.into());
+        assert_eq!(check(&other, true, true).unwrap_err(), FreeTunnelError::NotAProposer.into());
+        assert_eq!(check(&other, false, false).unwrap_err(), FreeTunnelError::NotAProposer.into());
+    }
+
+    #[test]
+    fn test_proposer_renounce_and_rotate() {
+        let (proposer, other, new_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = account_data(&basic_storage(Pubkey::new_unique(), vec![proposer, other]));
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let proposers = || DataAccountUtils::read_basic_storage(&storage).unwrap().proposers;
+        let sorted = |mut keys: Vec<Pubkey>| { keys.sort(); keys };
+        let mut outsider = FixtureAccount::new(Pubkey::new_unique()).signer();
+        let outsider = outsider.to_account_info();
+        let mut unsigned = FixtureAccount::new(proposer);
+        let unsigned = unsigned.to_account_info();
+
+        // Only a listed proposer, signing, can renounce or rotate
+        assert_eq!(Permissions::renounce_proposer(&outsider, &storage).unwrap_err(), FreeTunnelError::NotAProposer.into());
+        assert_eq!(Permissions::rotate_proposer_key(&outsider, &storage, &new_key).unwrap_err(), FreeTunnelError::NotAProposer.into());
+        assert_eq!(Permissions::renounce_proposer(&unsigned, &storage).unwrap_err(), FreeTunnelError::ProposerSignatureMissing.into());
+        assert_eq!(
+            Permissions::rotate_proposer_key(&unsigned, &storage, &new_key).unwrap_err(),
+            FreeTunnelError::ProposerSignatureMissing.into(),
+        );
+
+        // Rotating onto a listed key would drop a proposer
+        let mut account_proposer = FixtureAccount::new(proposer).signer();
+        let account_proposer = account_proposer.to_account_info();
+        assert_eq!(
+            Permissions::rotate_proposer_key(&account_proposer, &storage, &other).unwrap_err(),
+            FreeTunnelError::AlreadyProposer.into(),
+        );
+        assert_eq!(proposers(), sorted(vec![proposer, other]));
+
+        Permissions::rotate_proposer_key(&account_proposer, &storage, &new_key).unwrap();
+        assert_eq!(proposers(), sorted(vec![new_key, other]));
+        // The old key is no longer a proposer
+        assert_eq!(Permissions::renounce_proposer(&account_proposer, &storage).unwrap_err(), FreeTunnelError::NotAProposer.into());
+
+        // Rotating onto a key sorted before the old one keeps the list sorted too
+        let mut low_key = [0u8; 32];
+        low_key[31] = 1;
+        let low_key = Pubkey::new_from_array(low_key);
+        let mut account_new_key = FixtureAccount::new(new_key).signer();
+        Permissions::rotate_proposer_key(&account_new_key.to_account_info(), &storage, &low_key).unwrap();
+        assert_eq!(proposers(), vec![low_key, other]);
+
+        let mut account_low_key = FixtureAccount::new(low_key).signer();
+        Permissions::renounce_proposer(&account_low_key.to_account_info(), &storage).unwrap();
+        assert_eq!(proposers(), vec![other]);
+        assert_eq!(DataAccountUtils::read_basic_storage(&storage).unwrap().event_seq, 3);
+    }
+
+    #[test]
+    fn test_propose_rate_limit_per_proposer() {
+        runtime_stubs::set_clock(NOW);
+        let proposer = Pubkey::new_unique();
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![proposer]);
+        storage.min_propose_interval = 100;
+        let mut data = account_data(&storage);
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let propose = |key: &Pubkey| {
+            let mut account = FixtureAccount::new(*key).signer();
+            Permissions::assert_propose_rate_limit(&storage, &account.to_account_info())
+        };
+        let tracked = || DataAccountUtils::read_basic_storage(&storage).unwrap().proposer_last_propose;
+
+        propose(&proposer).unwrap();
+        assert_eq!(propose(&proposer).unwrap_err(), FreeTunnelError::ProposeTooFrequent.into());
+
+        // A key sharing the first hash byte has its own entry
+        let first_byte = |key: &Pubkey| keccak::hash(key.as_ref()).to_bytes()[0];
+        let colliding = std::iter::repeat_with(Pubkey::new_unique).find(|key| first_byte(key) == first_byte(&proposer)).unwrap();
+        propose(&colliding).unwrap();
+
+        // Past the limit the earliest entry is dropped, and no propose is rejected
+        let others: Vec<Pubkey> = (0..Constants::MAX_RATE_LIMITED_PROPOSERS).map(|_| Pubkey::new_unique()).collect();
+        for key in &others {
+            propose(key).unwrap();
+        }
+        assert_eq!(tracked().len(), Constants::MAX_RATE_LIMITED_PROPOSERS);
+        assert!(tracked().iter().all(|(key, _)| key != &proposer && key != &colliding));
+        assert_eq!(propose(&others[0]).unwrap_err(), FreeTunnelError::ProposeTooFrequent.into());
+
+        // A rotated key keeps the old key's time
+        let mut account_last = FixtureAccount::new(others[Constants::MAX_RATE_LIMITED_PROPOSERS - 1]).signer();
+        let new_key = Pubkey::new_unique();
+        let mut data = DataAccountUtils::read_basic_storage(&storage).unwrap();
+        data.proposers.push(account_last.key());
+        data.proposers.sort();
+        DataAccountUtils::write_account_data(&storage, data).unwrap();
+        Permissions::rotate_proposer_key(&account_last.to_account_info(), &storage, &new_key).unwrap();
+        assert_eq!(propose(&new_key).unwrap_err(), FreeTunnelError::ProposeTooFrequent.into());
+
+        // Entries expire with the interval
+        runtime_stubs::set_clock(NOW + 100);
+        propose(&others[0]).unwrap();
+        propose(&new_key).unwrap();
+        assert_eq!(tracked().len(), 2);
+    }
+
+    #[test]
+    fn test_open_lock_lets_any_signer_propose_lock() {
+        let (admin, proposer, user) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = account_data(&basic_storage(admin, vec![proposer]));
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let check = |key: &Pubkey| {
+            let mut account = FixtureAccount::new(*key).signer();
+            Permissions::assert_can_propose_lock(&storage, &account.to_account_info())
+        };
+
+        // Closed by default: proposers only
+        assert!(check(&proposer).is_ok());
+        assert_eq!(check(&user).unwrap_err(), FreeTunnelError::NotAProposer.into());
+
+        let mut account_user = FixtureAccount::new(user).signer();
+        assert_eq!(
+            Permissions::set_open_lock(&account_user.to_account_info(), &storage, true).unwrap_err(),
+            FreeTunnelError::AdminMismatch.into(),
+        );
+        let mut account_admin = FixtureAccount::new(admin).signer();
+        let account_admin = account_admin.to_account_info();
+        Permissions::set_open_lock(&account_admin, &storage, true).unwrap();
+        assert!(check(&proposer).is_ok());
+        assert!(check(&user).is_ok());
+
+        Permissions::set_open_lock(&account_admin, &storage, false).unwrap();
+        assert_eq!(check(&user).unwrap_err(), FreeTunnelError::NotAProposer.into());
+    }
+
+    #[test]
+    fn test_relayers_gate_executes_once_listed() {
+        let (admin, relayer, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = account_data(&basic_storage(admin, vec![]));
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let mut admin = FixtureAccount::new(admin).signer();
+        let admin = admin.to_account_info();
+
+        let check = |key: Option<&Pubkey>, is_signer: bool| {
+            let mut account = key.map(|key| {
+                let account = FixtureAccount::new(*key);
+                if is_signer { account.signer() } else { account }
+            });
+            let account = account.as_mut().map(|account| account.to_account_info());
+            Permissions::assert_only_relayer(&storage, account.as_ref())
+        };
+
+        // Permissionless: anyone, or no relayer account at all
+        assert!(check(None, false).is_ok());
+        assert!(check(Some(&other), true).is_ok());
+
+        // Permissioned: only a listed relayer that signs
+        Permissions::add_relayer(&admin, &storage, &relayer).unwrap();
+        assert_eq!(Permissions::add_relayer(&admin, &storage, &relayer).unwrap_err(), FreeTunnelError::AlreadyRelayer.into());
+        assert!(check(Some(&relayer), true).is_ok());
+        assert_eq!(check(Some(&relayer), false).unwrap_err(), FreeTunnelError::RelayerSignatureMissing.into());
+        assert_eq!(check(None, false).unwrap_err(), FreeTunnelError::RelayerSignatureMissing.into());
+        assert_eq!(check(Some(&other), true).unwrap_err(), FreeTunnelError::NotARelayer.into());
+
+        // Removing the last relayer makes executes permissionless again
+        Permissions::remove_relayer(&admin, &storage, &relayer).unwrap();
+        assert_eq!(Permissions::remove_relayer(&admin, &storage, &relayer).unwrap_err(), FreeTunnelError::NotExistingRelayer.into());
+        assert!(check(Some(&other), true).is_ok());
+    }
+
+    #[test]
+    fn test_relayers_limit() {
+        let admin = Pubkey::new_unique();
+        let mut data = account_data(&basic_storage(admin, vec![]));
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let mut admin = FixtureAccount::new(admin).signer();
+        let admin = admin.to_account_info();
+
+        for _ in 0..Constants::MAX_RELAYERS {
+            Permissions::add_relayer(&admin, &storage, &Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            Permissions::add_relayer(&admin, &storage, &Pubkey::new_unique()).unwrap_err(),
+            FreeTunnelError::StorageLimitReached.into(),
+        );
+    }
+
+    #[test]
+    fn test_event_seq_strictly_increases_across_instructions() {
+        runtime_stubs::set_clock(NOW);
+        let program_id = Pubkey::new_unique();
+        let (admin, proposer, relayer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = basic_storage(admin, vec![]);
+        storage.executors_group_length = 0;
+        let mut data = account_data(&storage);
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let mut admin = FixtureAccount::new(admin).signer().writable().lamports(1_000_000_000);
+        let admin = admin.to_account_info();
+        let mut system_program = FixtureAccount::new(solana_sdk_ids::system_program::ID).executable();
+        let system_program = system_program.to_account_info();
+        let event_seq = || DataAccountUtils::read_basic_storage(&storage).unwrap().event_seq;
+
+        let mut seen = vec![event_seq()];
+        Permissions::add_proposer(&admin, &storage, &proposer).unwrap();
+        seen.push(event_seq());
+        Permissions::add_relayer(&admin, &storage, &relayer).unwrap();
+        seen.push(event_seq());
+        // Events logged by the propose/execute/cancel paths after their own storage writes
+        seen.push(DataAccountUtils::next_event_seq(&storage).unwrap());
+        Permissions::remove_relayer(&admin, &storage, &relayer).unwrap();
+        seen.push(event_seq());
+        Permissions::remove_proposer(&admin, &storage, &proposer).unwrap();
+        seen.push(event_seq());
+
+        // A failed instruction logs no event and leaves the counter alone
+        assert!(Permissions::remove_proposer(&admin, &storage, &proposer).is_err());
+        assert_eq!(event_seq(), 5);
+
+        // Config and executors events are numbered too
+        let mut executors = FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTORS, &0u64.to_le_bytes())
+            .owner(solana_sdk_ids::system_program::ID);
+        let (_, executors_bump) = Pubkey::find_program_address(&[Constants::PREFIX_EXECUTORS, &0u64.to_le_bytes()], &program_id);
+        Permissions::init_executors(
+            &program_id,
+            &system_program,
+            &admin,
+            &admin,
+            &storage,
+            &executors.to_account_info(),
+            executors_bump,
+            &vec![[1; 20]],
+            1,
+            0,
+        )
+        .unwrap();
+        seen.push(event_seq());
+        Permissions::set_min_propose_interval(&admin, &storage, 60).unwrap();
+        seen.push(event_seq());
+        let (min_delay, max_delay) = (Constants::EXECUTORS_ROTATION_MIN_DELAY_FLOOR, Constants::EXECUTORS_ROTATION_MAX_DELAY);
+        Permissions::set_executor_rotation_window(&admin, &storage, min_delay, max_delay).unwrap();
+        seen.push(event_seq());
+        Permissions::set_max_proposers(&system_program, &admin, &storage, Constants::MAX_PROPOSERS as u8).unwrap();
+        seen.push(event_seq());
+        let mut rent_vault = FixtureAccount::pda(&program_id, Constants::PREFIX_RENT_VAULT, b"")
+            .owner(solana_sdk_ids::system_program::ID);
+        RentVault::fund_rent_vault(&program_id, &system_program, &admin, &storage, &rent_vault.to_account_info(), 10_000_000)
+            .unwrap();
+        seen.push(event_seq());
+
+        // Only applying a param change is an event, the proposal is not
+        let mut pending_change = FixtureAccount::pda(&program_id, Constants::PREFIX_PENDING_CHANGE, admin.key.as_ref())
+            .owner(solana_sdk_ids::system_program::ID);
+        let pending_change = pending_change.to_account_info();
+        let (_, pending_change_bump) =
+            Pubkey::find_program_address(&[Constants::PREFIX_PENDING_CHANGE, admin.key.as_ref()], &program_id);
+        let new_value = borsh::to_vec(&0u64).unwrap();
+        Permissions::propose_param_change(
+            &program_id,
+            &system_program,
+            &admin,
+            &storage,
+            &pending_change,
+            pending_change_bump,
+            Constants::PARAM_MIN_PROPOSE_INTERVAL,
+            new_value,
+        )
+        .unwrap();
+        assert_eq!(event_seq(), 10);
+        runtime_stubs::set_clock(NOW + Constants::PARAM_CHANGE_DELAY as i64);
+        Permissions::apply_param_change(&program_id, &admin, &storage, &pending_change).unwrap();
+        seen.push(event_seq());
+
+        assert_eq!(seen, (0..=11).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_active_since_default_window() {
+        let (min, max) = (Constants::EXECUTORS_ROTATION_MIN_DELAY, Constants::EXECUTORS_ROTATION_MAX_DELAY);
+        let at = |delay: u64| NOW as u64 + delay;
+        assert_eq!(
+            Permissions::assert_active_since_in_window(at(36 * 3600), NOW, min, max).unwrap_err(),
+            FreeTunnelError::ActiveSinceShouldAfter36h.into(),
+        );
+        assert!(Permissions::assert_active_since_in_window(at(36 * 3600 + 1), NOW, min, max).is_ok());
+        assert!(Permissions::assert_active_since_in_window(at(120 * 3600 - 1), NOW, min, max).is_ok());
+        assert_eq!(
+            Permissions::assert_active_since_in_window(at(120 * 3600), NOW, min, max).unwrap_err(),
+            FreeTunnelError::ActiveSinceShouldWithin5d.into(),
+        );
+    }
+
+    #[test]
+    fn test_active_since_testnet_window() {
+        // Emergency rotations on testnets with the smallest allowed delay
+        let (min, max) = (Constants::EXECUTORS_ROTATION_MIN_DELAY_FLOOR, 2 * 3600);
+        let at = |delay: u64| NOW as u64 + delay;
+        assert!(Permissions::assert_active_since_in_window(at(3600), NOW, min, max).is_err());
+        assert!(Permissions::assert_active_since_in_window(at(3600 + 1), NOW, min, max).is_ok());
+        assert!(Permissions::assert_active_since_in_window(at(36 * 3600 + 1), NOW, min, max).is_err());
+    }
+
+    #[test]
+    fn test_overwriting_executors_keeps_ed25519_executors() {
+        let (ed25519_1, ed25519_2, ed25519_3) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let next_executors_info = ExecutorsInfo {
+            ed25519_executors: vec![ed25519_1, ed25519_2],
+            ..executors_info(vec![[1; 20]], 2)
+        };
+        let overwrite = |ed25519_executors: &[Pubkey]| {
+            Permissions::assert_executors_overwritable(&next_executors_info, 2, 1, &[[1; 20]], ed25519_executors)
+        };
+        let rejected = Err(FreeTunnelError::FailedToOverwriteExistingExecutors.into());
+
+        // Equal
+        assert_eq!(overwrite(&[ed25519_2, ed25519_1]), Ok(()));
+        // Superset
+        assert_eq!(overwrite(&[ed25519_1, ed25519_2, ed25519_3]), Ok(()));
+        // Subset, which would silently drop an ed25519 executor
+        assert_eq!(overwrite(&[ed25519_1]), rejected);
+        // Disjoint
+        assert_eq!(overwrite(&[ed25519_3]), rejected);
+        // The secp256k1 list is still held to the same rule
+        assert_eq!(
+            Permissions::assert_executors_overwritable(&next_executors_info, 2, 1, &[[2; 20]], &[ed25519_1, ed25519_2]),
+            rejected,
+        );
+    }
+
+    #[test]
+    fn test_pause_keeps_first_pause_time() {
+        let admin = Pubkey::new_unique();
+        let mut data = account_data(&basic_storage(admin, vec![]));
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let mut account_admin = FixtureAccount::new(admin).signer();
+        let account_admin = account_admin.to_account_info();
+        let paused_since = || DataAccountUtils::read_basic_storage(&storage).unwrap().paused_since;
+
+        runtime_stubs::set_clock(NOW);
+        Permissions::assert_not_paused(&storage).unwrap();
+        Permissions::set_paused(&account_admin, &storage, true).unwrap();
+        assert_eq!(paused_since(), NOW as u64);
+        assert_eq!(Permissions::assert_not_paused(&storage).unwrap_err(), FreeTunnelError::BridgePaused.into());
+
+        // Pausing again cannot push a decommission back
+        runtime_stubs::set_clock(NOW + 100);
+        Permissions::set_paused(&account_admin, &storage, true).unwrap();
+        assert_eq!(paused_since(), NOW as u64);
+
+        Permissions::set_paused(&account_admin, &storage, false).unwrap();
+        assert_eq!(paused_since(), 0);
+        Permissions::assert_not_paused(&storage).unwrap();
+
+        let mut other = FixtureAccount::new(Pubkey::new_unique()).signer();
+        assert_eq!(
+            Permissions::set_paused(&other.to_account_info(), &storage, true).unwrap_err(),
+            FreeTunnelError::AdminMismatch.into(),
+        );
+    }
+}
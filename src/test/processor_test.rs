@@ -0,0 +1,398 @@
+#[cfg(test)]
+mod processor_test {
+    use crate::constants::Constants;
+    use crate::error::{DataAccountError, FreeTunnelError};
+    use crate::logic::req_helpers::ReqId;
+    use crate::processor::Processor;
+    use crate::state::{PendingAdminChange, ProposedLock, SignaturesView};
+    use crate::test::runtime_stubs;
+    use crate::test::test_fixtures::{self, account_data, basic_storage, mint_data, FixtureAccount};
+    use crate::utils::DataAccountUtils;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+
+    fn instruction_data(variant: u8, fields: impl BorshSerialize) -> Vec<u8> {
+        let mut data = vec![variant];
+        data.extend(borsh::to_vec(&fields).unwrap());
+        data
+    }
+
+    #[test]
+    fn test_initialize_rejects_nonzero_exe_index() {
+        let program_id = Pubkey::new_unique();
+        let (system_key, admin_key) = (Pubkey::default(), Pubkey::new_unique());
+        let (mut system_lamports, mut admin_lamports) = (0, 0);
+        let (mut system_data, mut admin_data) = (vec![], vec![]);
+        // Only the system program and the admin (signer) are passed
+        let accounts = [
+            AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+            AccountInfo::new(&admin_key, true, true, &mut admin_lamports, &mut admin_data, &system_key, false, 0),
+        ];
+        let executors: Vec<[u8; 20]> = vec![[1; 20]];
+        let expected: ProgramError = FreeTunnelError::InvalidExeIndex.into();
+
+        // A typo such as `exe_index = 5` fails before any account is read
+        let initialize = instruction_data(0, (true, executors.clone(), 1u64, 5u64, 0u8));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &initialize), Err(expected.clone()));
+        let initialize_executors = instruction_data(28, (executors.clone(), 1u64, 5u64));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &initialize_executors), Err(expected));
+
+        // `exe_index = 0` passes the check and goes on to read the missing accounts
+        let initialize = instruction_data(0, (true, executors, 1u64, 0u64, 0u8));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &initialize), Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    #[test]
+    fn test_initialize_rejects_bad_executors_before_creating_storage() {
+        let program_id = Pubkey::new_unique();
+        let (system_key, admin_key) = (Pubkey::default(), Pubkey::new_unique());
+        let (mut system_lamports, mut admin_lamports) = (0, 0);
+        let (mut system_data, mut admin_data) = (vec![], vec![]);
+        // Without the storage accounts, any check that runs after creating basic storage would
+        // surface as `NotEnoughAccountKeys` instead
+        let accounts = [
+            AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+            AccountInfo::new(&admin_key, true, true, &mut admin_lamports, &mut admin_data, &system_key, false, 0),
+        ];
+        let initialize = |executors: Vec<[u8; 20]>, threshold: u64| {
+            let data = instruction_data(0, (true, executors, threshold, 0u64, 0u8));
+            Processor::process_instruction(&program_id, &accounts, &data)
+        };
+        let too_many: Vec<[u8; 20]> = (0..=Constants::MAX_EXECUTORS as u8).map(|i| [i + 1; 20]).collect();
+
+        assert_eq!(initialize(too_many, 1), Err(FreeTunnelError::StorageLimitReached.into()));
+        assert_eq!(initialize(vec![[1; 20], [1; 20]], 1), Err(FreeTunnelError::DuplicatedExecutors.into()));
+        assert_eq!(initialize(vec![[1; 20]], 0), Err(FreeTunnelError::ThresholdMustBeGreaterThanZero.into()));
+        assert_eq!(initialize(vec![[1; 20]], 2), Err(FreeTunnelError::NotMeetThreshold.into()));
+        assert_eq!(initialize(vec![[1; 20], [2; 20]], 2), Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    #[test]
+    fn test_initialize_with_tokens_fails_fast() {
+        let program_id = Pubkey::new_unique();
+        let (system_key, admin_key) = (Pubkey::default(), Pubkey::new_unique());
+        let (mut system_lamports, mut admin_lamports) = (0, 0);
+        let (mut system_data, mut admin_data) = (vec![], vec![]);
+        let accounts = [
+            AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+            AccountInfo::new(&admin_key, true, true, &mut admin_lamports, &mut admin_data, &system_key, false, 0),
+        ];
+        let executors: Vec<[u8; 20]> = vec![[1; 20]];
+        let token = (1u8, Pubkey::new_unique(), 6u8);
+
+        // Same executor checks as `Initialize`
+        let data = instruction_data(37, (true, executors.clone(), 1u64, 5u64, vec![token]));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &data), Err(FreeTunnelError::InvalidExeIndex.into()));
+        let data = instruction_data(37, (true, executors.clone(), 2u64, 0u64, vec![token]));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &data), Err(FreeTunnelError::NotMeetThreshold.into()));
+
+        // More tokens than storage allows
+        let tokens = vec![token; Constants::MAX_TOKENS + 1];
+        let data = instruction_data(37, (true, executors.clone(), 1u64, 0u64, tokens));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &data), Err(FreeTunnelError::StorageLimitReached.into()));
+
+        let data = instruction_data(37, (true, executors, 1u64, 0u64, vec![token]));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &data), Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    #[test]
+    fn test_execute_rejects_signatures_over_budget() {
+        let program_id = Pubkey::new_unique();
+        // Largest bundle within the soft cap
+        let max = ((Constants::CU_SOFT_CAP - Constants::CU_BASE) / Constants::CU_PER_SIGNATURE) as usize;
+        assert!(Constants::expected_compute_units(max) <= Constants::CU_SOFT_CAP);
+        assert!(Constants::expected_compute_units(max + 1) > Constants::CU_SOFT_CAP);
+
+        for variant in [8u8, 11, 14, 17] {
+            let data = instruction_data(variant, (ReqId::new([0; 32]), vec![[0u8; 64]; max + 1], vec![[0u8; 20]; max + 1], 0u64));
+            let expected: ProgramError = FreeTunnelError::TooManySignaturesForBudget.into();
+            assert_eq!(Processor::process_instruction(&program_id, &[], &data), Err(expected));
+
+            // Within budget, it goes on to read the missing accounts
+            let data = instruction_data(variant, (ReqId::new([0; 32]), vec![[0u8; 64]; max], vec![[0u8; 20]; max], 0u64));
+            assert_eq!(Processor::process_instruction(&program_id, &[], &data), Err(ProgramError::NotEnoughAccountKeys));
+        }
+    }
+
+    #[test]
+    fn test_transfer_tokens_only_when_nothing_is_owed() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let transfer = |locked_balance: u64, pending_proposals: u16| {
+            let mut storage = basic_storage(admin, vec![]);
+            storage.mint_or_lock = false;
+            storage.tokens.insert(1, Pubkey::new_unique()).unwrap();
+            storage.vaults.insert(1, Pubkey::new_unique()).unwrap();
+            storage.locked_balance.insert(1, locked_balance).unwrap();
+            storage.pending_proposals.insert(1, pending_proposals).unwrap();
+            let mut fixtures = [
+                FixtureAccount::new(spl_token::id()).executable(),
+                FixtureAccount::new(admin).signer(),
+                FixtureAccount::pda(&program_id, Constants::CONTRACT_SIGNER, b""),
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&storage)),
+                FixtureAccount::new(Pubkey::new_unique()).writable(),
+                FixtureAccount::new(Pubkey::new_unique()).writable(),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            Processor::process_instruction(&program_id, &accounts, &instruction_data(41, (1u8, 100u64)))
+        };
+        assert_eq!(transfer(100, 0), Err(FreeTunnelError::LockedBalanceMustBeZero.into()));
+        assert_eq!(transfer(0, 1), Err(FreeTunnelError::PendingProposalsExist.into()));
+        // Past the checks, the vault passed here is not the recorded one
+        assert_eq!(transfer(0, 0), Err(FreeTunnelError::InvalidTokenAccount.into()));
+    }
+
+    #[test]
+    fn test_rotate_vault_checks_the_token_program_of_the_token() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let rotate = |token_program: Pubkey| {
+            let mut storage = basic_storage(admin, vec![]);
+            storage.mint_or_lock = false;
+            storage.tokens.insert(1, Pubkey::new_unique()).unwrap();
+            storage.token_programs.insert(1, spl_token::id()).unwrap();
+            storage.vaults.insert(1, vault).unwrap();
+            let mut fixtures = [
+                FixtureAccount::new(token_program).executable(),
+                FixtureAccount::new(admin).signer(),
+                FixtureAccount::pda(&program_id, Constants::CONTRACT_SIGNER, b""),
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&storage)),
+                FixtureAccount::new(vault).writable(),
+                FixtureAccount::new(Pubkey::new_unique()).writable(),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            Processor::process_instruction(&program_id, &accounts, &instruction_data(30, 1u8))
+        };
+        assert_eq!(rotate(spl_token_2022::id()), Err(FreeTunnelError::TokenProgramMismatch.into()));
+        // Past the check, the new vault here is not a token account of the contract
+        assert_eq!(rotate(spl_token::id()), Err(FreeTunnelError::InvalidNewVault.into()));
+    }
+
+    #[test]
+    fn test_execute_rejects_exe_index_past_stored_groups() {
+        let program_id = Pubkey::new_unique();
+        let req_id = [7u8; 32];
+        let execute_lock = |exe_index: u64| {
+            let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+            storage.mint_or_lock = false;
+            let mut fixtures = [
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&storage)),
+                FixtureAccount::pda(&program_id, Constants::PREFIX_LOCK, &req_id),
+                FixtureAccount::new(Pubkey::new_unique()),
+                FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTION_COMMIT, &req_id),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            let data = instruction_data(14, (req_id, Vec::<[u8; 64]>::new(), Vec::<[u8; 20]>::new(), exe_index));
+            Processor::process_instruction(&program_id, &accounts, &data)
+        };
+        // One group is stored: index 1 is reported as such, not as a PDA mismatch
+        assert_eq!(execute_lock(1), Err(FreeTunnelError::InvalidExeIndex.into()));
+        // Index 0 exists, so the unrelated executors account passed here is the next failure
+        assert_eq!(execute_lock(0), Err(DataAccountError::PdaAccountMismatch.into()));
+    }
+
+    #[test]
+    fn test_execute_honours_the_pinned_exe_index() {
+        let program_id = Pubkey::new_unique();
+        let req_id = [7u8; 32];
+        let execute_lock = |proposal: Vec<u8>, exe_index: u64| {
+            let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+            storage.mint_or_lock = false;
+            storage.executors_group_length = 2;
+            let mut fixtures = [
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&storage)),
+                FixtureAccount::pda(&program_id, Constants::PREFIX_LOCK, &req_id).data(proposal),
+                FixtureAccount::new(Pubkey::new_unique()),
+                FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTION_COMMIT, &req_id),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            let data = instruction_data(14, (req_id, Vec::<[u8; 64]>::new(), Vec::<[u8; 20]>::new(), exe_index));
+            Processor::process_instruction(&program_id, &accounts, &data)
+        };
+        let proposal = |pinned_exe_index: Option<u64>| {
+            account_data(&ProposedLock { inner: Pubkey::new_unique(), rent_from_vault: false, token_account: Pubkey::new_unique(), pinned_exe_index })
+        };
+        // Past the pin check, the unrelated executors account passed here is the next failure
+        let passed: ProgramError = DataAccountError::PdaAccountMismatch.into();
+
+        assert_eq!(execute_lock(proposal(Some(1)), 0), Err(FreeTunnelError::PinnedExeIndexMismatch.into()));
+        assert_eq!(execute_lock(proposal(Some(1)), 1), Err(passed.clone()));
+        assert_eq!(execute_lock(proposal(None), 0), Err(passed.clone()));
+        assert_eq!(execute_lock(proposal(None), 1), Err(passed.clone()));
+        // Proposals written before the pin was added can be executed by any group
+        let legacy = account_data(&(Pubkey::new_unique(), false, Pubkey::new_unique()));
+        assert_eq!(execute_lock(legacy.clone(), 0), Err(passed.clone()));
+        assert_eq!(execute_lock(legacy, 1), Err(passed));
+    }
+
+    #[test]
+    fn test_propose_unlock_requires_the_proposer_signature() {
+        let program_id = Pubkey::new_unique();
+        let proposer = Pubkey::new_unique();
+        let mut req_id = [0u8; 32];
+        req_id[6] = 2; // burn-unlock
+        req_id[16] = Constants::HUB_ID;
+        let propose_unlock = |proposer_fixture: FixtureAccount| {
+            let mut storage = basic_storage(Pubkey::new_unique(), vec![proposer]);
+            storage.mint_or_lock = false;
+            let mut fixtures = [
+                FixtureAccount::new(solana_sdk_ids::system_program::ID).executable(),
+                proposer_fixture,
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&storage)),
+                FixtureAccount::pda(&program_id, Constants::PREFIX_UNLOCK, &req_id),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            let data = instruction_data(16, (req_id, Pubkey::new_unique(), None::<u64>));
+            Processor::process_instruction(&program_id, &accounts, &data)
+        };
+        // Listed proposers still have to sign, checked before the instruction is dispatched
+        assert_eq!(propose_unlock(FixtureAccount::new(proposer).writable()), Err(FreeTunnelError::RequireSigner.into()));
+        // With the signature, the proposal moves on to the created time check, which reads the clock
+        assert_eq!(propose_unlock(FixtureAccount::new(proposer).signer().writable()), Err(ProgramError::UnsupportedSysvar));
+    }
+
+    #[test]
+    fn test_admin_cannot_be_handed_to_renounced_admin() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let mut storage_data = account_data(&basic_storage(admin, vec![]));
+        storage_data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut system_program = FixtureAccount::new(solana_sdk_ids::system_program::ID).executable();
+        let mut account_admin = FixtureAccount::new(admin).signer().writable().lamports(1_000_000_000);
+        let mut storage = FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(storage_data);
+        let renounced = borsh::to_vec(&Constants::RENOUNCED_ADMIN).unwrap();
+        let expected = Err(FreeTunnelError::NewAdminIsRenounced.into());
+
+        // Neither `TransferAdmin` nor a param change skips the delayed renounce
+        let accounts = [account_admin.to_account_info(), storage.to_account_info()];
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &instruction_data(1, Constants::RENOUNCED_ADMIN)), expected);
+
+        let mut pending_change = FixtureAccount::pda(&program_id, Constants::PREFIX_PENDING_CHANGE, admin.as_ref())
+            .owner(solana_sdk_ids::system_program::ID);
+        let accounts = [
+            system_program.to_account_info(),
+            account_admin.to_account_info(),
+            storage.to_account_info(),
+            pending_change.to_account_info(),
+        ];
+        let propose = instruction_data(32, (Constants::PARAM_ADMIN, renounced.clone()));
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &propose), expected);
+        assert!(pending_change.to_account_info().data_is_empty());
+
+        // A change to it that is already pending cannot be applied either
+        let pending = PendingAdminChange { parameter_id: Constants::PARAM_ADMIN, new_value: renounced, effective_at: 0 };
+        let mut pending_change = FixtureAccount::pda(&program_id, Constants::PREFIX_PENDING_CHANGE, admin.as_ref())
+            .data(account_data(&pending));
+        let accounts = [account_admin.to_account_info(), storage.to_account_info(), pending_change.to_account_info()];
+        assert_eq!(Processor::process_instruction(&program_id, &accounts, &[33]), expected);
+        assert_eq!(DataAccountUtils::read_basic_storage(&storage.to_account_info()).unwrap().admin, admin);
+    }
+
+    #[test]
+    fn test_verify_signatures_reports_instead_of_failing() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let program_id = Pubkey::new_unique();
+        let executors = vec![[1u8; 20], [2u8; 20]];
+        let mut data_account_executors = FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTORS, &0u64.to_le_bytes())
+            .data(account_data(&test_fixtures::executors_info(executors.clone(), 2)));
+        let accounts = [data_account_executors.to_account_info()];
+        let verify = |signatures: Vec<[u8; 64]>, executors: Vec<[u8; 20]>| {
+            let data = instruction_data(21, ([7u8; 32], signatures, executors, 0u64));
+            Processor::process_instruction(&program_id, &accounts, &data)?;
+            Ok::<_, ProgramError>(SignaturesView::try_from_slice(&runtime_stubs::return_data()).unwrap())
+        };
+        let error_code = |error: FreeTunnelError| Some(u64::from(ProgramError::from(error)));
+
+        // Invalid signatures and an unmet threshold still return the bitmask
+        assert_eq!(
+            verify(vec![[3; 64]; 2], executors.clone()).unwrap(),
+            SignaturesView { bitmask: 0, multisig_error: error_code(FreeTunnelError::InvalidSignature) },
+        );
+        assert_eq!(
+            verify(vec![[3; 64]], vec![[1; 20]]).unwrap(),
+            SignaturesView { bitmask: 0, multisig_error: error_code(FreeTunnelError::NotMeetThreshold) },
+        );
+
+        // Bundles that do not fit the bitmask or the compute budget fail
+        let too_many = Constants::MAX_EXECUTORS + 1;
+        assert_eq!(
+            verify(vec![[3; 64]; too_many], vec![[1; 20]; too_many]).unwrap_err(),
+            FreeTunnelError::StorageLimitReached.into(),
+        );
+        assert_eq!(verify(vec![[3; 64]; 65], vec![[1; 20]; 65]).unwrap_err(), FreeTunnelError::TooManySignaturesForBudget.into());
+    }
+
+    #[test]
+    fn test_add_token_above_max_decimals_needs_the_flag() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let add_token = |decimals: u8, allow_high_decimals: bool| {
+            let mut fixtures = [
+                FixtureAccount::new(solana_sdk_ids::system_program::ID).executable(),
+                FixtureAccount::new(spl_token::id()).executable(),
+                FixtureAccount::new(admin).signer().writable(),
+                FixtureAccount::new(Pubkey::new_unique()).writable(),
+                FixtureAccount::pda(&program_id, Constants::CONTRACT_SIGNER, b""),
+                FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"").data(account_data(&basic_storage(admin, vec![]))),
+                FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id()).data(mint_data(admin, decimals, 0)),
+                FixtureAccount::new(solana_sdk_ids::sysvar::rent::ID),
+                FixtureAccount::new(Pubkey::new_unique()).executable(),
+            ];
+            let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+            let data = instruction_data(5, (1u8, [0u8; 20], false, false, allow_high_decimals));
+            Processor::process_instruction(&program_id, &accounts, &data)
+        };
+        // Past the decimals check, the associated token program passed here is rejected
+        let passed: ProgramError = FreeTunnelError::InvalidAssociatedTokenProgram.into();
+
+        assert_eq!(add_token(Constants::MAX_DECIMALS, false), Err(passed.clone()));
+        assert_eq!(add_token(Constants::MAX_DECIMALS + 1, false), Err(FreeTunnelError::DecimalsTooHigh.into()));
+        assert_eq!(add_token(18, false), Err(FreeTunnelError::DecimalsTooHigh.into()));
+        assert_eq!(add_token(18, true), Err(passed));
+    }
+
+    #[test]
+    fn test_migrate_legacy_basic_storage() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let program_id = Pubkey::new_unique();
+        let (admin, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let legacy_length = test_fixtures::LEGACY_BASIC_STORAGE_LENGTH;
+        let rent = Rent::default();
+        let mut fixtures = [
+            FixtureAccount::new(solana_sdk_ids::system_program::ID).executable(),
+            FixtureAccount::new(Pubkey::new_unique()).signer().writable().lamports(1_000_000_000),
+            FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"")
+                .lamports(rent.minimum_balance(legacy_length))
+                .data(test_fixtures::legacy_basic_storage_data(admin, vec![], 2, mint)),
+        ];
+        let accounts: Vec<AccountInfo> = fixtures.iter_mut().map(|fixture| fixture.to_account_info()).collect();
+        Processor::process_instruction(&program_id, &accounts, &[53]).unwrap();
+
+        // Grown to the current size, paid by the payer, and written in the current layout
+        let required_length = Constants::basic_storage_length(Constants::MAX_PROPOSERS as u8);
+        assert_eq!(accounts[2].data_len(), required_length);
+        assert_eq!(accounts[2].lamports(), rent.minimum_balance(required_length));
+        assert_eq!(accounts[1].lamports(), 1_000_000_000 - (rent.minimum_balance(required_length) - rent.minimum_balance(legacy_length)));
+        let storage = DataAccountUtils::read_basic_storage(&accounts[2]).unwrap();
+        assert_eq!((storage.admin, storage.tokens.get(2), storage.event_seq), (admin, Some(&mint), 1));
+        assert_eq!(storage.pending_proposals.get(2), Some(&0));
+
+        // Running it again only advances the event counter
+        Processor::process_instruction(&program_id, &accounts, &[53]).unwrap();
+        assert_eq!(accounts[2].data_len(), required_length);
+        assert_eq!(DataAccountUtils::read_basic_storage(&accounts[2]).unwrap().event_seq, 2);
+    }
+
+    #[test]
+    fn test_entrypoint_checks_program_id() {
+        // Past the check, an empty instruction fails on its data instead
+        assert_eq!(crate::process_instruction(&crate::ID, &[], &[]), Err(ProgramError::InvalidInstructionData));
+        let foreign_id = crate::process_instruction(&Pubkey::new_unique(), &[], &[]);
+        match cfg!(feature = "custom-program-id") {
+            true => assert_eq!(foreign_id, Err(ProgramError::InvalidInstructionData)),
+            false => assert_eq!(foreign_id, Err(ProgramError::IncorrectProgramId)),
+        }
+    }
+}
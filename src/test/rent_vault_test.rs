@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod rent_vault_test {
+    use crate::constants::Constants;
+    use crate::error::FreeTunnelError;
+    use crate::logic::rent_vault::RentVault;
+    use crate::state::{ProposedLock, ProposedMint};
+    use crate::utils::DataAccountUtils;
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+    #[test]
+    fn test_can_cover_keeps_vault_rent_exempt() {
+        assert!(RentVault::can_cover(3_000, 1_000, 2_000));
+        assert!(!RentVault::can_cover(2_999, 1_000, 2_000));
+        assert!(!RentVault::can_cover(1_000, 1_000, 2_000));
+    }
+
+    #[test]
+    fn test_proposal_account_size_ignores_funding_source() {
+        let user_funded = ProposedMint { inner: Pubkey::new_unique(), proposer: Pubkey::new_unique(), rent_from_vault: false, pinned_exe_index: None };
+        let vault_funded = ProposedMint { rent_from_vault: true, ..user_funded };
+        let size = DataAccountUtils::proposal_account_size(&user_funded).unwrap();
+        assert_eq!(size, Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + 32 + 32 + 1 + 1);
+        assert_eq!(DataAccountUtils::proposal_account_size(&vault_funded).unwrap(), size);
+
+        let lock = ProposedLock { inner: Pubkey::new_unique(), rent_from_vault: true, token_account: Pubkey::new_unique(), pinned_exe_index: None };
+        assert_eq!(DataAccountUtils::proposal_account_size(&lock).unwrap(), Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + 65 + 1);
+    }
+
+    #[test]
+    fn test_rent_refund_routing() {
+        let program_id = Pubkey::new_unique();
+        let (rent_vault, _) = Pubkey::find_program_address(&[Constants::PREFIX_RENT_VAULT], &program_id);
+        let proposer = Pubkey::new_unique();
+        let owner = Pubkey::default();
+
+        let (mut vault_lamports, mut vault_data) = (0, vec![]);
+        let account_vault = AccountInfo::new(&rent_vault, false, true, &mut vault_lamports, &mut vault_data, &owner, false, 0);
+        let (mut proposer_lamports, mut proposer_data) = (0, vec![]);
+        let account_proposer = AccountInfo::new(&proposer, false, true, &mut proposer_lamports, &mut proposer_data, &owner, false, 0);
+
+        // User-funded proposals refund the proposer only
+        assert!(RentVault::assert_rent_refund(&program_id, &account_proposer, false, &proposer).is_ok());
+        assert_eq!(
+            RentVault::assert_rent_refund(&program_id, &account_vault, false, &proposer),
+            Err(FreeTunnelError::InvalidProposer.into())
+        );
+
+        // Vault-funded proposals refund the rent vault only
+        assert!(RentVault::assert_rent_refund(&program_id, &account_vault, true, &proposer).is_ok());
+        let expected: ProgramError = FreeTunnelError::InvalidRefundAccount.into();
+        assert_eq!(RentVault::assert_rent_refund(&program_id, &account_proposer, true, &proposer), Err(expected));
+    }
+}
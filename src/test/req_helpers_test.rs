@@ -1,8 +1,11 @@
 #[cfg(test)]
 mod req_helpers_test {
 
+    use crate::error::FreeTunnelError;
     use crate::logic::req_helpers::ReqId;
+    use crate::test::test_fixtures::{account_data, basic_storage, token_account_data, FixtureAccount};
     use hex;
+    use solana_program::pubkey::Pubkey;
 
     #[test]
     fn test_decoding_reqid() {
@@ -17,8 +20,8 @@ mod req_helpers_test {
         assert_eq!(req_id.action(), 0x77);
         assert_eq!(req_id.token_index(), 0x88);
         assert_eq!(req_id.raw_amount(), 0x99aabbccddeeff00);
-        assert_eq!(req_id.assert_from_chain_only(), Ok(()));
-        assert_eq!(req_id.assert_to_chain_only(), Ok(()));
+        assert_eq!(req_id.assert_mint_opposite_side(0xff), Ok(()));
+        assert_eq!(req_id.assert_mint_side(0xff), Ok(()));
     }
 
     #[test]
@@ -32,7 +35,7 @@ mod req_helpers_test {
         let req_id = ReqId::new(req_id_u8);
         let msg = req_id.msg_from_req_signing_message();
         let expected =
-            String::from("\x19Ethereum Signed Message:\n111[Solana Bridge]\nSign to execute a ")
+            String::from("\x19Ethereum Signed Message:\n112[SolvBTC Bridge]\nSign to execute a ")
                 + "lock-mint:\n0x112233445566018899aabbccddeeff004040ffffffffffffffffffffffffffff";
         assert_eq!(msg, expected.as_bytes());
     }
@@ -48,7 +51,7 @@ mod req_helpers_test {
         let req_id = ReqId::new(req_id_u8);
         let msg = req_id.msg_from_req_signing_message();
         let expected = String::from(
-            "\x19Ethereum Signed Message:\n113[Solana Bridge]\nSign to execute a ",
+            "\x19Ethereum Signed Message:\n114[SolvBTC Bridge]\nSign to execute a ",
         )
             + "burn-unlock:\n0x112233445566028899aabbccddeeff004040ffffffffffffffffffffffffffff";
         assert_eq!(msg, expected.as_bytes());
@@ -65,7 +68,7 @@ mod req_helpers_test {
         let req_id = ReqId::new(req_id_u8);
         let msg = req_id.msg_from_req_signing_message();
         let expected =
-            String::from("\x19Ethereum Signed Message:\n111[Solana Bridge]\nSign to execute a ")
+            String::from("\x19Ethereum Signed Message:\n112[SolvBTC Bridge]\nSign to execute a ")
                 + "burn-mint:\n0x112233445566038899aabbccddeeff004040ffffffffffffffffffffffffffff";
         assert_eq!(msg, expected.as_bytes());
     }
@@ -82,4 +85,111 @@ mod req_helpers_test {
         let msg = req_id.msg_from_req_signing_message();
         assert_eq!(msg, vec![] as Vec<u8>);
     }
+
+    #[test]
+    fn test_msg_from_req_force_cancel_message() {
+        let req_id_u8: [u8; 32] =
+            hex::decode("112233445566018899aabbccddeeff004040ffffffffffffffffffffffffffff")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let req_id = ReqId::new(req_id_u8);
+        let msg = req_id.msg_from_req_force_cancel_message();
+        let expected =
+            String::from("\x19Ethereum Signed Message:\n105[SolvBTC Bridge]\nSign to force-cancel:\n")
+                + "0x112233445566018899aabbccddeeff004040ffffffffffffffffffffffffffff";
+        assert_eq!(msg, expected.as_bytes());
+    }
+
+    #[test]
+    fn test_get_checked_token_against_token_account() {
+        let (mint, other_mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+        storage.tokens.insert(3, mint).unwrap();
+        storage.decimals.insert(3, 9).unwrap();
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&storage));
+        let storage = storage.to_account_info();
+
+        let mut data = [0u8; 32];
+        data[7] = 3;
+        let req_id = ReqId::new(data);
+        let mut token_account = FixtureAccount::new(Pubkey::new_unique())
+            .owner(spl_token::id())
+            .data(token_account_data(mint, owner, 1_000));
+        assert_eq!(req_id.get_checked_token(&storage, Some(&token_account.to_account_info())).unwrap(), (3, 9, mint));
+
+        let mut token_account = FixtureAccount::new(Pubkey::new_unique())
+            .owner(spl_token::id())
+            .data(token_account_data(other_mint, owner, 1_000));
+        assert_eq!(
+            req_id.get_checked_token(&storage, Some(&token_account.to_account_info())).unwrap_err(),
+            FreeTunnelError::TokenMismatch.into(),
+        );
+
+        // Token accounts must be owned by one of the token programs
+        let mut token_account = FixtureAccount::new(Pubkey::new_unique()).data(token_account_data(mint, owner, 1_000));
+        assert_eq!(
+            req_id.get_checked_token(&storage, Some(&token_account.to_account_info())).unwrap_err(),
+            FreeTunnelError::InvalidTokenAccount.into(),
+        );
+
+        data[7] = 4;
+        assert_eq!(
+            ReqId::new(data).get_checked_token(&storage, None).unwrap_err(),
+            FreeTunnelError::TokenIndexNonExistent.into(),
+        );
+    }
+
+    #[test]
+    fn test_assert_hub_side() {
+        let (hub, other) = (0xa1, 0x51);
+        let req_id = |action: u8, from: u8, to: u8| {
+            let mut data = [0u8; 32];
+            (data[6], data[16], data[17]) = (action, from, to);
+            ReqId::new(data)
+        };
+
+        // Mint contract: lock-mint and burn-unlock have this hub as the mint side
+        assert!(req_id(1, other, hub).assert_hub_side(true, hub).is_ok());
+        assert!(req_id(2, other, hub).assert_hub_side(true, hub).is_ok());
+        assert_eq!(req_id(1, hub, other).assert_hub_side(true, hub).unwrap_err(), FreeTunnelError::NotMintSide.into());
+        // Burn-mint from either side
+        assert!(req_id(3, other, hub).assert_hub_side(true, hub).is_ok());
+        assert!(req_id(3, hub, other).assert_hub_side(true, hub).is_ok());
+        assert_eq!(req_id(3, other, other).assert_hub_side(true, hub).unwrap_err(), FreeTunnelError::NotMintOppositeSide.into());
+
+        // Lock contract: this hub is the opposite side, and burn-mint does not apply
+        assert!(req_id(1, hub, other).assert_hub_side(false, hub).is_ok());
+        assert!(req_id(2, hub, other).assert_hub_side(false, hub).is_ok());
+        assert_eq!(req_id(1, other, hub).assert_hub_side(false, hub).unwrap_err(), FreeTunnelError::NotMintOppositeSide.into());
+        assert_eq!(req_id(3, hub, other).assert_hub_side(false, hub).unwrap_err(), FreeTunnelError::NotMintContract.into());
+
+        assert_eq!(req_id(4, other, hub).assert_hub_side(true, hub).unwrap_err(), FreeTunnelError::NotLockMint.into());
+    }
+
+    #[test]
+    fn test_checked_amount_overflow_boundary() {
+        let with_raw_amount = |raw_amount: u64| {
+            let mut data = [0u8; 32];
+            data[8..16].copy_from_slice(&raw_amount.to_be_bytes());
+            ReqId::new(data)
+        };
+        for (decimals, max_raw_amount) in [(9u8, u64::MAX / 1_000), (12, u64::MAX / 1_000_000), (18, u64::MAX / 1_000_000_000_000)] {
+            let factor = 10u64.pow((decimals - 6) as u32);
+            assert_eq!(ReqId::max_raw_amount(decimals), max_raw_amount);
+            assert_eq!(with_raw_amount(max_raw_amount).get_checked_amount(decimals), Ok(max_raw_amount * factor));
+            assert_eq!(
+                with_raw_amount(max_raw_amount + 1).get_checked_amount(decimals).unwrap_err(),
+                FreeTunnelError::ArithmeticOverflow.into(),
+            );
+        }
+
+        // Up to 6 decimals nothing is scaled up
+        assert_eq!(ReqId::max_raw_amount(6), u64::MAX);
+        assert_eq!(with_raw_amount(u64::MAX).get_checked_amount(6), Ok(u64::MAX));
+        // From 26 decimals the factor alone exceeds `u64::MAX`
+        assert_eq!(ReqId::max_raw_amount(25), 1);
+        assert_eq!(ReqId::max_raw_amount(26), 0);
+        assert_eq!(with_raw_amount(1).get_checked_amount(26).unwrap_err(), FreeTunnelError::ArithmeticOverflow.into());
+    }
 }
@@ -0,0 +1,170 @@
+//! Stand-ins for the runtime services an end-to-end `Processor` test needs: the `Clock` and
+//! `Rent` sysvars, return data, and the system and SPL token instructions the program reaches
+//! through CPI.
+//!
+//! They are off by default, so other tests keep seeing `UnsupportedSysvar` and no-op CPIs. A
+//! test turns them on for its own thread with `set_clock`:
+//!
+//! ```ignore
+//! runtime_stubs::set_clock(NOW);
+//! Processor::process_instruction(&program_id, &accounts, &data)?;
+//! ```
+//!
+//! Created accounts are resized in place, so the `FixtureAccount`s passed in must leave realloc
+//! room, which they do. Only SPL token (not token-2022) transfers are simulated.
+
+use std::{
+    cell::{Cell, RefCell},
+    sync::Once,
+};
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction, program_error::{ProgramError, UNSUPPORTED_SYSVAR},
+    program_pack::Pack, program_stubs::{self, SyscallStubs}, pubkey::Pubkey, rent::Rent,
+};
+use spl_token::{error::TokenError, instruction::TokenInstruction, state::Account as TokenAccount};
+
+thread_local! {
+    static CLOCK: Cell<Option<i64>> = const { Cell::new(None) };
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+static INSTALL: Once = Once::new();
+
+/// Turns the stubs on for the current thread, with `Clock::get` returning `unix_timestamp`
+pub fn set_clock(unix_timestamp: i64) {
+    INSTALL.call_once(|| { program_stubs::set_syscall_stubs(Box::new(RuntimeStubs)); });
+    CLOCK.with(|clock| clock.set(Some(unix_timestamp)));
+}
+
+/// Data last passed to `set_return_data` on the current thread
+pub fn return_data() -> Vec<u8> {
+    RETURN_DATA.with(|data| data.borrow().clone())
+}
+
+fn clock() -> Option<i64> {
+    CLOCK.with(|clock| clock.get())
+}
+
+struct RuntimeStubs;
+
+impl SyscallStubs for RuntimeStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match clock() {
+            Some(unix_timestamp) => {
+                unsafe { *(var_addr as *mut Clock) = Clock { unix_timestamp, ..Clock::default() }; }
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match clock() {
+            Some(_) => {
+                unsafe { *(var_addr as *mut Rent) = Rent::default(); }
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = data.to_vec());
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if clock().is_none() {
+            return Ok(());
+        }
+        let account = |index: usize| -> Result<&AccountInfo, ProgramError> {
+            let meta = instruction.accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            account_infos.iter().find(|info| info.key == &meta.pubkey).ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        if instruction.program_id == solana_sdk_ids::system_program::ID {
+            invoke_system(&instruction.data, account)
+        } else if instruction.program_id == spl_token::id() {
+            invoke_token(&instruction.data, account)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    let bytes = data.get(offset..offset + 8).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    let bytes = data.get(offset..offset + 32).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let remaining = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+/// `SystemInstruction` is bincode-encoded: a `u32` variant, then its fields
+fn invoke_system<'b, 'a: 'b>(
+    data: &[u8],
+    account: impl Fn(usize) -> Result<&'b AccountInfo<'a>, ProgramError>,
+) -> ProgramResult {
+    let variant = u32::from_le_bytes(data.get(..4).ok_or(ProgramError::InvalidInstructionData)?.try_into().unwrap());
+    match variant {
+        // CreateAccount { lamports, space, owner }
+        0 => {
+            let (from, to) = (account(0)?, account(1)?);
+            if to.lamports() != 0 || !to.data_is_empty() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            move_lamports(from, to, read_u64(data, 4)?)?;
+            to.resize(read_u64(data, 12)? as usize)?;
+            to.assign(&read_pubkey(data, 20)?);
+            Ok(())
+        }
+        // Assign { owner }
+        1 => {
+            account(0)?.assign(&read_pubkey(data, 4)?);
+            Ok(())
+        }
+        // Transfer { lamports }
+        2 => move_lamports(account(0)?, account(1)?, read_u64(data, 4)?),
+        // Allocate { space }
+        8 => account(0)?.resize(read_u64(data, 4)? as usize),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn invoke_token<'b, 'a: 'b>(
+    data: &[u8],
+    account: impl Fn(usize) -> Result<&'b AccountInfo<'a>, ProgramError>,
+) -> ProgramResult {
+    let (source, destination, authority, amount) = match TokenInstruction::unpack(data)? {
+        TokenInstruction::Transfer { amount } => (account(0)?, account(1)?, account(2)?, amount),
+        TokenInstruction::TransferChecked { amount, .. } => (account(0)?, account(2)?, account(3)?, amount),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let mut source_account = TokenAccount::unpack(&source.data.borrow())?;
+    let mut destination_account = TokenAccount::unpack(&destination.data.borrow())?;
+    if source_account.owner != *authority.key {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    if source_account.mint != destination_account.mint {
+        return Err(TokenError::MintMismatch.into());
+    }
+    source_account.amount = source_account.amount.checked_sub(amount).ok_or(TokenError::InsufficientFunds)?;
+    destination_account.amount = destination_account.amount.checked_add(amount).ok_or(TokenError::Overflow)?;
+    TokenAccount::pack(source_account, &mut source.data.borrow_mut())?;
+    TokenAccount::pack(destination_account, &mut destination.data.borrow_mut())?;
+    Ok(())
+}
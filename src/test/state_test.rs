@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod state_test {
+    use crate::constants::Constants;
+    use crate::state::{ExecutorsInfo, ProposalKind, ProposerActivity, SparseArray};
+    use crate::test::test_fixtures::basic_storage;
+    use borsh::BorshDeserialize;
+    use solana_program::pubkey::Pubkey;
+
+    fn req_id(n: u8) -> [u8; 32] {
+        [n; 32]
+    }
+
+    #[test]
+    fn test_proposer_activity_record_and_prune() {
+        let mut activity = ProposerActivity::default();
+        activity.record(req_id(1));
+        activity.record(req_id(2));
+        activity.record(req_id(3));
+        assert_eq!(activity.req_ids, vec![req_id(1), req_id(2), req_id(3)]);
+
+        assert!(activity.prune(&req_id(2)));
+        assert_eq!(activity.req_ids, vec![req_id(1), req_id(3)]);
+        assert!(!activity.prune(&req_id(2)));
+    }
+
+    #[test]
+    fn test_proposer_activity_wraps_around() {
+        let mut activity = ProposerActivity::default();
+        let max = Constants::MAX_PROPOSER_ACTIVITY as u8;
+        for n in 0..max + 2 {
+            activity.record(req_id(n));
+        }
+        // The two oldest entries were dropped
+        assert_eq!(activity.req_ids.len(), Constants::MAX_PROPOSER_ACTIVITY);
+        assert_eq!(activity.req_ids[0], req_id(2));
+        assert_eq!(activity.req_ids[Constants::MAX_PROPOSER_ACTIVITY - 1], req_id(max + 1));
+
+        // Fits the allocated account size when full
+        assert!(borsh::to_vec(&activity).unwrap().len() <= Constants::SIZE_PROPOSER_ACTIVITY);
+    }
+
+    #[test]
+    fn test_proposal_kind_discriminators() {
+        let kinds = [ProposalKind::Mint, ProposalKind::Burn, ProposalKind::Lock, ProposalKind::Unlock];
+        for (i, kind) in kinds.iter().enumerate() {
+            assert_eq!(kind.discriminator(), i as u8 + 1);
+            assert_eq!(ProposalKind::from_discriminator(kind.discriminator()), Some(*kind));
+        }
+        // Legacy proposals start with their length prefix, 32 or 64
+        assert_eq!(ProposalKind::from_discriminator(32), None);
+        assert_eq!(ProposalKind::from_discriminator(64), None);
+        assert_eq!(ProposalKind::from_discriminator(0), None);
+    }
+
+    #[test]
+    fn test_sparse_array_borsh_round_trip() {
+        let mut array = SparseArray::<u64>::default();
+        array.insert(17, 1_700).unwrap();
+        array.insert(3, 300).unwrap();
+        array.insert(31, 3_100).unwrap();
+
+        // Only the occupied slots are written: a `u32` count, then `(u8, u64)` per entry
+        let data = borsh::to_vec(&array).unwrap();
+        assert_eq!(data.len(), 4 + 3 * (1 + 8));
+
+        let decoded = SparseArray::<u64>::try_from_slice(&data).unwrap();
+        assert_eq!(decoded.len(), 3);
+        for id in 0..=u8::MAX {
+            let expected = match id {
+                3 => Some(300),
+                17 => Some(1_700),
+                31 => Some(3_100),
+                _ => None,
+            };
+            assert_eq!(decoded.get(id).copied(), expected, "id {}", id);
+        }
+    }
+
+    #[test]
+    fn test_full_basic_storage_fits_its_size() {
+        let mut storage = basic_storage(Pubkey::new_unique(), (0..Constants::MAX_PROPOSERS).map(|_| Pubkey::new_unique()).collect());
+        storage.relayers = (0..Constants::MAX_RELAYERS).map(|_| Pubkey::new_unique()).collect();
+        storage.proposer_last_propose = (0..Constants::MAX_RATE_LIMITED_PROPOSERS).map(|_| (Pubkey::new_unique(), u64::MAX)).collect();
+        for id in 0..Constants::MAX_TOKENS as u8 {
+            storage.tokens.insert(id, Pubkey::new_unique()).unwrap();
+            storage.vaults.insert(id, Pubkey::new_unique()).unwrap();
+            storage.decimals.insert(id, u8::MAX).unwrap();
+            storage.locked_balance.insert(id, u64::MAX).unwrap();
+            storage.pending_proposals.insert(id, u16::MAX).unwrap();
+            storage.previous_vaults.insert(id, (Pubkey::new_unique(), u64::MAX)).unwrap();
+            storage.peer_token_addresses.insert(id, [0xff; 20]).unwrap();
+            storage.retired_tokens.insert(id, Pubkey::new_unique()).unwrap();
+            storage.token_programs.insert(id, Pubkey::new_unique()).unwrap();
+            storage.pending_deposits.insert(id, u64::MAX).unwrap();
+        }
+        // Every sparse array is full
+        assert!(storage.tokens.insert(Constants::MAX_TOKENS as u8, Pubkey::new_unique()).is_err());
+
+        let serialized = borsh::to_vec(&storage).unwrap();
+        assert!(
+            serialized.len() <= Constants::SIZE_BASIC_STORAGE,
+            "serialized {} > SIZE_BASIC_STORAGE {}",
+            serialized.len(),
+            Constants::SIZE_BASIC_STORAGE,
+        );
+    }
+
+    #[test]
+    fn test_full_executors_info_fits_its_size() {
+        let executors_info = ExecutorsInfo {
+            index: u64::MAX,
+            threshold: u64::MAX,
+            active_since: u64::MAX,
+            inactive_after: u64::MAX,
+            executors: (0..Constants::MAX_EXECUTORS).map(|i| [i as u8; 20]).collect(),
+            ed25519_executors: (0..Constants::MAX_EXECUTORS).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let serialized = borsh::to_vec(&executors_info).unwrap();
+        assert!(
+            serialized.len() <= Constants::SIZE_EXECUTORS_STORAGE,
+            "serialized {} > SIZE_EXECUTORS_STORAGE {}",
+            serialized.len(),
+            Constants::SIZE_EXECUTORS_STORAGE,
+        );
+    }
+}
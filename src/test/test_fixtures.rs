@@ -0,0 +1,272 @@
+//! Builders for the `AccountInfo`s and account data that unit tests pass to the program logic.
+//!
+//! A `FixtureAccount` owns its key, lamports and data, and lends them out as an `AccountInfo`:
+//!
+//! ```ignore
+//! let mut storage = FixtureAccount::new(Pubkey::new_unique())
+//!     .owner(program_id)
+//!     .writable()
+//!     .data(account_data(&basic_storage(admin, vec![proposer])));
+//! let storage = storage.to_account_info();
+//! ```
+//!
+//! Account data is stored as `u32 LE length || borsh`, see `account_data`. Token accounts and
+//! mints use the SPL `Pack` layout and must be owned by `spl_token::id()` to be recognized.
+//!
+//! Key and data are laid out with the prefixes the runtime's serialized input has in front of
+//! them, and followed by the same realloc padding, so `AccountInfo::resize` (as in
+//! `DataAccountUtils::close_account`, or an account created through `runtime_stubs`) works on
+//! fixtures. The current length is kept in the prefix, so it survives across `to_account_info`.
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::MAX_PERMITTED_DATA_INCREASE, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey,
+    sysvar::{self, instructions::{construct_instructions_data, BorrowedInstruction}},
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+use crate::constants::{Constants, EthAddress};
+use crate::state::{BasicStorage, ExecutorsInfo, SparseArray};
+
+/// `AccountInfo::original_data_len` reads the 4 bytes before the key
+#[repr(C)]
+struct SerializedKey {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// `AccountInfo::resize` writes the new length in the 8 bytes before the data
+const SIZE_DATA_LEN_PREFIX: usize = 8;
+
+pub struct FixtureAccount {
+    key: SerializedKey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>, // prefixed with `SIZE_DATA_LEN_PREFIX` bytes, followed by the realloc padding
+    is_signer: bool,
+    is_writable: bool,
+    executable: bool,
+}
+
+impl FixtureAccount {
+    /// Empty, read-only account owned by the system program
+    pub fn new(key: Pubkey) -> Self {
+        Self {
+            key: SerializedKey { original_data_len: 0, key },
+            owner: solana_sdk_ids::system_program::ID,
+            lamports: 0,
+            data: vec![0u8; SIZE_DATA_LEN_PREFIX + MAX_PERMITTED_DATA_INCREASE],
+            is_signer: false,
+            is_writable: false,
+            executable: false,
+        }
+    }
+
+    /// Program account for `prefix` and `phrase`, as `DataAccountUtils::assert_account_match` expects
+    pub fn pda(program_id: &Pubkey, prefix: &[u8], phrase: &[u8]) -> Self {
+        let (key, _) = Pubkey::find_program_address(&[prefix, phrase], program_id);
+        Self::new(key).owner(*program_id).writable()
+    }
+
+    pub fn owner(mut self, owner: Pubkey) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    pub fn lamports(mut self, lamports: u64) -> Self {
+        self.lamports = lamports;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.key.original_data_len = data.len() as u32;
+        self.data = (data.len() as u64).to_le_bytes().to_vec();
+        self.data.extend(data);
+        self.data.resize(self.data.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        self
+    }
+
+    pub fn key(&self) -> Pubkey {
+        self.key.key
+    }
+
+    pub fn to_account_info(&mut self) -> AccountInfo<'_> {
+        let data_len = u64::from_le_bytes(self.data[..SIZE_DATA_LEN_PREFIX].try_into().unwrap()) as usize;
+        AccountInfo::new(
+            &self.key.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data[SIZE_DATA_LEN_PREFIX..SIZE_DATA_LEN_PREFIX + data_len],
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+/// Data of a program account holding `content`
+pub fn account_data<T: BorshSerialize>(content: &T) -> Vec<u8> {
+    let content = borsh::to_vec(content).unwrap();
+    let mut data = (content.len() as u32).to_le_bytes().to_vec();
+    data.extend(content);
+    data
+}
+
+/// Mint contract storage with one executors group and no tokens
+pub fn basic_storage(admin: Pubkey, proposers: Vec<Pubkey>) -> BasicStorage {
+    BasicStorage {
+        mint_or_lock: true,
+        admin,
+        proposers,
+        executors_group_length: 1,
+        tokens: SparseArray::default(),
+        vaults: SparseArray::default(),
+        decimals: SparseArray::default(),
+        locked_balance: SparseArray::default(),
+        min_propose_interval: 0,
+        proposer_last_propose: vec![],
+        max_proposers: Constants::MAX_PROPOSERS as u8,
+        pending_proposals: SparseArray::default(),
+        previous_vaults: SparseArray::default(),
+        peer_token_addresses: SparseArray::default(),
+        rotation_min_delay: Constants::EXECUTORS_ROTATION_MIN_DELAY,
+        rotation_max_delay: Constants::EXECUTORS_ROTATION_MAX_DELAY,
+        retired_tokens: SparseArray::default(),
+        relayers: vec![],
+        token_programs: SparseArray::default(),
+        pending_deposits: SparseArray::default(),
+        event_seq: 0,
+        renounce_after: 0,
+        open_lock: false,
+        paused_since: 0,
+    }
+}
+
+/// `BasicStorage` as the first release stored it, before any field was appended
+#[derive(BorshSerialize)]
+pub struct LegacyBasicStorage {
+    pub mint_or_lock: bool,
+    pub admin: Pubkey,
+    pub proposers: Vec<Pubkey>,
+    pub executors_group_length: u64,
+    pub tokens: SparseArray<Pubkey>,
+    pub vaults: SparseArray<Pubkey>,
+    pub decimals: SparseArray<u8>,
+    pub locked_balance: SparseArray<u64>,
+}
+
+/// Length of a basic storage account created by the first release
+pub const LEGACY_BASIC_STORAGE_LENGTH: usize = Constants::SIZE_LENGTH
+    + 1 + 32 + (4 + 32 * Constants::MAX_PROPOSERS) + 8
+    + (4 + Constants::MAX_TOKENS * (1 + 32))
+    + (4 + Constants::MAX_TOKENS * (1 + 32))
+    + (4 + Constants::MAX_TOKENS * (1 + 1))
+    + (4 + Constants::MAX_TOKENS * (1 + 8));
+
+/// Lock contract storage in the first release's layout, holding `token_index` for `mint`
+pub fn legacy_basic_storage_data(admin: Pubkey, proposers: Vec<Pubkey>, token_index: u8, mint: Pubkey) -> Vec<u8> {
+    let mut storage = LegacyBasicStorage {
+        mint_or_lock: false,
+        admin,
+        proposers,
+        executors_group_length: 1,
+        tokens: SparseArray::default(),
+        vaults: SparseArray::default(),
+        decimals: SparseArray::default(),
+        locked_balance: SparseArray::default(),
+    };
+    storage.tokens.insert(token_index, mint).unwrap();
+    storage.vaults.insert(token_index, Pubkey::new_unique()).unwrap();
+    storage.decimals.insert(token_index, 6).unwrap();
+    storage.locked_balance.insert(token_index, 1_000).unwrap();
+    let mut data = account_data(&storage);
+    data.resize(LEGACY_BASIC_STORAGE_LENGTH, 0);
+    data
+}
+
+/// Executors group 0, active since the epoch and never inactive
+pub fn executors_info(executors: Vec<EthAddress>, threshold: u64) -> ExecutorsInfo {
+    ExecutorsInfo {
+        index: 0,
+        threshold,
+        active_since: 1,
+        inactive_after: 0,
+        executors,
+        ed25519_executors: vec![],
+    }
+}
+
+/// Initialized SPL token account of `mint` holding `amount`
+pub fn token_account_data(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+    let account = TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    };
+    let mut data = vec![0u8; TokenAccount::LEN];
+    account.pack_into_slice(&mut data);
+    data
+}
+
+/// Initialized SPL mint with `supply`, minted by `mint_authority`
+pub fn mint_data(mint_authority: Pubkey, decimals: u8, supply: u64) -> Vec<u8> {
+    let mint = Mint {
+        mint_authority: COption::Some(mint_authority),
+        supply,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut data = vec![0u8; Mint::LEN];
+    mint.pack_into_slice(&mut data);
+    data
+}
+
+/// Data of a `secp256k1_program` instruction at `ix_index` with one entry
+pub fn build_secp256k1_ix_data(eth_address: [u8; 20], signature: [u8; 64], recovery_id: u8, message: &[u8], ix_index: u8) -> Vec<u8> {
+    // Layout: count | offsets (11 bytes) | eth_address (20) | signature (64 + 1) | message
+    let eth_address_offset: u16 = 1 + 11;
+    let signature_offset: u16 = eth_address_offset + 20;
+    let message_offset: u16 = signature_offset + 65;
+    let mut data = vec![1u8];
+    data.extend_from_slice(&signature_offset.to_le_bytes());
+    data.push(ix_index);
+    data.extend_from_slice(&eth_address_offset.to_le_bytes());
+    data.push(ix_index);
+    data.extend_from_slice(&message_offset.to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(ix_index);
+    data.extend_from_slice(&eth_address);
+    data.extend_from_slice(&signature);
+    data.push(recovery_id);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Instructions sysvar of a transaction with `instructions`, each given as `(program_id, data)`
+pub fn instructions_sysvar(instructions: &[(Pubkey, Vec<u8>)]) -> FixtureAccount {
+    let instructions: Vec<BorrowedInstruction> = instructions
+        .iter()
+        .map(|(program_id, data)| BorrowedInstruction { program_id, accounts: vec![], data })
+        .collect();
+    FixtureAccount::new(sysvar::instructions::ID).data(construct_instructions_data(&instructions))
+}
@@ -0,0 +1,220 @@
+#[cfg(test)]
+mod token_ops_test {
+    use crate::constants::Constants;
+    use crate::error::FreeTunnelError;
+    use crate::logic::token_ops;
+    use crate::state::{BasicStorage, ReconciliationView, SparseArray};
+    use crate::test::test_fixtures::{account_data, basic_storage, token_account_data, FixtureAccount};
+    use crate::utils::DataAccountUtils;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+
+    #[test]
+    fn test_token_reuse_needs_confirmation_for_a_different_mint() {
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut retired_tokens = SparseArray::default();
+        let previously_used: ProgramError = FreeTunnelError::TokenIndexPreviouslyUsed.into();
+
+        // Add: a fresh index needs no flag
+        assert_eq!(token_ops::assert_token_reuse_confirmed(&retired_tokens, 1, &mint, false), Ok(None));
+
+        // Remove, then re-add the same mint without the flag
+        retired_tokens.insert(1, mint).unwrap();
+        assert_eq!(token_ops::assert_token_reuse_confirmed(&retired_tokens, 1, &mint, false), Ok(Some(mint)));
+
+        // Re-add a different mint: rejected without the flag, accepted with it
+        assert_eq!(token_ops::assert_token_reuse_confirmed(&retired_tokens, 1, &other_mint, false), Err(previously_used));
+        assert_eq!(token_ops::assert_token_reuse_confirmed(&retired_tokens, 1, &other_mint, true), Ok(Some(mint)));
+
+        // Other indexes are unaffected
+        assert_eq!(token_ops::assert_token_reuse_confirmed(&retired_tokens, 2, &other_mint, false), Ok(None));
+    }
+
+    #[test]
+    fn test_deposit_token_account_must_belong_to_proposer() {
+        let (mint, proposer, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut token_program = FixtureAccount::new(spl_token::id()).executable();
+        let token_program = token_program.to_account_info();
+
+        let mut owned = FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id()).data(token_account_data(mint, proposer, 100));
+        assert!(token_ops::assert_token_account_owner(&token_program, &owned.to_account_info(), &proposer).is_ok());
+
+        let mut foreign = FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id()).data(token_account_data(mint, other, 100));
+        assert_eq!(
+            token_ops::assert_token_account_owner(&token_program, &foreign.to_account_info(), &proposer).unwrap_err(),
+            FreeTunnelError::TokenAccountOwnerMismatch.into(),
+        );
+
+        let mut uninitialized = FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id()).data(vec![0; 165]);
+        assert_eq!(
+            token_ops::assert_token_account_owner(&token_program, &uninitialized.to_account_info(), &proposer).unwrap_err(),
+            FreeTunnelError::InvalidTokenAccount.into(),
+        );
+    }
+
+    #[test]
+    fn test_cancel_refunds_to_the_recorded_token_account() {
+        let (mint, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut token_program = FixtureAccount::new(spl_token::id()).executable();
+        let token_program = token_program.to_account_info();
+        let source = Pubkey::new_unique();
+        let ata = get_associated_token_address(&proposer, &mint);
+        let check = |token_account: Pubkey, source: Option<Pubkey>| {
+            let mut token_account = FixtureAccount::new(token_account);
+            token_ops::assert_refund_token_account(&token_program, &token_account.to_account_info(), source, &proposer, &mint)
+        };
+
+        // The deposit's source account only, even over the proposer's ATA
+        assert!(check(source, Some(source)).is_ok());
+        assert_eq!(check(ata, Some(source)).unwrap_err(), FreeTunnelError::InvalidTokenAccount.into());
+
+        // Proposals without a recorded source refund to the proposer's ATA
+        assert!(check(ata, None).is_ok());
+        assert_eq!(check(source, None).unwrap_err(), FreeTunnelError::InvalidTokenAccount.into());
+    }
+
+    #[test]
+    fn test_token_program_must_match_the_added_one() {
+        use crate::test::test_fixtures::{account_data, basic_storage};
+
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+        storage.token_programs.insert(1, spl_token::id()).unwrap();
+        storage.token_programs.insert(2, spl_token_2022::id()).unwrap();
+        storage.tokens.insert(4, Pubkey::new_unique()).unwrap(); // added before token programs were recorded
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&storage));
+        let storage = storage.to_account_info();
+        let check = |token_index: u8, token_program: Pubkey| {
+            let mut token_program = FixtureAccount::new(token_program).executable();
+            token_ops::assert_token_program_of(&storage, token_index, &token_program.to_account_info())
+        };
+
+        assert!(check(1, spl_token::id()).is_ok());
+        assert!(check(2, spl_token_2022::id()).is_ok());
+        // Token-2022 submitted for a classic SPL token, and the other way round
+        assert_eq!(check(1, spl_token_2022::id()).unwrap_err(), FreeTunnelError::TokenProgramMismatch.into());
+        assert_eq!(check(2, spl_token::id()).unwrap_err(), FreeTunnelError::TokenProgramMismatch.into());
+        assert_eq!(check(3, spl_token::id()).unwrap_err(), FreeTunnelError::TokenIndexNonExistent.into());
+        assert!(check(4, spl_token::id()).is_ok());
+        assert!(check(4, spl_token_2022::id()).is_ok());
+        assert_eq!(check(4, Pubkey::new_unique()).unwrap_err(), FreeTunnelError::InvalidTokenProgram.into());
+    }
+
+    #[test]
+    fn test_unlock_split_across_previous_and_current_vault() {
+        let (mint, contract_signer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut token_program = FixtureAccount::new(spl_token::id()).executable();
+        let token_program = token_program.to_account_info();
+        let split = |previous_balance: Option<u64>, current_balance: u64, amount: u64| {
+            let mut previous = previous_balance.map(|balance| {
+                FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id())
+                    .data(token_account_data(mint, contract_signer, balance))
+            });
+            let mut current = FixtureAccount::new(Pubkey::new_unique()).owner(spl_token::id())
+                .data(token_account_data(mint, contract_signer, current_balance));
+            let previous = previous.as_mut().map(|previous| previous.to_account_info());
+            token_ops::split_unlock_amount(&token_program, previous.as_ref(), &current.to_account_info(), amount)
+        };
+
+        // Amount spans both vaults: the previous one is drained first
+        assert_eq!(split(Some(60), 100, 150), Ok((60, 90)));
+        // The previous vault alone suffices
+        assert_eq!(split(Some(200), 0, 150), Ok((150, 0)));
+        // Without the previous vault, the current one pays everything
+        assert_eq!(split(None, 150, 150), Ok((0, 150)));
+        // Together still not enough
+        assert_eq!(split(Some(60), 80, 150).unwrap_err(), FreeTunnelError::VaultBalanceInsufficient.into());
+    }
+
+    #[cfg(feature = "invariants")]
+    #[test]
+    fn test_vault_invariant_tripwire() {
+        use crate::test::test_fixtures::{account_data, basic_storage};
+
+        let (mint, vault, contract_signer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+        storage.tokens.insert(1, mint).unwrap();
+        storage.vaults.insert(1, vault).unwrap();
+        storage.locked_balance.insert(1, 100).unwrap();
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&storage));
+        let storage = storage.to_account_info();
+        let mut token_program = FixtureAccount::new(spl_token::id()).executable();
+        let token_program = token_program.to_account_info();
+        let check = |token_account: Pubkey, vault_balance: u64| {
+            let mut token_account = FixtureAccount::new(token_account).owner(spl_token::id())
+                .data(token_account_data(mint, contract_signer, vault_balance));
+            token_ops::assert_vault_invariant(&token_program, &storage, 1, &token_account.to_account_info())
+        };
+
+        assert!(check(vault, 100).is_ok());
+        assert!(check(vault, 150).is_ok());
+        // Tokens pulled out of the vault behind the program's back
+        assert_eq!(check(vault, 99).unwrap_err(), FreeTunnelError::InvariantViolated.into());
+        // A previous vault does not hold the locked balance anymore
+        assert!(check(Pubkey::new_unique(), 0).is_ok());
+    }
+
+    #[test]
+    fn test_reconciliation_after_lock_unlock_and_direct_transfer() {
+        let (vault, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = basic_storage(Pubkey::new_unique(), vec![]);
+        storage.mint_or_lock = false;
+        storage.tokens.insert(1, mint).unwrap();
+        storage.vaults.insert(1, vault).unwrap();
+        storage.locked_balance.insert(1, 0).unwrap();
+        storage.pending_deposits.insert(1, 0).unwrap();
+        let mut data = account_data(&storage);
+        data.resize(Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE, 0);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).writable().data(data);
+        let storage = storage.to_account_info();
+        let add_locked = |amount: i64| {
+            let mut basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(&storage).unwrap();
+            let locked_balance = basic_storage.locked_balance.get_mut(1).unwrap();
+            *locked_balance = (*locked_balance as i64 + amount) as u64;
+            DataAccountUtils::write_account_data(&storage, basic_storage).unwrap();
+        };
+        let reconcile = |vault_balance: u64| {
+            let basic_storage: BasicStorage = DataAccountUtils::read_basic_storage(&storage).unwrap();
+            token_ops::reconciliation(&basic_storage, 1, vault_balance).unwrap()
+        };
+
+        // ProposeLock deposits 100, ExecuteLock moves it into the locked balance
+        token_ops::update_pending_deposits(&storage, 1, 100, true).unwrap();
+        assert_eq!(reconcile(100).pending_deposits, 100);
+        assert_eq!(reconcile(100).drift, 0);
+        token_ops::update_pending_deposits(&storage, 1, 100, false).unwrap();
+        add_locked(100);
+
+        // ProposeUnlock takes 30 out of the locked balance while the vault still holds it
+        add_locked(-30);
+        token_ops::update_pending_deposits(&storage, 1, 30, true).unwrap();
+        assert_eq!((reconcile(100).locked_balance, reconcile(100).pending_deposits, reconcile(100).drift), (70, 30, 0));
+        token_ops::update_pending_deposits(&storage, 1, 30, false).unwrap();
+
+        // ExecuteUnlock paid out 30, then 5 were sent to the vault out of band
+        assert_eq!(
+            reconcile(75),
+            ReconciliationView { token_index: 1, vault, locked_balance: 70, vault_balance: 75, pending_deposits: 0, drift: 5 },
+        );
+        // A vault short of the locked balance shows a negative drift
+        assert_eq!(reconcile(60).drift, -10);
+
+        // Removing a deposit from before it was tracked stops at zero
+        token_ops::update_pending_deposits(&storage, 1, 1, false).unwrap();
+        assert_eq!(reconcile(75).pending_deposits, 0);
+        assert_eq!(reconcile(75).drift, 5);
+    }
+
+    #[test]
+    fn test_vault_balance_detects_token_program_from_owner() {
+        let data = token_account_data(Pubkey::new_unique(), Pubkey::new_unique(), 42);
+        for owner in [spl_token::id(), spl_token_2022::id()] {
+            let mut vault = FixtureAccount::new(Pubkey::new_unique()).owner(owner).data(data.clone());
+            assert_eq!(token_ops::token_account_balance_by_owner(&vault.to_account_info()), Ok(42));
+        }
+        let mut not_a_token_account = FixtureAccount::new(Pubkey::new_unique()).data(data);
+        assert_eq!(
+            token_ops::token_account_balance_by_owner(&not_a_token_account.to_account_info()),
+            Err(FreeTunnelError::InvalidTokenAccount.into()),
+        );
+    }
+}
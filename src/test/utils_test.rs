@@ -1,7 +1,14 @@
 #[cfg(test)]
 mod utils_test {
-    use crate::utils::SignatureUtils;
+    use crate::constants::Constants;
+    use crate::error::{DataAccountError, FreeTunnelError};
+    use crate::state::{BasicStorage, ProposalHeader, ProposalKind, ProposedMint, ProposedUnlock};
+    use crate::test::runtime_stubs;
+    use crate::test::test_fixtures::{self, account_data, build_secp256k1_ix_data, executors_info, instructions_sysvar, FixtureAccount};
+    use crate::utils::{DataAccountUtils, SignatureUtils};
+    use borsh::BorshSerialize;
     use hex;
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
 
     #[test]
     fn test_eth_address_from_pubkey() {
@@ -27,7 +34,10 @@ mod utils_test {
             .unwrap()
             .try_into()
             .unwrap();
-        assert_eq!(eth_address, eth_address_expected);
+        assert_eq!(eth_address, Some(eth_address_expected));
+
+        // An invalid signature (`r` and `s` of zero) recovers nothing, not the zero address
+        assert_eq!(SignatureUtils::recover_eth_address(message, [0; 64]), None);
     }
 
     #[test]
@@ -39,29 +49,773 @@ mod utils_test {
     }
 
     #[test]
-    fn test_cmp_addr_list() {
+    fn test_is_superset_of() {
         let eth_addr1 = [0; 20];
         let eth_addr2 = [1; 20];
         let eth_addr3 = [2; 20];
-        assert!(SignatureUtils::cmp_addr_list(
-            &vec![eth_addr1, eth_addr2],
-            &vec![eth_addr1]
-        ));
-        assert!(!SignatureUtils::cmp_addr_list(
-            &vec![eth_addr1],
-            &vec![eth_addr1, eth_addr2]
-        ));
-        assert!(SignatureUtils::cmp_addr_list(
-            &vec![eth_addr1, eth_addr2],
-            &vec![eth_addr1, eth_addr1]
-        ));
-        assert!(!SignatureUtils::cmp_addr_list(
-            &vec![eth_addr2, eth_addr1],
-            &vec![eth_addr2, eth_addr2]
-        ));
-        assert!(!SignatureUtils::cmp_addr_list(
-            &vec![eth_addr2, eth_addr3],
-            &vec![eth_addr2, eth_addr3]
-        ));
+        // Equal sets, so the same pending group can be re-submitted
+        assert!(SignatureUtils::is_superset_of(&[eth_addr1, eth_addr2], &[eth_addr1, eth_addr2]));
+        // Superset
+        assert!(SignatureUtils::is_superset_of(&[eth_addr1, eth_addr2, eth_addr3], &[eth_addr1, eth_addr3]));
+        // Subset
+        assert!(!SignatureUtils::is_superset_of(&[eth_addr1], &[eth_addr1, eth_addr2]));
+        // Disjoint
+        assert!(!SignatureUtils::is_superset_of(&[eth_addr3], &[eth_addr1, eth_addr2]));
+        // Stored unsorted by older versions
+        assert!(SignatureUtils::is_superset_of(&[eth_addr1, eth_addr2, eth_addr3], &[eth_addr3, eth_addr1]));
+    }
+
+    #[test]
+    fn test_parse_secp256k1_instruction() {
+        let message = b"stupid";
+        let signature_hex = "6fd862958c41d532022e404a809e92ec699bd0739f8d782ca752b07ff978f341f43065a96dc53a21b4eb4ce96a84a7c4103e3485b0c87d868df545fcce0f3983";
+        let compact: [u8; 64] = hex::decode(signature_hex).unwrap().try_into().unwrap();
+        let mut raw = compact;
+        let recovery_id = raw[32] >> 7;
+        raw[32] &= 0x7f;
+        let eth_address: [u8; 20] = hex::decode("2eF8a51F8fF129DBb874A0efB021702F59C1b211")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let data = build_secp256k1_ix_data(eth_address, raw, recovery_id, message, 0);
+        let entries = SignatureUtils::parse_secp256k1_instruction(&data, 0).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, eth_address);
+        assert_eq!(entries[0].1, compact);
+        assert_eq!(entries[0].2, message.to_vec());
+        assert_eq!(SignatureUtils::recover_eth_address(&entries[0].2, entries[0].1), Some(eth_address));
+    }
+
+    #[test]
+    fn test_parse_secp256k1_instruction_rejects_foreign_offsets() {
+        let data = build_secp256k1_ix_data([1; 20], [2; 64], 0, b"msg", 1);
+        assert!(SignatureUtils::parse_secp256k1_instruction(&data, 0).is_err());
+        assert!(SignatureUtils::parse_secp256k1_instruction(&data, 1).is_ok());
+        assert!(SignatureUtils::parse_secp256k1_instruction(&data[..data.len() - 1], 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_ed25519_instruction() {
+        // Layout: count | padding | offsets (14 bytes) | pubkey (32) | signature (64) | message
+        let message = b"msg";
+        let pubkey_offset: u16 = 2 + 14;
+        let signature_offset: u16 = pubkey_offset + 32;
+        let message_offset: u16 = signature_offset + 64;
+        let mut data = vec![1u8, 0];
+        data.extend_from_slice(&signature_offset.to_le_bytes());
+        data.extend_from_slice(&u16::MAX.to_le_bytes());
+        data.extend_from_slice(&pubkey_offset.to_le_bytes());
+        data.extend_from_slice(&u16::MAX.to_le_bytes());
+        data.extend_from_slice(&message_offset.to_le_bytes());
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&[7; 32]);
+        data.extend_from_slice(&[9; 64]);
+        data.extend_from_slice(message);
+
+        let entries = SignatureUtils::parse_ed25519_instruction(&data, 2).unwrap();
+        assert_eq!(entries, vec![(Pubkey::new_from_array([7; 32]), message.to_vec())]);
+        assert!(SignatureUtils::parse_ed25519_instruction(&data, 3).is_err());
+    }
+
+    #[test]
+    fn test_join_pubkey_list() {
+        let pubkeys = vec![Pubkey::new_from_array([1; 32])];
+        let result = SignatureUtils::join_pubkey_list(&pubkeys);
+        let expected = format!("0x{}\n", "01".repeat(32));
+        assert_eq!(result, expected.as_bytes());
+    }
+
+    #[test]
+    fn test_signature_bitmask() {
+        let message = b"stupid";
+        let signature_hex = "6fd862958c41d532022e404a809e92ec699bd0739f8d782ca752b07ff978f341f43065a96dc53a21b4eb4ce96a84a7c4103e3485b0c87d868df545fcce0f3983";
+        let signature: [u8; 64] = hex::decode(signature_hex).unwrap().try_into().unwrap();
+        let executor: [u8; 20] = hex::decode("2eF8a51F8fF129DBb874A0efB021702F59C1b211")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let all_valid = SignatureUtils::signature_bitmask(message, &vec![signature, signature], &vec![executor, executor]);
+        assert_eq!(all_valid, 0b11);
+        let one_invalid = SignatureUtils::signature_bitmask(message, &vec![signature, signature], &vec![[1; 20], executor]);
+        assert_eq!(one_invalid, 0b10);
+        let zero_address = SignatureUtils::signature_bitmask(message, &vec![signature], &vec![[0; 20]]);
+        assert_eq!(zero_address, 0);
+    }
+
+    fn executors_account_data(executors_len: usize) -> Vec<u8> {
+        account_data(&executors_info(vec![[1u8; 20]; executors_len], 1))
+    }
+
+    #[test]
+    fn test_read_executors_info_rejects_oversized_list() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let mut lamports = 0;
+        let mut data = executors_account_data(Constants::MAX_EXECUTORS);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_executors_info(&account).unwrap().executors.len(), Constants::MAX_EXECUTORS);
+
+        let mut lamports = 0;
+        let mut data = executors_account_data(Constants::MAX_EXECUTORS + 1);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_executors_info(&account).unwrap_err(), ProgramError::InvalidAccountData);
+    }
+
+    /// `ExecutorsInfo` as stored before `ed25519_executors` was added
+    #[derive(BorshSerialize)]
+    struct LegacyExecutorsInfo {
+        index: u64,
+        threshold: u64,
+        active_since: u64,
+        inactive_after: u64,
+        executors: Vec<[u8; 20]>,
+    }
+
+    #[test]
+    fn test_read_executors_info_legacy_layout() {
+        let legacy = LegacyExecutorsInfo { index: 2, threshold: 1, active_since: 5, inactive_after: 0, executors: vec![[7u8; 20]] };
+        let mut account = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&legacy));
+        let executors_info = DataAccountUtils::read_executors_info(&account.to_account_info()).unwrap();
+        assert_eq!((executors_info.index, executors_info.executors), (2, vec![[7u8; 20]]));
+        assert!(executors_info.ed25519_executors.is_empty());
+    }
+
+    #[test]
+    fn test_write_legacy_executors_info_grows_the_account() {
+        runtime_stubs::set_clock(1_700_000_000);
+        // A full group, in an account of the size used before `ed25519_executors` was added
+        let legacy = LegacyExecutorsInfo {
+            index: 0,
+            threshold: 1,
+            active_since: 1,
+            inactive_after: 0,
+            executors: vec![[7u8; 20]; Constants::MAX_EXECUTORS],
+        };
+        let legacy_length = Constants::SIZE_LENGTH + 8 + 8 + 8 + 8 + (4 + 20 * Constants::MAX_EXECUTORS);
+        let data = account_data(&legacy);
+        assert_eq!(data.len(), legacy_length);
+        let rent = Rent::default();
+        let mut executors = FixtureAccount::new(Pubkey::new_unique()).writable().lamports(rent.minimum_balance(legacy_length)).data(data);
+        let executors = executors.to_account_info();
+        let mut system_program = FixtureAccount::new(solana_sdk_ids::system_program::ID).executable();
+        let mut payer = FixtureAccount::new(Pubkey::new_unique()).signer().writable().lamports(1_000_000_000);
+        let payer = payer.to_account_info();
+
+        let mut executors_info = DataAccountUtils::read_executors_info(&executors).unwrap();
+        executors_info.inactive_after = 9;
+        assert_eq!(DataAccountUtils::write_account_data(&executors, &executors_info).unwrap_err(), ProgramError::InvalidAccountData);
+        DataAccountUtils::write_account_data_growing(&system_program.to_account_info(), &payer, &executors, &executors_info).unwrap();
+
+        assert_eq!(executors.data_len(), legacy_length + 4);
+        assert_eq!(executors.lamports(), rent.minimum_balance(legacy_length + 4));
+        assert_eq!(payer.lamports(), 1_000_000_000 - (rent.minimum_balance(legacy_length + 4) - rent.minimum_balance(legacy_length)));
+        let executors_info = DataAccountUtils::read_executors_info(&executors).unwrap();
+        assert_eq!((executors_info.inactive_after, executors_info.executors.len()), (9, Constants::MAX_EXECUTORS));
+    }
+
+    /// Executor `0x2eF8...b211` and its signature over `b"stupid"`, compact and raw with its recovery id
+    fn known_executor_signature() -> ([u8; 20], [u8; 64], [u8; 64], u8) {
+        let compact: [u8; 64] = hex::decode("6fd862958c41d532022e404a809e92ec699bd0739f8d782ca752b07ff978f341f43065a96dc53a21b4eb4ce96a84a7c4103e3485b0c87d868df545fcce0f3983")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let executor: [u8; 20] = hex::decode("2eF8a51F8fF129DBb874A0efB021702F59C1b211")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let mut raw = compact;
+        raw[32] &= 0x7f;
+        (executor, compact, raw, compact[32] >> 7)
+    }
+
+    fn build_ed25519_ix_data(pubkey: Pubkey, message: &[u8]) -> Vec<u8> {
+        // Layout: count | padding | offsets (14 bytes) | pubkey (32) | signature (64) | message,
+        // every offset pointing into this instruction (`u16::MAX`)
+        let pubkey_offset: u16 = 2 + 14;
+        let signature_offset: u16 = pubkey_offset + 32;
+        let message_offset: u16 = signature_offset + 64;
+        let mut data = vec![1u8, 0];
+        for offset in [signature_offset, pubkey_offset] {
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&u16::MAX.to_le_bytes());
+        }
+        data.extend_from_slice(&message_offset.to_le_bytes());
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.extend_from_slice(&u16::MAX.to_le_bytes());
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(&[9; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_multisig_counts_secp256k1_and_ed25519_executors_together() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let message = b"stupid";
+        let (executor, signature, raw, recovery_id) = known_executor_signature();
+        let (ed25519_executor, unregistered) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut executors_info = executors_info(vec![executor], 2);
+        executors_info.ed25519_executors = vec![ed25519_executor];
+        let mut executors = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&executors_info));
+        let executors = executors.to_account_info();
+        let secp256k1_ix = (solana_sdk_ids::secp256k1_program::ID, build_secp256k1_ix_data(executor, raw, recovery_id, message, 0));
+        let ed25519_ix = |pubkey: Pubkey, message: &[u8]| (solana_sdk_ids::ed25519_program::ID, build_ed25519_ix_data(pubkey, message));
+        let check = |instructions: &[(Pubkey, Vec<u8>)], executors_signing: Vec<[u8; 20]>| {
+            let mut sysvar_instructions = instructions_sysvar(instructions);
+            let signatures = vec![signature; executors_signing.len()];
+            SignatureUtils::assert_multisig_valid(&executors, Some(&sysvar_instructions.to_account_info()), message, &signatures, &executors_signing)
+        };
+
+        // One of each meets the threshold of 2
+        assert!(check(&[secp256k1_ix.clone(), ed25519_ix(ed25519_executor, message)], vec![executor]).is_ok());
+
+        // Neither kind alone does
+        let not_meet_threshold = Err(FreeTunnelError::NotMeetThreshold.into());
+        assert_eq!(check(&[secp256k1_ix.clone()], vec![executor]), not_meet_threshold);
+        assert_eq!(check(&[ed25519_ix(ed25519_executor, message)], vec![]), not_meet_threshold);
+
+        // A registered ed25519 executor counts once however often it signs, and only over this message
+        let twice = [ed25519_ix(ed25519_executor, message), ed25519_ix(ed25519_executor, message)];
+        assert_eq!(check(&twice, vec![]), not_meet_threshold);
+        assert_eq!(check(&[secp256k1_ix.clone(), ed25519_ix(ed25519_executor, b"other")], vec![executor]), not_meet_threshold);
+        assert_eq!(check(&[secp256k1_ix.clone(), ed25519_ix(unregistered, message)], vec![executor]), not_meet_threshold);
+
+        // Without the instructions sysvar, ed25519 executors cannot sign at all
+        assert_eq!(
+            SignatureUtils::assert_multisig_valid(&executors, None, message, &vec![signature], &vec![executor]),
+            not_meet_threshold,
+        );
+    }
+
+    #[test]
+    fn test_multisig_rejects_forged_instructions_sysvar() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let message = b"stupid";
+        let (executor, signature, raw, recovery_id) = known_executor_signature();
+        let mut executors = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&executors_info(vec![executor], 1)));
+        let executors = executors.to_account_info();
+        let instructions = [(solana_sdk_ids::secp256k1_program::ID, build_secp256k1_ix_data(executor, raw, recovery_id, message, 0))];
+
+        let mut sysvar_instructions = instructions_sysvar(&instructions);
+        let sysvar_instructions = sysvar_instructions.to_account_info();
+        assert!(SignatureUtils::assert_multisig_valid(&executors, Some(&sysvar_instructions), message, &vec![signature], &vec![executor]).is_ok());
+
+        // The same data in any other account is not trusted
+        let mut forged = FixtureAccount::new(Pubkey::new_unique()).data(sysvar_instructions.data.borrow().to_vec());
+        assert_eq!(
+            SignatureUtils::assert_multisig_valid(&executors, Some(&forged.to_account_info()), message, &vec![signature], &vec![executor]),
+            Err(ProgramError::UnsupportedSysvar),
+        );
+    }
+
+    #[test]
+    fn test_multisig_rejects_mismatched_secp256k1_instructions() {
+        runtime_stubs::set_clock(1_700_000_000);
+        let message = b"stupid";
+        let (executor, signature, raw, recovery_id) = known_executor_signature();
+        let mut executors = FixtureAccount::new(Pubkey::new_unique()).data(account_data(&executors_info(vec![executor], 1)));
+        let executors = executors.to_account_info();
+        let check = |instructions: &[(Pubkey, Vec<u8>)]| {
+            let mut sysvar_instructions = instructions_sysvar(instructions);
+            SignatureUtils::assert_multisig_valid(&executors, Some(&sysvar_instructions.to_account_info()), message, &vec![signature], &vec![executor])
+        };
+        let secp256k1_ix = |message: &[u8], ix_index: u8| {
+            (solana_sdk_ids::secp256k1_program::ID, build_secp256k1_ix_data(executor, raw, recovery_id, message, ix_index))
+        };
+        let invalid_signature = Err(FreeTunnelError::InvalidSignature.into());
+
+        // Verified over another message
+        assert_eq!(check(&[secp256k1_ix(b"other", 0)]), invalid_signature);
+
+        // Offsets pointing at another instruction, whose data the runtime would verify instead
+        let other_ix = (Pubkey::new_unique(), vec![0; 200]);
+        assert_eq!(check(&[other_ix.clone(), secp256k1_ix(message, 0)]), invalid_signature);
+        assert_eq!(check(&[secp256k1_ix(message, 1), other_ix.clone()]), invalid_signature);
+        assert!(check(&[other_ix, secp256k1_ix(message, 1)]).is_ok());
+    }
+
+    fn basic_storage(proposers_len: usize, max_proposers: u8) -> BasicStorage {
+        let proposers = (0..proposers_len).map(|_| Pubkey::new_unique()).collect();
+        BasicStorage { max_proposers, ..test_fixtures::basic_storage(Pubkey::default(), proposers) }
+    }
+
+    fn basic_storage_account_data(proposers_len: usize, max_proposers: u8) -> Vec<u8> {
+        account_data(&basic_storage(proposers_len, max_proposers))
+    }
+
+    #[test]
+    fn test_read_basic_storage_legacy_layout() {
+        let (admin, proposer, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let data = test_fixtures::legacy_basic_storage_data(admin, vec![proposer], 7, mint);
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(data);
+        let storage = DataAccountUtils::read_basic_storage(&storage.to_account_info()).unwrap();
+
+        assert_eq!((storage.mint_or_lock, storage.admin, storage.proposers), (false, admin, vec![proposer]));
+        assert_eq!((storage.tokens.get(7), storage.locked_balance.get(7)), (Some(&mint), Some(&1_000)));
+        // Appended fields read as their defaults, and the token has nothing pending
+        assert_eq!(storage.max_proposers as usize, Constants::MAX_PROPOSERS);
+        assert_eq!((storage.rotation_min_delay, storage.rotation_max_delay), (Constants::EXECUTORS_ROTATION_MIN_DELAY, Constants::EXECUTORS_ROTATION_MAX_DELAY));
+        assert_eq!((storage.pending_proposals.get(7), storage.pending_deposits.get(7)), (Some(&0), Some(&0)));
+        assert_eq!((storage.token_programs.len(), storage.relayers.len(), storage.event_seq, storage.open_lock), (0, 0, 0, false));
+
+        // Data ending inside a field is still rejected
+        let mut truncated = account_data(&test_fixtures::basic_storage(admin, vec![]));
+        let body_length = truncated.len() - Constants::SIZE_LENGTH - 5; // drops `open_lock` and half of `renounce_after`
+        truncated.truncate(Constants::SIZE_LENGTH + body_length);
+        truncated[..4].copy_from_slice(&(body_length as u32).to_le_bytes());
+        let mut storage = FixtureAccount::new(Pubkey::new_unique()).data(truncated);
+        assert_eq!(DataAccountUtils::read_basic_storage(&storage.to_account_info()).unwrap_err(), ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_read_basic_storage_rejects_oversized_proposers() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let max = Constants::MAX_PROPOSERS;
+
+        let mut lamports = 0;
+        let mut data = basic_storage_account_data(max, max as u8);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_basic_storage(&account).unwrap().proposers.len(), max);
+
+        let mut lamports = 0;
+        let mut data = basic_storage_account_data(max + 1, max as u8);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_basic_storage(&account).unwrap_err(), ProgramError::InvalidAccountData);
+
+        // A raised `max_proposers` extends the limit
+        let mut lamports = 0;
+        let mut data = basic_storage_account_data(max + 1, max as u8 + 1);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_basic_storage(&account).unwrap().proposers.len(), max + 1);
+    }
+
+    #[test]
+    fn test_basic_storage_peer_token_addresses_round_trip() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut basic_storage = basic_storage(1, Constants::MAX_PROPOSERS as u8);
+        for token_index in 1..=Constants::MAX_TOKENS as u8 {
+            basic_storage.peer_token_addresses.insert(token_index, [token_index; 20]).unwrap();
+        }
+
+        let mut lamports = 0;
+        let mut data = vec![0u8; Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE];
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        DataAccountUtils::write_account_data(&account, basic_storage).unwrap();
+
+        let read = DataAccountUtils::read_basic_storage(&account).unwrap();
+        assert_eq!(read.peer_token_addresses.len(), Constants::MAX_TOKENS);
+        assert_eq!(read.peer_token_addresses[3], [3; 20]);
+        assert_eq!(read.peer_token_addresses.get(0), None);
+    }
+
+    #[test]
+    fn test_read_proposal_inner_legacy_and_extended() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (recipient, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Legacy proposals hold only `inner`
+        let mut lamports = 0;
+        let mut data = account_data(&recipient);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), recipient);
+        assert!(DataAccountUtils::read_account_data::<ProposedMint>(&account).is_err());
+
+        let mut lamports = 0;
+        let mut data = account_data(&ProposedMint { inner: recipient, proposer, rent_from_vault: false, pinned_exe_index: None });
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), recipient);
+        assert_eq!(DataAccountUtils::read_account_data::<ProposedMint>(&account).unwrap().proposer, proposer);
+
+        let mut lamports = 0;
+        let mut data = account_data(&ProposedUnlock { inner: recipient, proposer, rent_from_vault: false, pinned_exe_index: None });
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), recipient);
+        assert_eq!(DataAccountUtils::read_account_data::<ProposedUnlock>(&account).unwrap().proposer, proposer);
+
+        let mut lamports = 0;
+        let mut data = account_data(&[0u8; 16]);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap_err(), ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_proposal_header_offsets() {
+        // Indexer memcmp filters depend on these exact values
+        assert_eq!(Constants::PROPOSAL_OFFSET_DISCRIMINATOR, 0);
+        assert_eq!(Constants::PROPOSAL_OFFSET_STATUS, 1);
+        assert_eq!(Constants::PROPOSAL_OFFSET_TOKEN_INDEX, 2);
+        assert_eq!(Constants::PROPOSAL_OFFSET_CREATED_TIME, 3);
+        assert_eq!(Constants::PROPOSAL_OFFSET_PARTY, 11);
+        assert_eq!(Constants::PROPOSAL_OFFSET_AMOUNT, 43);
+        assert_eq!(Constants::SIZE_PROPOSAL_HEADER, 51);
+
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (recipient, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let header = ProposalHeader {
+            discriminator: ProposalKind::Mint.discriminator(),
+            status: Constants::PROPOSAL_STATUS_PROPOSED,
+            token_index: 7,
+            created_time: 0x0102030405,
+            party: recipient,
+            amount: 1_000_000,
+        };
+        let content = borsh::to_vec(&ProposedMint { inner: recipient, proposer, rent_from_vault: false, pinned_exe_index: None }).unwrap();
+
+        let mut lamports = 0;
+        let mut data = vec![0u8; Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + content.len()];
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        DataAccountUtils::write_proposal_data(&account, &header, &content).unwrap();
+        {
+            let data = account.data.borrow();
+            assert_eq!(data[Constants::PROPOSAL_OFFSET_DISCRIMINATOR], 1);
+            assert_eq!(data[Constants::PROPOSAL_OFFSET_STATUS], Constants::PROPOSAL_STATUS_PROPOSED);
+            assert_eq!(data[Constants::PROPOSAL_OFFSET_TOKEN_INDEX], 7);
+            assert_eq!(data[3..11], 0x0102030405u64.to_le_bytes());
+            assert_eq!(data[11..43], recipient.to_bytes());
+            assert_eq!(data[43..51], 1_000_000u64.to_le_bytes());
+            assert_eq!(data[51..55], (content.len() as u32).to_le_bytes());
+        }
+
+        assert_eq!(DataAccountUtils::read_proposal_header(&account).unwrap(), Some(header));
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), recipient);
+        assert_eq!(DataAccountUtils::read_proposal_data::<ProposedMint>(&account).unwrap().proposer, proposer);
+
+        DataAccountUtils::mark_proposal_executed(&account).unwrap();
+        assert_eq!(account.data.borrow()[Constants::PROPOSAL_OFFSET_STATUS], Constants::PROPOSAL_STATUS_EXECUTED);
+        assert_eq!(account.data.borrow()[43..51], 1_000_000u64.to_le_bytes());
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), Constants::EXECUTED_PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_proposal_pinned_exe_index() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (recipient, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let header = ProposalHeader {
+            discriminator: ProposalKind::Mint.discriminator(),
+            status: Constants::PROPOSAL_STATUS_PROPOSED,
+            token_index: 7,
+            created_time: 0,
+            party: recipient,
+            amount: 1_000_000,
+        };
+        let proposal_account = |content: Vec<u8>| {
+            let mut data = vec![0u8; Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + content.len()];
+            {
+                let mut lamports = 0;
+                let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+                DataAccountUtils::write_proposal_data(&account, &header, &content).unwrap();
+            }
+            data
+        };
+
+        let mut lamports = 0;
+        let mut data = proposal_account(borsh::to_vec(&ProposedMint { inner: recipient, proposer, rent_from_vault: false, pinned_exe_index: Some(3) }).unwrap());
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_pinned_exe_index(&account).unwrap(), Some(3));
+        assert_eq!(DataAccountUtils::read_proposal_data::<ProposedMint>(&account).unwrap().pinned_exe_index, Some(3));
+
+        // Proposals written before the pin was added end right after `rent_from_vault`
+        let mut lamports = 0;
+        let mut data = proposal_account(borsh::to_vec(&(recipient, proposer, true)).unwrap());
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_pinned_exe_index(&account).unwrap(), None);
+        let legacy = DataAccountUtils::read_proposal_data::<ProposedMint>(&account).unwrap();
+        assert_eq!((legacy.proposer, legacy.rent_from_vault, legacy.pinned_exe_index), (proposer, true, None));
+
+        // A missing proposal is unpinned
+        let mut lamports = 0;
+        let mut data = vec![];
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_pinned_exe_index(&account).unwrap(), None);
+    }
+
+    #[test]
+    fn test_legacy_proposal_without_header() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (recipient, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut lamports = 0;
+        let mut data = account_data(&ProposedMint { inner: recipient, proposer, rent_from_vault: false, pinned_exe_index: None });
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(DataAccountUtils::read_proposal_header(&account).unwrap(), None);
+        assert_eq!(DataAccountUtils::read_proposal_data::<ProposedMint>(&account).unwrap().proposer, proposer);
+
+        // Legacy single-pubkey proposals can still be marked executed in place
+        let mut lamports = 0;
+        let mut data = account_data(&recipient);
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        DataAccountUtils::mark_proposal_executed(&account).unwrap();
+        assert_eq!(DataAccountUtils::read_proposal_inner(&account).unwrap(), Constants::EXECUTED_PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_create_or_update_existing_data_account() {
+        let program_id = Pubkey::new_unique();
+        let (key, bump_seed) = Pubkey::find_program_address(&[b"prefix", b"phrase"], &program_id);
+        let system_key = Pubkey::default();
+        let (mut system_lamports, mut system_data) = (0, vec![]);
+        let system_program = AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0);
+        let payer_key = Pubkey::new_unique();
+        let (mut payer_lamports, mut payer_data) = (1_000, vec![]);
+        let payer = AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &system_key, false, 0);
+
+        // An existing account is rewritten in place, without touching any lamports
+        let mut lamports = 500;
+        let mut data = account_data(&1u64);
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+        DataAccountUtils::create_or_update_data_account(
+            &program_id, &system_program, &payer, &account, b"prefix", b"phrase", bump_seed, 12, 2u64,
+        ).unwrap();
+        assert_eq!(DataAccountUtils::read_account_data::<u64>(&account).unwrap(), 2);
+        assert_eq!((account.lamports(), payer.lamports()), (500, 1_000));
+
+        // Seeds must still match the account
+        assert_eq!(
+            DataAccountUtils::create_or_update_data_account(
+                &program_id, &system_program, &payer, &account, b"prefix", b"other", bump_seed, 12, 3u64,
+            ).unwrap_err(),
+            ProgramError::Custom(DataAccountError::PdaAccountMismatch as u32)
+        );
+
+        // And be owned by the program
+        let mut lamports = 500;
+        let mut data = account_data(&1u64);
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &system_key, false, 0);
+        assert_eq!(
+            DataAccountUtils::create_or_update_data_account(
+                &program_id, &system_program, &payer, &account, b"prefix", b"phrase", bump_seed, 12, 3u64,
+            ).unwrap_err(),
+            ProgramError::Custom(DataAccountError::PdaAccountNotOwned as u32)
+        );
+    }
+
+    #[test]
+    fn test_assert_account_match_and_ownership() {
+        let program_id = Pubkey::new_unique();
+        let mut storage = FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"");
+        let storage = storage.to_account_info();
+        let (_, bump_seed) = Pubkey::find_program_address(&[Constants::BASIC_STORAGE], &program_id);
+        assert_eq!(DataAccountUtils::assert_account_match(&program_id, &storage, Constants::BASIC_STORAGE, b"").unwrap(), bump_seed);
+        assert!(DataAccountUtils::assert_owned_by_program(&program_id, &storage).is_ok());
+        assert_eq!(
+            DataAccountUtils::assert_account_match(&program_id, &storage, Constants::CONTRACT_SIGNER, b"").unwrap_err(),
+            ProgramError::Custom(DataAccountError::PdaAccountMismatch as u32)
+        );
+
+        let mut foreign = FixtureAccount::new(*storage.key);
+        assert_eq!(
+            DataAccountUtils::assert_owned_by_program(&program_id, &foreign.to_account_info()).unwrap_err(),
+            ProgramError::Custom(DataAccountError::PdaAccountNotOwned as u32)
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_basic_storage_with_fixtures() {
+        let program_id = Pubkey::new_unique();
+        let (admin, proposer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut storage = FixtureAccount::pda(&program_id, Constants::BASIC_STORAGE, b"")
+            .data(vec![0u8; Constants::SIZE_LENGTH + Constants::SIZE_BASIC_STORAGE]);
+        let storage = storage.to_account_info();
+
+        let mut basic_storage = test_fixtures::basic_storage(admin, vec![proposer]);
+        basic_storage.locked_balance.insert(1, 500).unwrap();
+        DataAccountUtils::write_account_data(&storage, basic_storage).unwrap();
+
+        let read = DataAccountUtils::read_basic_storage(&storage).unwrap();
+        assert_eq!(read.locked_balance[1], 500);
+        assert_eq!((read.admin, read.proposers), (admin, vec![proposer]));
+
+        // Content larger than the account is rejected
+        let mut small = FixtureAccount::new(Pubkey::new_unique()).owner(program_id).writable().data(vec![0u8; 16]);
+        assert_eq!(
+            DataAccountUtils::write_account_data(&small.to_account_info(), test_fixtures::basic_storage(admin, vec![])).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    #[test]
+    fn test_close_account_moves_lamports_and_reassigns() {
+        let program_id = Pubkey::new_unique();
+        let req_id = [7u8; 32];
+        let mut proposal = FixtureAccount::pda(&program_id, Constants::PREFIX_MINT, &req_id)
+            .lamports(1_000).data(account_data(&Pubkey::new_unique()));
+        let proposal = proposal.to_account_info();
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).writable().lamports(500);
+        let refund = refund.to_account_info();
+
+        DataAccountUtils::close_account(&program_id, &proposal, &refund, Constants::PREFIX_MINT, &req_id, false).unwrap();
+        assert_eq!((proposal.lamports(), refund.lamports()), (0, 1_500));
+        assert_eq!(proposal.data_len(), 0);
+        assert_eq!(proposal.owner, &solana_sdk_ids::system_program::ID);
+    }
+
+    #[test]
+    fn test_close_account_rejections() {
+        let program_id = Pubkey::new_unique();
+        let req_id = [7u8; 32];
+        let close = |data_account: &mut FixtureAccount, refund: &mut FixtureAccount, seeds: (&[u8], &[u8]), force: bool| {
+            let (data_account, refund) = (data_account.to_account_info(), refund.to_account_info());
+            let result = DataAccountUtils::close_account(&program_id, &data_account, &refund, seeds.0, seeds.1, force);
+            (result, data_account.lamports(), data_account.data_len())
+        };
+        let proposal = || FixtureAccount::pda(&program_id, Constants::PREFIX_MINT, &req_id)
+            .lamports(1_000).data(account_data(&Pubkey::new_unique()));
+        let mint_seeds: (&[u8], &[u8]) = (Constants::PREFIX_MINT, &req_id);
+        let data_len = account_data(&Pubkey::default()).len();
+
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).lamports(500);
+        assert_eq!(
+            close(&mut proposal(), &mut refund, mint_seeds, false),
+            (Err(FreeTunnelError::RefundAccountNotWritable.into()), 1_000, data_len)
+        );
+
+        // Adding to the refund balance must not overflow, and nothing moves when it would
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).writable().lamports(u64::MAX);
+        assert_eq!(
+            close(&mut proposal(), &mut refund, mint_seeds, false),
+            (Err(FreeTunnelError::ArithmeticOverflow.into()), 1_000, data_len)
+        );
+
+        // The account cannot refund itself
+        let mut data_account = proposal();
+        let mut refund = FixtureAccount::new(data_account.key()).writable();
+        assert_eq!(
+            close(&mut data_account, &mut refund, mint_seeds, false),
+            (Err(FreeTunnelError::InvalidRefundAccount.into()), 1_000, data_len)
+        );
+
+        // The account must be the PDA of the expected seeds
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).writable();
+        assert_eq!(
+            close(&mut proposal(), &mut refund, (Constants::PREFIX_BURN, &req_id), false),
+            (Err(ProgramError::Custom(DataAccountError::PdaAccountMismatch as u32)), 1_000, data_len)
+        );
+        assert_eq!(
+            close(&mut proposal(), &mut refund, (Constants::PREFIX_MINT, &[8u8; 32]), false),
+            (Err(ProgramError::Custom(DataAccountError::PdaAccountMismatch as u32)), 1_000, data_len)
+        );
+
+        // Basic storage and executors are only closed when forced
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).writable().lamports(500);
+        let storage_seeds: (&[u8], &[u8]) = (Constants::BASIC_STORAGE, b"");
+        let storage_data = account_data(&test_fixtures::basic_storage(Pubkey::new_unique(), vec![]));
+        let storage = || FixtureAccount::pda(&program_id, storage_seeds.0, storage_seeds.1)
+            .lamports(1_000).data(storage_data.clone());
+        assert_eq!(
+            close(&mut storage(), &mut refund, storage_seeds, false),
+            (Err(FreeTunnelError::CriticalAccountNotClosable.into()), 1_000, storage_data.len())
+        );
+        assert_eq!(close(&mut storage(), &mut refund, storage_seeds, true), (Ok(()), 0, 0));
+
+        let exe_index = 0u64.to_le_bytes();
+        let mut executors = FixtureAccount::pda(&program_id, Constants::PREFIX_EXECUTORS, &exe_index)
+            .lamports(1_000).data(executors_account_data(3));
+        assert_eq!(
+            close(&mut executors, &mut refund, (Constants::PREFIX_EXECUTORS, &exe_index), false).0.unwrap_err(),
+            FreeTunnelError::CriticalAccountNotClosable.into()
+        );
+    }
+
+    #[test]
+    fn test_close_account_refund_rent_exemption() {
+        let minimum_balance = Rent::default().minimum_balance(0);
+
+        // A zero-lamport refund target must end up rent exempt
+        assert_eq!(
+            DataAccountUtils::assert_refund_rent_exempt(1_000, minimum_balance).unwrap_err(),
+            FreeTunnelError::RefundBelowRentExempt.into()
+        );
+        assert_eq!(
+            DataAccountUtils::assert_refund_rent_exempt(minimum_balance - 1, minimum_balance).unwrap_err(),
+            FreeTunnelError::RefundBelowRentExempt.into()
+        );
+        assert!(DataAccountUtils::assert_refund_rent_exempt(minimum_balance, minimum_balance).is_ok());
+
+        // An already funded refund account is not checked against the rent
+        let program_id = Pubkey::new_unique();
+        let req_id = [7u8; 32];
+        let mut proposal = FixtureAccount::pda(&program_id, Constants::PREFIX_MINT, &req_id)
+            .lamports(1_000).data(account_data(&Pubkey::new_unique()));
+        let proposal = proposal.to_account_info();
+        let mut refund = FixtureAccount::new(Pubkey::new_unique()).writable().lamports(1);
+        let refund = refund.to_account_info();
+        DataAccountUtils::close_account(&program_id, &proposal, &refund, Constants::PREFIX_MINT, &req_id, false).unwrap();
+        assert_eq!((proposal.lamports(), refund.lamports()), (0, 1_001));
+    }
+
+    #[test]
+    fn test_is_executor_sorted_and_legacy_unsorted() {
+        let (a, b, c, d) = ([0x11; 20], [0x22; 20], [0x33; 20], [0x44; 20]);
+
+        let sorted = vec![a, b, c];
+        assert!(SignatureUtils::is_executor(&sorted, &b, true));
+        assert!(!SignatureUtils::is_executor(&sorted, &d, true));
+
+        // Accounts written before executors were stored sorted fall back to a linear scan
+        let legacy = vec![c, a, b];
+        assert!(SignatureUtils::is_executor(&legacy, &a, false));
+        assert!(SignatureUtils::is_executor(&legacy, &c, false));
+        assert!(!SignatureUtils::is_executor(&legacy, &d, false));
+    }
+
+    #[test]
+    fn test_submitted_executors_must_be_sorted() {
+        use crate::error::FreeTunnelError;
+        let (a, b, c, d) = ([0x11; 20], [0x22; 20], [0x33; 20], [0x44; 20]);
+
+        assert!(SignatureUtils::assert_executors_sorted(&[]).is_ok());
+        assert!(SignatureUtils::assert_executors_sorted(&[a, c, d]).is_ok());
+        assert_eq!(
+            SignatureUtils::assert_executors_sorted(&[a, b, b]).unwrap_err(),
+            FreeTunnelError::DuplicatedExecutors.into(),
+        );
+        assert_eq!(
+            SignatureUtils::assert_executors_sorted(&[a, c, b]).unwrap_err(),
+            FreeTunnelError::ExecutorsNotSorted.into(),
+        );
+
+        let stored = vec![a, b, c];
+        assert!(SignatureUtils::all_executors_registered(&stored, &[a, c]));
+        assert!(SignatureUtils::all_executors_registered(&stored, &[a, b, c]));
+        assert!(!SignatureUtils::all_executors_registered(&stored, &[b, d]));
+
+        // Stored lists that predate sorting are still checked correctly
+        let legacy = vec![c, a, b];
+        assert!(SignatureUtils::all_executors_registered(&legacy, &[a, c]));
+        assert!(!SignatureUtils::all_executors_registered(&legacy, &[a, d]));
+    }
+
+    #[test]
+    fn test_verify_executor_signature() {
+        use crate::error::FreeTunnelError;
+        let message = b"stupid";
+        let signature: [u8; 64] = hex::decode("6fd862958c41d532022e404a809e92ec699bd0739f8d782ca752b07ff978f341f43065a96dc53a21b4eb4ce96a84a7c4103e3485b0c87d868df545fcce0f3983")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let executor: [u8; 20] = hex::decode("2eF8a51F8fF129DBb874A0efB021702F59C1b211")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(SignatureUtils::verify_executor_signature(message, signature, executor), Ok(()));
+        assert_eq!(
+            SignatureUtils::verify_executor_signature(b"other", signature, executor),
+            Err(FreeTunnelError::InvalidSignature),
+        );
+        assert_eq!(
+            SignatureUtils::verify_executor_signature(message, signature, Constants::ETH_ZERO_ADDRESS),
+            Err(FreeTunnelError::SignerCannotBeZeroAddress),
+        );
     }
 }
@@ -1,29 +1,36 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use std::{cmp::Ordering, collections::HashSet};
+use std::{cmp::Ordering, collections::HashSet, hash::Hash};
 
 use solana_program::{
     account_info::AccountInfo,
     clock::Clock,
     entrypoint::ProgramResult,
     keccak,
-    program::invoke_signed,
+    msg,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
     secp256k1_recover::secp256k1_recover,
     sysvar::{rent::Rent, Sysvar},
 };
+use solana_instructions_sysvar::load_instruction_at_checked;
 use solana_sdk_ids;
-use solana_system_interface::instruction::create_account;
+use solana_system_interface::instruction::{create_account, transfer};
 
 use crate::{
     constants::{Constants, EthAddress},
     error::{DataAccountError, FreeTunnelError},
-    state::ExecutorsInfo,
+    state::{BasicStorage, ExecutorsInfo, ProposalHeader, ProposalKind},
 };
 
 pub struct SignatureUtils;
 pub struct DataAccountUtils;
 
+/// `(eth_address, compact_signature, message)` of a `secp256k1_program` entry
+pub(crate) type VerifiedSecp256k1 = (EthAddress, [u8; 64], Vec<u8>);
+/// `(pubkey, message)` of an `ed25519_program` entry
+pub(crate) type VerifiedEd25519 = (Pubkey, Vec<u8>);
+
 impl SignatureUtils {
     pub(crate) fn log10(n: u64) -> u64 {
         if n == 0 {
@@ -43,23 +50,24 @@ impl SignatureUtils {
         result
     }
 
-    pub(crate) fn cmp_addr_list(list1: &Vec<EthAddress>, list2: &Vec<EthAddress>) -> bool {
-        match list1.len().cmp(&list2.len()) {
-            Ordering::Greater => true,
-            Ordering::Less => false,
-            Ordering::Equal => list1
-                .iter()
-                .zip(list2.iter())
-                .find_map(|(a, b)| match a.cmp(b) {
-                    Ordering::Greater => Some(true),
-                    Ordering::Less => Some(false),
-                    Ordering::Equal => None,
-                })
-                .unwrap_or(false),
+    pub(crate) fn join_pubkey_list(pubkeys: &Vec<Pubkey>) -> Vec<u8> {
+        let mut result = Vec::new();
+        for pubkey in pubkeys {
+            result.extend_from_slice(b"0x");
+            result.extend_from_slice(hex::encode(pubkey.to_bytes()).as_bytes());
+            result.extend_from_slice(b"\n");
         }
+        result
+    }
+
+    /// Whether `new_executors` keeps every executor of `old_executors`, in any order.
+    /// A pending executors group may only be overwritten by such a set, for either signature scheme
+    pub(crate) fn is_superset_of<T: Ord>(new_executors: &[T], old_executors: &[T]) -> bool {
+        let sorted = new_executors.is_sorted();
+        old_executors.iter().all(|executor| Self::is_executor(new_executors, executor, sorted))
     }
 
-    pub(crate) fn assert_executors_not_duplicated(executors: &[EthAddress]) -> ProgramResult {
+    pub(crate) fn assert_executors_not_duplicated<T: Eq + Hash>(executors: &[T]) -> ProgramResult {
         let mut seen = HashSet::new();
         match executors.iter().all(|addr| seen.insert(addr)) {
             true => Ok(()),
@@ -67,77 +75,129 @@ impl SignatureUtils {
         }
     }
 
-    pub(crate) fn eth_address_from_pubkey(pk: [u8; 64]) -> EthAddress {
+    pub fn eth_address_from_pubkey(pk: [u8; 64]) -> EthAddress {
         let hash = keccak::hash(&pk).to_bytes();
         let mut address = [0u8; 20];
         address.copy_from_slice(&hash[12..32]);
         address
     }
 
-    pub(crate) fn recover_eth_address(message: &[u8], mut signature: [u8; 64]) -> EthAddress {
+    /// Recovers the signer of a compact signature, whose recovery id is the highest bit of byte 32.
+    /// Returns `None` if recovery fails
+    pub fn recover_eth_address(message: &[u8], mut signature: [u8; 64]) -> Option<EthAddress> {
         let digest = keccak::hash(&message).to_bytes();
 
         let first_bit_of_s = signature.get_mut(32).unwrap();
         let recovery_id = *first_bit_of_s >> 7;
         *first_bit_of_s = *first_bit_of_s & 0x7f;
 
-        let pubkey = secp256k1_recover(&digest, recovery_id, &signature);
-        match pubkey {
-            Ok(eth_pubkey) => Self::eth_address_from_pubkey(eth_pubkey.to_bytes()),
-            Err(_error) => [0; 20],
-        }
+        secp256k1_recover(&digest, recovery_id, &signature)
+            .ok()
+            .map(|eth_pubkey| Self::eth_address_from_pubkey(eth_pubkey.to_bytes()))
     }
 
-    fn assert_signature_valid(
+    /// Same check as on-chain execution, without any account access, so relayers can
+    /// pre-validate executor signatures off-chain
+    pub fn verify_executor_signature(
         message: &[u8],
         signature: [u8; 64],
-        eth_signer: EthAddress,
-    ) -> ProgramResult {
-        match eth_signer == Constants::ETH_ZERO_ADDRESS {
-            true => Err(FreeTunnelError::SignerCannotBeZeroAddress.into()),
+        expected: EthAddress,
+    ) -> Result<(), FreeTunnelError> {
+        match expected == Constants::ETH_ZERO_ADDRESS {
+            true => Err(FreeTunnelError::SignerCannotBeZeroAddress),
             false => {
-                let recovered_eth_addr = Self::recover_eth_address(message, signature);
-                match recovered_eth_addr == eth_signer {
-                    true => Ok(()),
-                    false => Err(FreeTunnelError::InvalidSignature.into()),
+                match Self::recover_eth_address(message, signature) {
+                    Some(recovered_eth_addr) if recovered_eth_addr == expected => Ok(()),
+                    _ => Err(FreeTunnelError::InvalidSignature),
                 }
             }
         }
     }
 
+    pub fn assert_signature_valid(
+        message: &[u8],
+        signature: [u8; 64],
+        eth_signer: EthAddress,
+    ) -> ProgramResult {
+        Ok(Self::verify_executor_signature(message, signature, eth_signer)?)
+    }
+
+    /// Bit `i` is set when `signatures[i]` is a valid signature of `executors[i]` over `message`.
+    /// Callers bound `signatures` to 64.
+    pub(crate) fn signature_bitmask(
+        message: &[u8],
+        signatures: &Vec<[u8; 64]>,
+        executors: &Vec<EthAddress>,
+    ) -> u64 {
+        signatures
+            .iter()
+            .zip(executors.iter())
+            .enumerate()
+            .filter(|(_, (signature, executor))| Self::assert_signature_valid(message, **signature, **executor).is_ok())
+            .fold(0u64, |bitmask, (i, _)| bitmask | (1 << i))
+    }
+
+    /// Binary searches executors stored sorted, falling back to a linear scan for
+    /// accounts written before executors were kept sorted. Callers check `sorted` once per
+    /// list, not once per lookup
+    pub(crate) fn is_executor<T: Ord>(current_executors: &[T], executor: &T, sorted: bool) -> bool {
+        match sorted {
+            true => current_executors.binary_search(executor).is_ok(),
+            false => current_executors.contains(executor),
+        }
+    }
+
     fn assert_executors_valid(
         data_account_executors: &AccountInfo,
         executors: &Vec<EthAddress>,
+        ed25519_signers: &[Pubkey],
     ) -> ProgramResult {
         // Check executors threshold
         let ExecutorsInfo {
-            index: _,
+            index,
             threshold,
             active_since,
             inactive_after,
             executors: current_executors,
-        } = DataAccountUtils::read_account_data(data_account_executors)?;
-        if executors.len() < threshold as usize {
+            ed25519_executors: current_ed25519_executors,
+        } = DataAccountUtils::read_executors_info(data_account_executors)?;
+        // Each registered ed25519 executor counts at most once
+        let ed25519_count = current_ed25519_executors
+            .iter()
+            .filter(|e| ed25519_signers.contains(e))
+            .count();
+        if executors.len() + ed25519_count < threshold as usize {
+            msg!("NotMeetThreshold: exe_index={}, threshold={}, signers={}", index, threshold, executors.len() + ed25519_count);
             return Err(FreeTunnelError::NotMeetThreshold.into());
         }
 
         // Check timestamp for current index
         let now = Clock::get()?.unix_timestamp;
         if now <= (active_since as i64) {
+            msg!("ExecutorsNotYetActive: exe_index={}, active_since={}, now={}", index, active_since, now);
             return Err(FreeTunnelError::ExecutorsNotYetActive.into());
         }
 
         // Check timestamp for inactive_after
         if inactive_after != 0 && now >= (inactive_after as i64) {
+            msg!("ExecutorsOfNextIndexIsActive: exe_index={}, inactive_after={}, now={}", index, inactive_after, now);
             return Err(FreeTunnelError::ExecutorsOfNextIndexIsActive.into());
         }
 
         // Check executors index
-        for (i, executor) in executors.iter().enumerate() {
-            if executors[0..i].iter().any(|e| e == executor) {
-                return Err(FreeTunnelError::DuplicatedExecutors.into());
+        if cfg!(feature = "unsorted-executors") {
+            let current_sorted = current_executors.is_sorted();
+            for (i, executor) in executors.iter().enumerate() {
+                if executors[0..i].iter().any(|e| e == executor) {
+                    return Err(FreeTunnelError::DuplicatedExecutors.into());
+                }
+                if !Self::is_executor(&current_executors, executor, current_sorted) {
+                    return Err(FreeTunnelError::NonExecutors.into());
+                }
             }
-            if !current_executors.iter().any(|e| e == executor) {
+        } else {
+            Self::assert_executors_sorted(executors)?;
+            if !Self::all_executors_registered(&current_executors, executors) {
                 return Err(FreeTunnelError::NonExecutors.into());
             }
         }
@@ -145,8 +205,141 @@ impl SignatureUtils {
         Ok(())
     }
 
+    /// Submitted executors must be strictly increasing, which also rules out duplicates
+    pub(crate) fn assert_executors_sorted(executors: &[EthAddress]) -> ProgramResult {
+        for pair in executors.windows(2) {
+            match pair[0].cmp(&pair[1]) {
+                Ordering::Less => {}
+                Ordering::Equal => return Err(FreeTunnelError::DuplicatedExecutors.into()),
+                Ordering::Greater => return Err(FreeTunnelError::ExecutorsNotSorted.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks sorted `executors` against the stored list with a linear merge,
+    /// or per-executor lookups when the stored list predates sorting
+    pub(crate) fn all_executors_registered(current_executors: &[EthAddress], executors: &[EthAddress]) -> bool {
+        if !current_executors.is_sorted() {
+            return executors.iter().all(|executor| Self::is_executor(current_executors, executor, false));
+        }
+        let mut current = current_executors.iter();
+        executors.iter().all(|executor| current.any(|e| e == executor))
+    }
+
+    /// Parses the data of a native `secp256k1_program` instruction located at `ix_index`,
+    /// returning `(eth_address, compact_signature, message)` for each verified entry.
+    /// Offsets must point into the instruction itself, and the signature is converted
+    /// to the compact format used by `recover_eth_address`.
+    pub(crate) fn parse_secp256k1_instruction(
+        data: &[u8],
+        ix_index: u8,
+    ) -> Result<Vec<VerifiedSecp256k1>, ProgramError> {
+        let read_slice = |offset: usize, len: usize| -> Result<&[u8], ProgramError> {
+            data.get(offset..offset + len).ok_or(ProgramError::InvalidInstructionData)
+        };
+        let read_u16 = |offset: usize| -> Result<usize, ProgramError> {
+            Ok(u16::from_le_bytes(read_slice(offset, 2)?.try_into().unwrap()) as usize)
+        };
+
+        let count = *data.first().ok_or(ProgramError::InvalidInstructionData)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            // `SecpSignatureOffsets` is 11 bytes each, following the 1-byte count
+            let start = 1 + i * 11;
+            let offsets = read_slice(start, 11)?;
+            if offsets[2] != ix_index || offsets[5] != ix_index || offsets[10] != ix_index {
+                return Err(FreeTunnelError::InvalidSignature.into());
+            }
+            let signature_offset = read_u16(start)?;
+            let eth_address_offset = read_u16(start + 3)?;
+            let message_offset = read_u16(start + 6)?;
+            let message_size = read_u16(start + 8)?;
+
+            let raw_signature = read_slice(signature_offset, 65)?;
+            let recovery_id = raw_signature[64];
+            if recovery_id > 1 || raw_signature[32] & 0x80 != 0 {
+                return Err(FreeTunnelError::InvalidSignature.into());
+            }
+            let mut signature = [0u8; 64];
+            signature.copy_from_slice(&raw_signature[..64]);
+            signature[32] |= recovery_id << 7;
+
+            let eth_address: EthAddress = read_slice(eth_address_offset, 20)?.try_into().unwrap();
+            let message = read_slice(message_offset, message_size)?.to_vec();
+            entries.push((eth_address, signature, message));
+        }
+        Ok(entries)
+    }
+
+    /// Parses the data of a native `ed25519_program` instruction located at `ix_index`,
+    /// returning `(pubkey, message)` for each verified entry. Offsets must point into
+    /// the instruction itself (`u16::MAX` or its own index).
+    pub(crate) fn parse_ed25519_instruction(
+        data: &[u8],
+        ix_index: u16,
+    ) -> Result<Vec<VerifiedEd25519>, ProgramError> {
+        let read_slice = |offset: usize, len: usize| -> Result<&[u8], ProgramError> {
+            data.get(offset..offset + len).ok_or(ProgramError::InvalidInstructionData)
+        };
+        let read_u16 = |offset: usize| -> Result<u16, ProgramError> {
+            Ok(u16::from_le_bytes(read_slice(offset, 2)?.try_into().unwrap()))
+        };
+
+        let count = *data.first().ok_or(ProgramError::InvalidInstructionData)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            // `Ed25519SignatureOffsets` is 14 bytes each, following the count and a padding byte
+            let start = 2 + i * 14;
+            for index_offset in [2, 6, 12] {
+                let index = read_u16(start + index_offset)?;
+                if index != u16::MAX && index != ix_index {
+                    return Err(FreeTunnelError::InvalidSignature.into());
+                }
+            }
+            let pubkey_offset = read_u16(start + 4)? as usize;
+            let message_offset = read_u16(start + 8)? as usize;
+            let message_size = read_u16(start + 10)? as usize;
+
+            let pubkey = Pubkey::new_from_array(read_slice(pubkey_offset, 32)?.try_into().unwrap());
+            let message = read_slice(message_offset, message_size)?.to_vec();
+            entries.push((pubkey, message));
+        }
+        Ok(entries)
+    }
+
+    /// Collects every signature already verified by `secp256k1_program` and `ed25519_program`
+    /// instructions in the current transaction, read through the instructions sysvar.
+    fn load_verified_signatures(
+        sysvar_instructions: &AccountInfo,
+    ) -> Result<(Vec<VerifiedSecp256k1>, Vec<VerifiedEd25519>), ProgramError> {
+        let mut verified_secp256k1 = Vec::new();
+        let mut verified_ed25519 = Vec::new();
+        let mut index = 0;
+        loop {
+            let ix = match load_instruction_at_checked(index, sysvar_instructions) {
+                Ok(ix) => ix,
+                Err(ProgramError::InvalidArgument) => break, // out of bounds
+                Err(e) => return Err(e),
+            };
+            if ix.program_id == solana_sdk_ids::secp256k1_program::ID && index <= u8::MAX as usize {
+                verified_secp256k1.extend(Self::parse_secp256k1_instruction(&ix.data, index as u8)?);
+            } else if ix.program_id == solana_sdk_ids::ed25519_program::ID && index < u16::MAX as usize {
+                verified_ed25519.extend(Self::parse_ed25519_instruction(&ix.data, index as u16)?);
+            }
+            index += 1;
+        }
+        Ok((verified_secp256k1, verified_ed25519))
+    }
+
+    /// Checks the executors' signatures over `message`. When `sysvar_instructions` is provided,
+    /// signatures are matched against `secp256k1_program` instructions in the same transaction
+    /// instead of being recovered in-program, which saves the `secp256k1_recover` cost.
+    /// Registered ed25519 executors can only sign through `ed25519_program` instructions,
+    /// and count toward the same threshold.
     pub(crate) fn assert_multisig_valid(
         data_account_executors: &AccountInfo,
+        sysvar_instructions: Option<&AccountInfo>,
         message: &[u8],
         signatures: &Vec<[u8; 64]>,
         executors: &Vec<EthAddress>,
@@ -154,10 +347,75 @@ impl SignatureUtils {
         if signatures.len() != executors.len() {
             return Err(FreeTunnelError::ArrayLengthNotEqual.into());
         }
-        Self::assert_executors_valid(data_account_executors, executors)?;
+        let (verified_secp256k1, ed25519_signers) = match sysvar_instructions {
+            Some(sysvar_instructions) => {
+                let (verified_secp256k1, verified_ed25519) = Self::load_verified_signatures(sysvar_instructions)?;
+                let ed25519_signers: Vec<Pubkey> = verified_ed25519
+                    .into_iter()
+                    .filter(|(_, verified_message)| verified_message.as_slice() == message)
+                    .map(|(pubkey, _)| pubkey)
+                    .collect();
+                (Some(verified_secp256k1), ed25519_signers)
+            }
+            None => (None, Vec::new()),
+        };
+        Self::assert_executors_valid(data_account_executors, executors, &ed25519_signers)?;
 
-        for (i, executor) in executors.iter().enumerate() {
-            Self::assert_signature_valid(message, signatures[i], *executor)?;
+        match verified_secp256k1 {
+            Some(verified) => {
+                for (i, executor) in executors.iter().enumerate() {
+                    if *executor == Constants::ETH_ZERO_ADDRESS {
+                        return Err(FreeTunnelError::SignerCannotBeZeroAddress.into());
+                    }
+                    let found = verified.iter().any(|(eth_addr, signature, verified_message)| {
+                        eth_addr == executor && *signature == signatures[i] && verified_message.as_slice() == message
+                    });
+                    if !found {
+                        return Err(FreeTunnelError::InvalidSignature.into());
+                    }
+                }
+            }
+            None => {
+                for (i, executor) in executors.iter().enumerate() {
+                    Self::assert_signature_valid(message, signatures[i], *executor)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `assert_multisig_valid`, then checks that every executor of the group signed rather than
+    /// a threshold: each address, and each ed25519 executor through `sysvar_instructions`
+    pub(crate) fn assert_multisig_complete(
+        data_account_executors: &AccountInfo,
+        sysvar_instructions: Option<&AccountInfo>,
+        message: &[u8],
+        signatures: &Vec<[u8; 64]>,
+        executors: &Vec<EthAddress>,
+    ) -> ProgramResult {
+        Self::assert_multisig_valid(data_account_executors, sysvar_instructions, message, signatures, executors)?;
+        // The check above leaves `executors` registered and free of duplicates, so the lengths
+        // match only when every address signed
+        let executors_info = DataAccountUtils::read_executors_info(data_account_executors)?;
+        let ed25519_signers: Vec<Pubkey> = match sysvar_instructions {
+            Some(sysvar_instructions) => Self::load_verified_signatures(sysvar_instructions)?
+                .1
+                .into_iter()
+                .filter(|(_, verified_message)| verified_message.as_slice() == message)
+                .map(|(pubkey, _)| pubkey)
+                .collect(),
+            None => Vec::new(),
+        };
+        if executors.len() != executors_info.executors.len()
+            || !executors_info.ed25519_executors.iter().all(|executor| ed25519_signers.contains(executor))
+        {
+            msg!(
+                "DecommissionQuorumIncomplete: exe_index={}, signers={}, executors={}",
+                executors_info.index,
+                executors.len() + ed25519_signers.len(),
+                executors_info.executors.len() + executors_info.ed25519_executors.len()
+            );
+            return Err(FreeTunnelError::DecommissionQuorumIncomplete.into());
         }
         Ok(())
     }
@@ -171,7 +429,15 @@ impl DataAccountUtils {
     pub fn read_account_data<Data: BorshDeserialize>(
         data_account: &AccountInfo,
     ) -> Result<Data, ProgramError> {
-        let account_data = data_account.data.borrow();
+        Self::read_length_prefixed(&data_account.data.borrow())
+    }
+
+    fn read_length_prefixed<Data: BorshDeserialize>(account_data: &[u8]) -> Result<Data, ProgramError> {
+        Data::try_from_slice(Self::length_prefixed_body(account_data)?)
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    fn length_prefixed_body(account_data: &[u8]) -> Result<&[u8], ProgramError> {
         if account_data.len() < 4 {
             return Err(ProgramError::InvalidAccountData);
         }
@@ -179,19 +445,113 @@ impl DataAccountUtils {
         if data_len > account_data.len() - 4 {
             return Err(ProgramError::InvalidAccountData);
         }
-        Data::try_from_slice(&account_data[4..4 + data_len])
-            .map_err(|_| ProgramError::InvalidAccountData)
+        Ok(&account_data[4..4 + data_len])
+    }
+
+    /// Offset of the length-prefixed proposal struct: after the fixed `ProposalHeader`, or 0 for
+    /// proposals created before the header. Those start with the length prefix (32 or 64), which
+    /// is never a valid discriminator
+    fn proposal_body_offset(account_data: &[u8]) -> usize {
+        if account_data.len() >= Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH
+            && ProposalKind::from_discriminator(account_data[Constants::PROPOSAL_OFFSET_DISCRIMINATOR]).is_some()
+        {
+            Constants::SIZE_PROPOSAL_HEADER
+        } else { 0 }
+    }
+
+    /// Fixed-layout header of a proposal account, `None` for proposals created before it
+    pub fn read_proposal_header(data_account: &AccountInfo) -> Result<Option<ProposalHeader>, ProgramError> {
+        let account_data = data_account.data.borrow();
+        match Self::proposal_body_offset(&account_data) {
+            0 => Ok(None),
+            _ => ProposalHeader::try_from_slice(&account_data[..Constants::SIZE_PROPOSAL_HEADER])
+                .map(Some)
+                .map_err(|_| ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Reads the proposal struct following the header, if any
+    pub fn read_proposal_data<Data: BorshDeserialize>(data_account: &AccountInfo) -> Result<Data, ProgramError> {
+        let account_data = data_account.data.borrow();
+        let offset = Self::proposal_body_offset(&account_data);
+        Self::read_length_prefixed(&account_data[offset..]).or_else(|_| {
+            // Proposals created before `pinned_exe_index` was added end without it: read as `None`
+            let mut body = Self::length_prefixed_body(&account_data[offset..])?.to_vec();
+            body.push(0);
+            Data::try_from_slice(&body).map_err(|_| ProgramError::InvalidAccountData)
+        })
+    }
+
+    /// Reads `pinned_exe_index`, which every proposal struct has after the same 65 bytes of fields.
+    /// Missing proposals, and those created before it was added, read as unpinned
+    pub fn read_proposal_pinned_exe_index(data_account: &AccountInfo) -> Result<Option<u64>, ProgramError> {
+        const PINNED_EXE_INDEX_OFFSET: usize = 32 + 32 + 1;
+        if data_account.data_is_empty() {
+            return Ok(None);
+        }
+        let account_data = data_account.data.borrow();
+        let body = Self::length_prefixed_body(&account_data[Self::proposal_body_offset(&account_data)..])?;
+        if body.len() <= PINNED_EXE_INDEX_OFFSET {
+            return Ok(None);
+        }
+        Option::<u64>::try_from_slice(&body[PINNED_EXE_INDEX_OFFSET..]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Reads the leading pubkey every proposal struct starts with (`inner`), ignoring any fields
+    /// after it, so proposals written before those fields were added stay readable
+    pub fn read_proposal_inner(data_account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        let account_data = data_account.data.borrow();
+        let account_data = &account_data[Self::proposal_body_offset(&account_data)..];
+        if account_data.len() < 4 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let data_len = u32::from_le_bytes(account_data[..4].try_into().unwrap()) as usize;
+        if data_len < 32 || data_len > account_data.len() - 4 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Pubkey::new_from_array(account_data[4..36].try_into().unwrap()))
     }
 
+    /// Reads basic storage, rejecting data with more proposers than the configured limit
+    pub fn read_basic_storage(data_account_basic_storage: &AccountInfo) -> Result<BasicStorage, ProgramError> {
+        let basic_storage: BasicStorage = Self::read_account_data(data_account_basic_storage)?;
+        // `max_proposers` may exceed `MAX_PROPOSERS` once the account has been resized for it
+        let limit = Constants::MAX_PROPOSERS.max(basic_storage.max_proposers as usize);
+        if basic_storage.proposers.len() > limit {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(basic_storage)
+    }
+
+    /// Advances the event counter of basic storage, for events logged after the storage was written
+    pub fn next_event_seq(data_account_basic_storage: &AccountInfo) -> Result<u64, ProgramError> {
+        let mut basic_storage = Self::read_basic_storage(data_account_basic_storage)?;
+        let event_seq = basic_storage.next_event_seq()?;
+        Self::write_account_data(data_account_basic_storage, basic_storage)?;
+        Ok(event_seq)
+    }
+
+    /// Reads executors info, rejecting data whose executor lists exceed `MAX_EXECUTORS`
+    pub fn read_executors_info(data_account_executors: &AccountInfo) -> Result<ExecutorsInfo, ProgramError> {
+        let executors_info: ExecutorsInfo = Self::read_account_data(data_account_executors)?;
+        if executors_info.executors.len() > Constants::MAX_EXECUTORS
+            || executors_info.ed25519_executors.len() > Constants::MAX_EXECUTORS
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(executors_info)
+    }
+
+    /// Checks the data account is the PDA of `[prefix, phrase]`, returning its bump seed
     pub fn assert_account_match(
         program_id: &Pubkey,
         data_account: &AccountInfo,
         prefix: &[u8],
         phrase: &[u8],
-    ) -> ProgramResult {
-        let (pda_pubkey, _) = Pubkey::find_program_address(&[prefix, phrase], program_id);
+    ) -> Result<u8, ProgramError> {
+        let (pda_pubkey, bump_seed) = Pubkey::find_program_address(&[prefix, phrase], program_id);
         match data_account.key == &pda_pubkey {
-            true => Ok(()),
+            true => Ok(bump_seed),
             false => Err(DataAccountError::PdaAccountMismatch.into()),
         }
     }
@@ -211,6 +571,7 @@ impl DataAccountUtils {
     /// * `data_account` - Account to be created as a PDA
     /// * `prefix` - Seed prefix for PDA derivation
     /// * `phrase` - Additional seed for PDA derivation
+    /// * `bump_seed` - Bump seed returned by `assert_account_match` for this PDA
     /// * `data_length` - Size of the account data in bytes
     pub fn create_data_account<'a, Data: BorshSerialize>(
         program_id: &Pubkey,
@@ -219,13 +580,79 @@ impl DataAccountUtils {
         data_account: &AccountInfo<'a>,
         prefix: &[u8],
         phrase: &[u8],
+        bump_seed: u8,
         data_length: usize,
         content: Data,
     ) -> ProgramResult {
-        let (pda_pubkey, bump_seed) = Pubkey::find_program_address(&[prefix, phrase], program_id);
-        if pda_pubkey != *data_account.key {
-            Err(DataAccountError::PdaAccountMismatch.into())
-        } else if !data_account.is_writable {
+        Self::allocate_data_account(program_id, system_program, account_payer, data_account, prefix, phrase, bump_seed, data_length)?;
+        Self::write_account_data(data_account, content)
+    }
+
+    /// Same as `create_data_account`, but if the account already exists, skips the creation (and
+    /// its lamport transfer) and only rewrites `content`, which must fit the existing account
+    pub fn create_or_update_data_account<'a, Data: BorshSerialize>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account: &AccountInfo<'a>,
+        prefix: &[u8],
+        phrase: &[u8],
+        bump_seed: u8,
+        data_length: usize,
+        content: Data,
+    ) -> ProgramResult {
+        if data_account.data_is_empty() {
+            return Self::create_data_account(
+                program_id, system_program, account_payer, data_account, prefix, phrase, bump_seed, data_length, content,
+            );
+        }
+        let pda_pubkey = Pubkey::create_program_address(&[prefix, phrase, &[bump_seed]], program_id)
+            .map_err(|_| DataAccountError::PdaAccountMismatch)?;
+        if data_account.key != &pda_pubkey {
+            return Err(DataAccountError::PdaAccountMismatch.into());
+        }
+        Self::assert_owned_by_program(program_id, data_account)?;
+        if !data_account.is_writable {
+            return Err(DataAccountError::PdaAccountNotWritable.into());
+        }
+        Self::write_account_data(data_account, content)
+    }
+
+    /// Creates a proposal account of `kind` for `req_id`, sized for the header and `content`
+    pub fn create_proposal_account<'a, Data: BorshSerialize>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account: &AccountInfo<'a>,
+        kind: ProposalKind,
+        req_id_data: &[u8; 32],
+        bump_seed: u8,
+        header: ProposalHeader,
+        content: Data,
+    ) -> ProgramResult {
+        let content = borsh::to_vec(&content).map_err(|_| ProgramError::InvalidAccountData)?;
+        let data_length = Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + content.len();
+        Self::allocate_data_account(program_id, system_program, account_payer, data_account, kind.prefix(), req_id_data, bump_seed, data_length)?;
+        Self::write_proposal_data(data_account, &header, &content)
+    }
+
+    /// Size of a proposal account holding `content` after the header
+    pub fn proposal_account_size<Data: BorshSerialize>(content: &Data) -> Result<usize, ProgramError> {
+        let content_length = borsh::object_length(content).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Constants::SIZE_PROPOSAL_HEADER + Constants::SIZE_LENGTH + content_length)
+    }
+
+    fn allocate_data_account<'a>(
+        program_id: &Pubkey,
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account: &AccountInfo<'a>,
+        prefix: &[u8],
+        phrase: &[u8],
+        bump_seed: u8,
+        data_length: usize,
+    ) -> ProgramResult {
+        if !data_account.is_writable {
             Err(DataAccountError::PdaAccountNotWritable.into())
         } else if !account_payer.is_signer {
             Err(ProgramError::MissingRequiredSignature)
@@ -249,7 +676,7 @@ impl DataAccountUtils {
                 ],
                 &[&[prefix.as_ref(), phrase.as_ref(), &[bump_seed]]],
             )?;
-            Self::write_account_data(data_account, content)
+            Ok(())
         }
     }
 
@@ -257,27 +684,95 @@ impl DataAccountUtils {
         data_account: &AccountInfo,
         content: Data,
     ) -> ProgramResult {
-        let account_data = &mut data_account.data.borrow_mut()[..];
-        if account_data.len() < 4 {
-            return Err(ProgramError::InvalidAccountData);
-        }
         let mut buffer = Vec::new();
         content
             .serialize(&mut buffer)
             .map_err(|_| ProgramError::InvalidAccountData)?;
+        Self::write_length_prefixed(&mut data_account.data.borrow_mut()[..], &buffer)
+    }
+
+    /// Same as `write_account_data`, but first grows an account created for a shorter layout
+    /// when `content` does not fit it
+    pub fn write_account_data_growing<'a, Data: BorshSerialize>(
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account: &AccountInfo<'a>,
+        content: Data,
+    ) -> ProgramResult {
+        let content_length = borsh::object_length(&content).map_err(|_| ProgramError::InvalidAccountData)?;
+        Self::grow_data_account(system_program, account_payer, data_account, Constants::SIZE_LENGTH + content_length)?;
+        Self::write_account_data(data_account, content)
+    }
+
+    /// Grows `data_account` to `data_length` if it is shorter, with `account_payer` topping up
+    /// the rent for the extra bytes
+    pub fn grow_data_account<'a>(
+        system_program: &AccountInfo<'a>,
+        account_payer: &AccountInfo<'a>,
+        data_account: &AccountInfo<'a>,
+        data_length: usize,
+    ) -> ProgramResult {
+        if data_account.data_len() >= data_length {
+            return Ok(());
+        }
+        let required_lamports = Rent::get()?.minimum_balance(data_length);
+        let lamports_diff = required_lamports.saturating_sub(data_account.lamports());
+        if lamports_diff > 0 {
+            invoke(
+                &transfer(account_payer.key, data_account.key, lamports_diff),
+                &[account_payer.clone(), data_account.clone(), system_program.clone()],
+            )?;
+        }
+        data_account.resize(data_length)
+    }
+
+    fn write_length_prefixed(account_data: &mut [u8], buffer: &[u8]) -> ProgramResult {
+        if account_data.len() < 4 {
+            return Err(ProgramError::InvalidAccountData);
+        }
         if buffer.len() > account_data.len() - 4 {
             return Err(ProgramError::InvalidAccountData);
         }
         account_data[..4].copy_from_slice(&(buffer.len() as u32).to_le_bytes());
-        account_data[4..4 + buffer.len()].copy_from_slice(&buffer);
+        account_data[4..4 + buffer.len()].copy_from_slice(buffer);
         Ok(())
     }
 
+    /// Writes the fixed-layout header, then the borsh-encoded proposal struct with its length prefix
+    pub fn write_proposal_data(data_account: &AccountInfo, header: &ProposalHeader, content: &[u8]) -> ProgramResult {
+        let account_data = &mut data_account.data.borrow_mut()[..];
+        if account_data.len() < Constants::SIZE_PROPOSAL_HEADER {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let header = borsh::to_vec(header).map_err(|_| ProgramError::InvalidAccountData)?;
+        account_data[..Constants::SIZE_PROPOSAL_HEADER].copy_from_slice(&header);
+        Self::write_length_prefixed(&mut account_data[Constants::SIZE_PROPOSAL_HEADER..], content)
+    }
+
+    /// Replaces the proposal struct with `Constants::EXECUTED_PLACEHOLDER` and flags the header,
+    /// if any, as executed. The placeholder alone also fits proposals created without the header
+    pub fn mark_proposal_executed(data_account: &AccountInfo) -> ProgramResult {
+        let account_data = &mut data_account.data.borrow_mut()[..];
+        let offset = Self::proposal_body_offset(account_data);
+        if offset != 0 {
+            account_data[Constants::PROPOSAL_OFFSET_STATUS] = Constants::PROPOSAL_STATUS_EXECUTED;
+        }
+        Self::write_length_prefixed(&mut account_data[offset..], Constants::EXECUTED_PLACEHOLDER.as_ref())
+    }
+
+    /// Moves all lamports of `data_account` to `refund_account` and hands it back to the system
+    /// program. `data_account` must be the PDA of `prefix` and `phrase`. Accounts still holding a
+    /// `BasicStorage` or `ExecutorsInfo` are refused unless `force` is set, so a wrong PDA passed
+    /// to a close path cannot take the contract down
     pub fn close_account<'a>(
         program_id: &Pubkey,
         data_account: &AccountInfo<'a>,
         refund_account: &AccountInfo<'a>,
+        prefix: &[u8],
+        phrase: &[u8],
+        force: bool,
     ) -> ProgramResult {
+        Self::assert_account_match(program_id, data_account, prefix, phrase)?;
         Self::assert_owned_by_program(program_id, data_account)?;
         if !data_account.is_writable {
             return Err(DataAccountError::PdaAccountNotWritable.into());
@@ -285,12 +780,21 @@ impl DataAccountUtils {
         if !refund_account.is_writable {
             return Err(FreeTunnelError::RefundAccountNotWritable.into());
         }
+        if refund_account.key == data_account.key {
+            return Err(FreeTunnelError::InvalidRefundAccount.into());
+        }
+        if !force && Self::holds_critical_data(data_account) {
+            return Err(FreeTunnelError::CriticalAccountNotClosable.into());
+        }
 
         let refund_lamports = refund_account.lamports();
         let data_lamports = data_account.lamports();
         let new_refund_lamports = refund_lamports
             .checked_add(data_lamports)
             .ok_or(FreeTunnelError::ArithmeticOverflow)?;
+        if refund_lamports == 0 && refund_account.data_is_empty() {
+            Self::assert_refund_rent_exempt(new_refund_lamports, Rent::get()?.minimum_balance(0))?;
+        }
 
         **refund_account.lamports.borrow_mut() = new_refund_lamports;
         **data_account.lamports.borrow_mut() = 0;
@@ -299,4 +803,18 @@ impl DataAccountUtils {
         data_account.assign(&solana_sdk_ids::system_program::ID);
         Ok(())
     }
+
+    /// A fresh refund account below rent exemption would not survive the transaction
+    pub fn assert_refund_rent_exempt(new_refund_lamports: u64, minimum_balance: u64) -> ProgramResult {
+        if new_refund_lamports < minimum_balance {
+            msg!("RefundBelowRentExempt: refund_lamports={}, minimum_balance={}", new_refund_lamports, minimum_balance);
+            return Err(FreeTunnelError::RefundBelowRentExempt.into());
+        }
+        Ok(())
+    }
+
+    fn holds_critical_data(data_account: &AccountInfo) -> bool {
+        Self::read_account_data::<BasicStorage>(data_account).is_ok()
+            || Self::read_account_data::<ExecutorsInfo>(data_account).is_ok()
+    }
 }